@@ -0,0 +1,533 @@
+//! `bulk-send`: one escrow per row of a CSV, packed as many per transaction as fit.
+//!
+//! Every step is written to a journal before it is acted on, so a run that stops halfway can
+//! be started again with the same arguments. Rows whose transaction landed are never sent
+//! again, and a transaction that was in flight is looked up, or resent until its blockhash
+//! expires, before its rows are given to a new transaction. Each row keeps the secret it was
+//! first given, and a link only ever points at the escrow that actually landed.
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::thread::sleep;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::message::Message;
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Signature, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+
+use crate::escrow::next_nonce;
+use crate::link::{hex, Link, Secret};
+use crate::transfer::{Asset, Transfer};
+use crate::{error, now, App};
+
+/// How often an unconfirmed transaction is polled and resent.
+const RESEND_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(clap::Args)]
+pub struct BulkArgs {
+    /// CSV with an `amount` column and optional `mint`, `expires_in` and `email` columns.
+    /// An empty mint, or `SOL`, sends lamports.
+    input: PathBuf,
+    /// Where to write the claim links.
+    #[arg(long, short)]
+    output: PathBuf,
+    /// Progress journal; defaults to `<output>.journal`. Rerun with the same journal to resume.
+    #[arg(long)]
+    journal: Option<PathBuf>,
+    /// Seconds until the sender can take the funds back, for rows without `expires_in`.
+    #[arg(long, default_value_t = crate::DEFAULT_EXPIRES_IN)]
+    expires_in: i64,
+    #[arg(long, default_value = crate::link::DEFAULT_LINK_PREFIX)]
+    link_prefix: String,
+    /// Most escrows created by one transaction.
+    #[arg(long, default_value_t = 8)]
+    max_per_transaction: usize,
+    /// Times a transaction is rebuilt after its blockhash expires without it landing.
+    #[arg(long, default_value_t = 3)]
+    retries: u32,
+}
+
+#[derive(Deserialize)]
+struct Row {
+    amount: u64,
+    #[serde(default)]
+    mint: Option<String>,
+    #[serde(default)]
+    expires_in: Option<i64>,
+    #[serde(default)]
+    email: Option<String>,
+}
+
+#[derive(Serialize)]
+struct LinkRow<'a> {
+    email: Option<&'a str>,
+    amount: u64,
+    mint: String,
+    expiration_time: i64,
+    escrow: String,
+    link: String,
+    signature: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Entry {
+    /// First entry; ties the journal to one input file.
+    Input {
+        sha256: String,
+    },
+    /// The secret and expiration a row keeps for the whole run.
+    Plan {
+        row: usize,
+        secret: String,
+        expiration_time: i64,
+    },
+    /// A signed transaction, written before it is first sent.
+    Submit {
+        signature: String,
+        rows: Vec<usize>,
+        escrows: Vec<String>,
+        transaction: String,
+        last_valid_block_height: u64,
+    },
+    Confirm {
+        signature: String,
+    },
+    /// The transaction can no longer land; its rows go back to pending.
+    Abandon {
+        signature: String,
+    },
+}
+
+struct Plan {
+    secret: Secret,
+    expiration_time: i64,
+}
+
+#[derive(PartialEq)]
+enum Status {
+    Pending,
+    Confirmed,
+    Abandoned,
+}
+
+struct Submission {
+    signature: String,
+    rows: Vec<usize>,
+    escrows: Vec<Pubkey>,
+    transaction: Transaction,
+    last_valid_block_height: u64,
+    status: Status,
+}
+
+enum Outcome {
+    Landed,
+    Expired,
+    Failed(anyhow::Error),
+}
+
+/// Append-only JSON lines, synced after every write.
+struct Journal {
+    file: File,
+    plans: HashMap<usize, Plan>,
+    submissions: Vec<Submission>,
+    input: Option<String>,
+}
+
+impl Journal {
+    fn open(path: &Path) -> Result<Self> {
+        let mut journal = Self {
+            file: OpenOptions::new()
+                .create(true)
+                .read(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("could not open journal {}", path.display()))?,
+            plans: HashMap::new(),
+            submissions: Vec::new(),
+            input: None,
+        };
+        let mut contents = Vec::new();
+        journal.file.read_to_end(&mut contents)?;
+        let mut complete = 0;
+        for line in contents.split_inclusive(|byte| *byte == b'\n') {
+            // A crash can leave the last line half written; it is dropped.
+            let Some(json) = line.strip_suffix(b"\n") else {
+                break;
+            };
+            let Ok(entry) = serde_json::from_slice::<Entry>(json) else {
+                break;
+            };
+            journal.apply(entry)?;
+            complete += line.len() as u64;
+        }
+        journal.file.set_len(complete)?;
+        Ok(journal)
+    }
+
+    fn apply(&mut self, entry: Entry) -> Result<()> {
+        match entry {
+            Entry::Input { sha256 } => self.input = Some(sha256),
+            Entry::Plan {
+                row,
+                secret,
+                expiration_time,
+            } => {
+                self.plans.insert(
+                    row,
+                    Plan {
+                        secret: Secret::parse(&secret),
+                        expiration_time,
+                    },
+                );
+            }
+            Entry::Submit {
+                signature,
+                rows,
+                escrows,
+                transaction,
+                last_valid_block_height,
+            } => self.submissions.push(Submission {
+                signature,
+                rows,
+                escrows: escrows
+                    .iter()
+                    .map(|escrow| Pubkey::from_str(escrow))
+                    .collect::<Result<_, _>>()?,
+                transaction: bincode::deserialize(&STANDARD.decode(transaction)?)?,
+                last_valid_block_height,
+                status: Status::Pending,
+            }),
+            Entry::Confirm { signature } => self.set_status(&signature, Status::Confirmed),
+            Entry::Abandon { signature } => self.set_status(&signature, Status::Abandoned),
+        }
+        Ok(())
+    }
+
+    fn set_status(&mut self, signature: &str, status: Status) {
+        if let Some(submission) = self
+            .submissions
+            .iter_mut()
+            .find(|submission| submission.signature == signature)
+        {
+            submission.status = status;
+        }
+    }
+
+    fn append(&mut self, entries: Vec<Entry>) -> Result<()> {
+        let mut lines = Vec::new();
+        for entry in &entries {
+            serde_json::to_writer(&mut lines, entry)?;
+            lines.push(b'\n');
+        }
+        self.file.write_all(&lines)?;
+        self.file.sync_data()?;
+        for entry in entries {
+            self.apply(entry)?;
+        }
+        Ok(())
+    }
+
+    fn confirmed_rows(&self) -> HashSet<usize> {
+        self.submissions
+            .iter()
+            .filter(|submission| submission.status == Status::Confirmed)
+            .flat_map(|submission| submission.rows.iter().copied())
+            .collect()
+    }
+}
+
+impl App {
+    pub fn bulk_send(&self, args: &BulkArgs) -> Result<Value> {
+        let input = fs::read(&args.input)
+            .with_context(|| format!("could not read {}", args.input.display()))?;
+        let rows = csv::Reader::from_reader(input.as_slice())
+            .deserialize()
+            .collect::<Result<Vec<Row>, _>>()?;
+        let journal_path = args.journal.clone().unwrap_or_else(|| {
+            let mut path = args.output.clone().into_os_string();
+            path.push(".journal");
+            path.into()
+        });
+
+        let mut journal = Journal::open(&journal_path)?;
+        let sha256 = hex(&Sha256::digest(&input));
+        match &journal.input {
+            Some(journaled) if *journaled != sha256 => bail!(
+                "journal {} was written for a different input file",
+                journal_path.display()
+            ),
+            Some(_) => {}
+            None => journal.append(vec![Entry::Input { sha256 }])?,
+        }
+        let plans = (0..rows.len())
+            .filter(|row| !journal.plans.contains_key(row))
+            .map(|row| Entry::Plan {
+                row,
+                secret: Secret::generate().to_string(),
+                expiration_time: now() + rows[row].expires_in.unwrap_or(args.expires_in),
+            })
+            .collect();
+        journal.append(plans)?;
+        let transfers = self.transfers(&rows, &journal)?;
+
+        // Transactions a previous run sent but never saw land.
+        let in_flight = journal
+            .submissions
+            .iter()
+            .filter(|submission| submission.status == Status::Pending)
+            .map(|submission| {
+                (
+                    submission.signature.clone(),
+                    submission.transaction.clone(),
+                    submission.last_valid_block_height,
+                )
+            })
+            .collect::<Vec<_>>();
+        for (signature, transaction, last_valid_block_height) in in_flight {
+            let entry = match self.settle(&transaction, last_valid_block_height, true)? {
+                Outcome::Landed => Entry::Confirm { signature },
+                Outcome::Expired | Outcome::Failed(_) => Entry::Abandon { signature },
+            };
+            journal.append(vec![entry])?;
+        }
+
+        let sender = self.keypair.pubkey();
+        let mut sent = 0;
+        let mut attempts = 0;
+        loop {
+            let confirmed = journal.confirmed_rows();
+            let pending = (0..rows.len())
+                .filter(|row| !confirmed.contains(row))
+                .collect::<Vec<_>>();
+            if pending.is_empty() {
+                break;
+            }
+
+            let nonce = next_nonce(&self.client, &sender)?;
+            let (batch, escrows, mut transaction) = self.pack(
+                &pending,
+                &transfers,
+                &journal,
+                nonce,
+                args.max_per_transaction,
+            )?;
+            let (blockhash, last_valid_block_height) = self
+                .client
+                .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())?;
+            transaction.sign(&[&self.keypair], blockhash);
+            let signature = transaction.signatures[0].to_string();
+            journal.append(vec![Entry::Submit {
+                signature: signature.clone(),
+                rows: batch.clone(),
+                escrows: escrows.iter().map(Pubkey::to_string).collect(),
+                transaction: STANDARD.encode(bincode::serialize(&transaction)?),
+                last_valid_block_height,
+            }])?;
+
+            match self.settle(&transaction, last_valid_block_height, false)? {
+                Outcome::Landed => {
+                    journal.append(vec![Entry::Confirm { signature }])?;
+                    sent += batch.len();
+                    attempts = 0;
+                }
+                Outcome::Expired => {
+                    journal.append(vec![Entry::Abandon { signature }])?;
+                    attempts += 1;
+                    if attempts > args.retries {
+                        bail!(
+                            "rows {batch:?} did not land after {attempts} attempts; rerun to resume"
+                        );
+                    }
+                }
+                Outcome::Failed(error) => {
+                    journal.append(vec![Entry::Abandon { signature }])?;
+                    return Err(error.context(format!("rows {batch:?} were rejected")));
+                }
+            }
+        }
+
+        self.write_links(args, &rows, &transfers, &journal)?;
+        Ok(json!({
+            "rows": rows.len(),
+            "sent": sent,
+            "output": args.output.display().to_string(),
+            "journal": journal_path.display().to_string(),
+        }))
+    }
+
+    /// The escrow each row describes, with the expiration fixed by its plan.
+    fn transfers(&self, rows: &[Row], journal: &Journal) -> Result<Vec<Transfer>> {
+        let mut token_programs = HashMap::new();
+        rows.iter()
+            .enumerate()
+            .map(|(index, row)| {
+                let asset = match row.mint.as_deref() {
+                    None | Some("SOL") => Asset::Sol,
+                    Some(mint) => {
+                        let mint = Pubkey::from_str(mint)
+                            .map_err(|_| anyhow!("row {index}: invalid mint {mint}"))?;
+                        let token_program = match token_programs.get(&mint) {
+                            Some(token_program) => *token_program,
+                            None => {
+                                let token_program = self.token_program(&mint)?;
+                                token_programs.insert(mint, token_program);
+                                token_program
+                            }
+                        };
+                        Asset::Spl {
+                            mint,
+                            token_program,
+                        }
+                    }
+                };
+                Ok(Transfer {
+                    asset,
+                    amount: row.amount,
+                    expiration_time: journal.plans[&index].expiration_time,
+                    memo: None,
+                    receipt: false,
+                })
+            })
+            .collect()
+    }
+
+    /// An unsigned transaction creating escrows for as many leading `pending` rows as fit,
+    /// numbered from the sender's next `nonce`.
+    fn pack(
+        &self,
+        pending: &[usize],
+        transfers: &[Transfer],
+        journal: &Journal,
+        nonce: u64,
+        max: usize,
+    ) -> Result<(Vec<usize>, Vec<Pubkey>, Transaction)> {
+        let sender = self.keypair.pubkey();
+        let mut rows = Vec::new();
+        let mut escrows = Vec::new();
+        let mut instructions = Vec::new();
+        let mut packed = None;
+        for &row in pending.iter().take(max.max(1)) {
+            let (escrow, instruction) = transfers[row].instruction(
+                &sender,
+                nonce + rows.len() as u64,
+                &journal.plans[&row].secret,
+            );
+            instructions.push(instruction);
+            let transaction = Transaction::new_unsigned(Message::new(&instructions, Some(&sender)));
+            if bincode::serialized_size(&transaction)? > PACKET_DATA_SIZE as u64 {
+                break;
+            }
+            rows.push(row);
+            escrows.push(escrow);
+            packed = Some(transaction);
+        }
+        let transaction =
+            packed.ok_or_else(|| anyhow!("row {} does not fit in a transaction", pending[0]))?;
+        Ok((rows, escrows, transaction))
+    }
+
+    /// Waits until `transaction` lands or can no longer land, resending it meanwhile.
+    ///
+    /// A transaction the cluster may have seen is only given up on once its blockhash has
+    /// expired; one rejected by preflight on its first send was never forwarded.
+    fn settle(
+        &self,
+        transaction: &Transaction,
+        last_valid_block_height: u64,
+        mut forwarded: bool,
+    ) -> Result<Outcome> {
+        let signature: Signature = transaction.signatures[0];
+        let mut rejection = None;
+        loop {
+            if let Some(status) = self
+                .client
+                .get_signature_status_with_commitment_and_history(
+                    &signature,
+                    CommitmentConfig::confirmed(),
+                    true,
+                )?
+            {
+                return Ok(match status {
+                    Ok(()) => Outcome::Landed,
+                    Err(error) => {
+                        Outcome::Failed(anyhow!("transaction {signature} failed: {error}"))
+                    }
+                });
+            }
+            if self.client.get_block_height()? > last_valid_block_height {
+                return Ok(rejection.map_or(Outcome::Expired, Outcome::Failed));
+            }
+            if rejection.is_none() {
+                match self.client.send_transaction(transaction) {
+                    Ok(_) => forwarded = true,
+                    Err(error) => match error.get_transaction_error() {
+                        None
+                        | Some(TransactionError::AlreadyProcessed)
+                        | Some(TransactionError::BlockhashNotFound) => {}
+                        Some(_) if !forwarded => {
+                            return Ok(Outcome::Failed(error::explain(error)));
+                        }
+                        Some(_) => rejection = Some(error::explain(error)),
+                    },
+                }
+            }
+            sleep(RESEND_INTERVAL);
+        }
+    }
+
+    /// Writes the link sheet, in input order, replacing any earlier one.
+    fn write_links(
+        &self,
+        args: &BulkArgs,
+        rows: &[Row],
+        transfers: &[Transfer],
+        journal: &Journal,
+    ) -> Result<()> {
+        let mut landed = HashMap::new();
+        for submission in &journal.submissions {
+            if submission.status == Status::Confirmed {
+                for (row, escrow) in submission.rows.iter().zip(&submission.escrows) {
+                    landed.insert(*row, (*escrow, &submission.signature));
+                }
+            }
+        }
+
+        let mut partial = args.output.clone().into_os_string();
+        partial.push(".partial");
+        let mut writer = csv::Writer::from_path(&partial)?;
+        for (index, (row, transfer)) in rows.iter().zip(transfers).enumerate() {
+            let (escrow, signature) = landed[&index];
+            let link = Link {
+                escrow,
+                secret: journal.plans[&index].secret.clone(),
+            };
+            writer.serialize(LinkRow {
+                email: row.email.as_deref(),
+                amount: row.amount,
+                mint: match transfer.asset {
+                    Asset::Sol => "SOL".to_string(),
+                    Asset::Spl { mint, .. } => mint.to_string(),
+                },
+                expiration_time: transfer.expiration_time,
+                escrow: escrow.to_string(),
+                link: link.format(&args.link_prefix),
+                signature: signature.clone(),
+            })?;
+        }
+        writer.flush()?;
+        drop(writer);
+        fs::rename(&partial, &args.output)?;
+        Ok(())
+    }
+}
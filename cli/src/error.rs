@@ -0,0 +1,53 @@
+//! Readable messages for failed transactions.
+use anyhow::anyhow;
+use solana_rpc_client_api::client_error::{Error as ClientError, ErrorKind};
+use solana_rpc_client_api::request::{RpcError, RpcResponseErrorData};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
+
+/// First Anchor error code; `CustomError` variants are numbered from here.
+const ANCHOR_ERROR_OFFSET: u32 = 6000;
+
+/// Turns a failed send into the program's own error message when there is one, e.g.
+/// `InvalidSecret (6002): Invalid secret provided.`
+pub fn explain(error: ClientError) -> anyhow::Error {
+    if let ErrorKind::RpcError(RpcError::RpcResponseError {
+        data: RpcResponseErrorData::SendTransactionPreflightFailure(simulation),
+        ..
+    }) = error.kind()
+    {
+        if let Some(error) = simulation.logs.as_deref().and_then(explain_logs) {
+            return error;
+        }
+    }
+    if let Some(TransactionError::InstructionError(_, InstructionError::Custom(code))) =
+        error.get_transaction_error()
+    {
+        if code >= ANCHOR_ERROR_OFFSET {
+            return anyhow!("program error {code}");
+        }
+    }
+    anyhow!(error)
+}
+
+/// The program's own error message, when the logs of a failed transaction carry one.
+pub fn explain_logs(logs: &[String]) -> Option<anyhow::Error> {
+    logs.iter()
+        .find_map(|log| anchor_error(log))
+        .map(|message| anyhow!(message))
+}
+
+/// Parses the line Anchor logs for every error it returns:
+/// `AnchorError ... Error Code: <name>. Error Number: <n>. Error Message: <message>.`
+fn anchor_error(log: &str) -> Option<String> {
+    let rest = log.split_once("AnchorError")?.1;
+    let name = rest.split_once("Error Code: ")?.1.split_once('.')?.0;
+    let number = rest.split_once("Error Number: ")?.1.split_once('.')?.0;
+    let message = rest.split_once("Error Message: ")?.1;
+    let account = rest
+        .split_once("caused by account: ")
+        .and_then(|(_, account)| account.split_once('.'))
+        .map(|(account, _)| format!(" (account: {account})"))
+        .unwrap_or_default();
+    Some(format!("{name} ({number}): {message}{account}"))
+}
@@ -0,0 +1,184 @@
+//! Program addresses and decoded escrow accounts.
+use anchor_lang::{AccountDeserialize, Discriminator};
+use anyhow::{anyhow, bail, Result};
+use instant_send_program::{
+    EscrowAccount, EscrowMemo, EscrowSOLAccount, RentPolicy, SenderNonce, SEED_ESCROW_MEMO,
+    SEED_RECEIPT_MINT, SEED_SENDER_NONCE, SEED_SENDER_REGISTRY,
+};
+use serde_json::{json, Value};
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+
+pub fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &instant_send_program::ID).0
+}
+
+pub fn sender_nonce(sender: &Pubkey) -> Pubkey {
+    pda(&[SEED_SENDER_NONCE, sender.as_ref()])
+}
+
+pub fn sender_registry(sender: &Pubkey) -> Pubkey {
+    pda(&[SEED_SENDER_REGISTRY, sender.as_ref()])
+}
+
+pub fn escrow_memo(escrow: &Pubkey) -> Pubkey {
+    pda(&[SEED_ESCROW_MEMO, escrow.as_ref()])
+}
+
+pub fn receipt_mint(escrow: &Pubkey) -> Pubkey {
+    pda(&[SEED_RECEIPT_MINT, escrow.as_ref()])
+}
+
+/// The token account holding the receipt of `escrow`, wherever it was transferred to, or
+/// `None` once its holder burned it.
+pub fn receipt_account(client: &RpcClient, escrow: &Pubkey) -> Result<Option<Pubkey>> {
+    client
+        .get_token_largest_accounts(&receipt_mint(escrow))?
+        .into_iter()
+        .find(|balance| balance.amount.amount == "1")
+        .map(|balance| {
+            balance
+                .address
+                .parse()
+                .map_err(|_| anyhow!("invalid receipt account address"))
+        })
+        .transpose()
+}
+
+/// Nonce the sender's next escrow is derived from; 0 before their first escrow.
+pub fn next_nonce(client: &RpcClient, sender: &Pubkey) -> Result<u64> {
+    match fetch(client, &sender_nonce(sender))? {
+        Some(account) => Ok(SenderNonce::try_deserialize(&mut account.data.as_slice())?.next_nonce),
+        None => Ok(0),
+    }
+}
+
+pub fn fetch(client: &RpcClient, address: &Pubkey) -> Result<Option<Account>> {
+    Ok(client
+        .get_account_with_commitment(address, client.commitment())?
+        .value)
+}
+
+pub enum Escrow {
+    Spl(EscrowAccount),
+    Sol(EscrowSOLAccount),
+}
+
+impl Escrow {
+    pub fn decode(account: &Account) -> Result<Self> {
+        if account.owner != instant_send_program::ID {
+            bail!("the account is not owned by the instant-send program");
+        }
+        let mut data = account.data.as_slice();
+        match account.data.get(..8) {
+            Some(discriminator) if discriminator == EscrowAccount::DISCRIMINATOR => {
+                Ok(Escrow::Spl(EscrowAccount::try_deserialize(&mut data)?))
+            }
+            Some(discriminator) if discriminator == EscrowSOLAccount::DISCRIMINATOR => {
+                Ok(Escrow::Sol(EscrowSOLAccount::try_deserialize(&mut data)?))
+            }
+            _ => bail!("the account is not an escrow"),
+        }
+    }
+
+    pub fn load(client: &RpcClient, address: &Pubkey) -> Result<Self> {
+        let account = fetch(client, address)?
+            .ok_or_else(|| anyhow!("escrow {address} does not exist; it may have been closed"))?;
+        Self::decode(&account)
+    }
+
+    pub fn sender(&self) -> Pubkey {
+        match self {
+            Escrow::Spl(escrow) => escrow.sender,
+            Escrow::Sol(escrow) => escrow.sender,
+        }
+    }
+
+    pub fn rent_policy(&self) -> RentPolicy {
+        match self {
+            Escrow::Spl(escrow) => escrow.rent_policy,
+            Escrow::Sol(escrow) => escrow.rent_policy,
+        }
+    }
+
+    pub fn has_memo(&self) -> bool {
+        match self {
+            Escrow::Spl(escrow) => escrow.has_memo,
+            Escrow::Sol(escrow) => escrow.has_memo,
+        }
+    }
+
+    pub fn has_receipt(&self) -> bool {
+        match self {
+            Escrow::Spl(escrow) => escrow.has_receipt,
+            Escrow::Sol(escrow) => escrow.has_receipt,
+        }
+    }
+
+    /// Everything worth showing about the escrow at `address`, including its memo.
+    pub fn to_json(&self, client: &RpcClient, address: &Pubkey, now: i64) -> Result<Value> {
+        let memo = match self.has_memo() {
+            true => fetch(client, &escrow_memo(address))?
+                .map(|account| EscrowMemo::try_deserialize(&mut account.data.as_slice()))
+                .transpose()?
+                .map(|memo| memo.memo),
+            false => None,
+        };
+        // The two layouts share every field but the mint and the risk flags.
+        macro_rules! common {
+            ($escrow:expr, $asset:expr, $mint:expr, $risk_flags:expr) => {
+                json!({
+                    "address": address.to_string(),
+                    "asset": $asset,
+                    "sender": $escrow.sender.to_string(),
+                    "mint": $mint,
+                    "amount": $escrow.amount,
+                    "withdrawn": $escrow.withdrawn,
+                    "remaining": $escrow.remaining(),
+                    "expiration_time": $escrow.expiration_time,
+                    "expired": now > $escrow.expiration_time,
+                    "is_redeemed": $escrow.is_redeemed,
+                    "version": $escrow.version,
+                    "nonce": $escrow.nonce,
+                    "rent_policy": rent_policy_name($escrow.rent_policy),
+                    "pin_required": $escrow.pin_hash != [0u8; 32],
+                    "condition_program": $escrow
+                        .condition
+                        .is_set()
+                        .then(|| $escrow.condition.program_id.to_string()),
+                    "approvals": $escrow.approvers.count.gt(&0).then(|| json!({
+                        "threshold": $escrow.approvers.threshold,
+                        "approvers": $escrow.approvers.approvers[..$escrow.approvers.count as usize]
+                            .iter()
+                            .map(|approver| approver.to_string())
+                            .collect::<Vec<_>>(),
+                        "approved": $escrow.approvers.approved.count_ones(),
+                    })),
+                    "streaming": $escrow.vesting.is_set(),
+                    "allowance": $escrow.allowance.is_set(),
+                    "receipt": $escrow.has_receipt,
+                    "risk_flags": $risk_flags,
+                    "memo": memo,
+                })
+            };
+        }
+        Ok(match self {
+            Escrow::Spl(escrow) => common!(
+                escrow,
+                "spl",
+                escrow.token_mint.to_string(),
+                Some(escrow.risk_flags)
+            ),
+            Escrow::Sol(escrow) => common!(escrow, "sol", None::<String>, None::<u16>),
+        })
+    }
+}
+
+fn rent_policy_name(policy: RentPolicy) -> &'static str {
+    match policy {
+        RentPolicy::Sender => "sender",
+        RentPolicy::Recipient => "recipient",
+        RentPolicy::Redeemer => "redeemer",
+    }
+}
@@ -0,0 +1,109 @@
+//! Claim links: `<prefix><escrow>#<secret>`, the secret base58 encoded.
+//!
+//! The secret travels in the URL fragment, which browsers never send to the server
+//! hosting the claim page.
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use solana_sdk::bs58;
+use solana_sdk::pubkey::Pubkey;
+
+pub const DEFAULT_LINK_PREFIX: &str = "instant-send://claim/";
+
+pub struct Link {
+    pub escrow: Pubkey,
+    pub secret: Secret,
+}
+
+impl Link {
+    pub fn format(&self, prefix: &str) -> String {
+        format!("{prefix}{}#{}", self.escrow, self.secret)
+    }
+
+    /// Accepts a full link with any prefix, or just `<escrow>#<secret>`.
+    pub fn parse(link: &str) -> Result<Self> {
+        let (address, secret) = link
+            .rsplit_once('#')
+            .ok_or_else(|| anyhow!("the link has no secret after '#'"))?;
+        let escrow = address.rsplit('/').next().unwrap_or(address);
+        Ok(Self {
+            escrow: Pubkey::from_str(escrow)
+                .map_err(|_| anyhow!("the link has no valid escrow address"))?,
+            secret: Secret::parse(secret),
+        })
+    }
+}
+
+/// What opens an escrow: the program stores `sha256` of its bytes.
+///
+/// Links carry 32 random bytes, base58 encoded, which are redeemed through the `_v2`
+/// instructions. Any other fragment is a text secret from an older client and goes through
+/// the legacy `String` instructions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Secret {
+    Bytes([u8; 32]),
+    Text(String),
+}
+
+impl Secret {
+    pub fn generate() -> Self {
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        Secret::Bytes(bytes)
+    }
+
+    pub fn parse(encoded: &str) -> Self {
+        match bs58::decode(encoded).into_vec().map(<[u8; 32]>::try_from) {
+            Ok(Ok(bytes)) => Secret::Bytes(bytes),
+            _ => Secret::Text(encoded.to_string()),
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Secret::Bytes(bytes) => bytes,
+            Secret::Text(text) => text.as_bytes(),
+        }
+    }
+
+    /// The commitment stored on the escrow.
+    pub fn hash(&self) -> [u8; 32] {
+        Sha256::digest(self.as_bytes()).into()
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Secret::Bytes(bytes) => f.write_str(&bs58::encode(bytes).into_string()),
+            Secret::Text(text) => f.write_str(text),
+        }
+    }
+}
+
+/// An escrow address or a claim link, as accepted by `inspect` and `refund`.
+pub fn parse_target(target: &str) -> Result<(Pubkey, Option<Secret>)> {
+    match Pubkey::from_str(target) {
+        Ok(escrow) => Ok((escrow, None)),
+        Err(_) => {
+            let link = Link::parse(target)?;
+            Ok((link.escrow, Some(link.secret)))
+        }
+    }
+}
+
+/// The salt delivered with a PIN, base58 encoded.
+pub fn parse_pin_salt(encoded: &str) -> Result<[u8; 16]> {
+    bs58::decode(encoded)
+        .into_vec()
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("the PIN salt is not 16 base58-encoded bytes"))
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
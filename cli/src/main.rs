@@ -0,0 +1,581 @@
+//! `instant-send`: create, redeem, refund and inspect instant-send links from a terminal.
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand};
+use instant_send_program::secret::PinProof;
+use instant_send_program::{accounts, instruction, SenderRegistry, SEED_CONFIG};
+use serde_json::{json, Value};
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use solana_sdk::{system_program, sysvar};
+
+mod bulk;
+mod error;
+mod escrow;
+mod link;
+mod transfer;
+mod view;
+
+use bulk::BulkArgs;
+use escrow::{
+    escrow_memo, fetch, next_nonce, pda, receipt_account, receipt_mint, sender_registry, Escrow,
+};
+use link::{parse_pin_salt, parse_target, Link, Secret, DEFAULT_LINK_PREFIX};
+use transfer::{Asset, Transfer};
+
+const DEFAULT_EXPIRES_IN: i64 = 7 * 24 * 60 * 60;
+
+#[derive(Parser)]
+#[command(name = "instant-send", version, about)]
+struct Cli {
+    /// Keypair that signs and pays for transactions.
+    #[arg(long, short = 'k', default_value_t = default_keypair())]
+    keypair: String,
+    /// RPC endpoint of the cluster.
+    #[arg(long, short = 'u', default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// Prints JSON instead of text.
+    #[arg(long)]
+    json: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Escrows SOL and prints the claim link.
+    SendSol {
+        /// Amount in lamports.
+        amount: u64,
+        #[command(flatten)]
+        send: SendArgs,
+    },
+    /// Escrows SPL tokens and prints the claim link.
+    SendSpl {
+        mint: Pubkey,
+        /// Amount in base units of the mint.
+        amount: u64,
+        #[command(flatten)]
+        send: SendArgs,
+    },
+    /// Escrows every row of a CSV and writes a CSV of claim links. Resumable.
+    BulkSend(BulkArgs),
+    /// Claims the escrow behind a link.
+    Redeem {
+        link: String,
+        /// Wallet receiving the funds; defaults to the keypair.
+        #[arg(long)]
+        recipient: Option<Pubkey>,
+        /// Second factor, for escrows that require a PIN.
+        #[arg(long, requires = "pin_salt")]
+        pin: Option<String>,
+        /// Salt delivered with the PIN.
+        #[arg(long, requires = "pin", value_parser = parse_pin_salt)]
+        pin_salt: Option<[u8; 16]>,
+    },
+    /// Returns an expired escrow to its sender, or to the keypair if it holds the receipt.
+    Refund {
+        /// Claim link, or escrow address together with `--secret`; escrows with a receipt
+        /// need no secret.
+        target: String,
+        #[arg(long)]
+        secret: Option<String>,
+    },
+    /// Shows an escrow's state.
+    Inspect {
+        /// Escrow address or claim link.
+        target: String,
+    },
+    /// Shows what the program itself reports for an escrow, as a claim page would.
+    Status {
+        /// Escrow address or claim link; a link's secret is checked too.
+        target: String,
+    },
+    /// Lists a sender's open escrows.
+    List {
+        /// Defaults to the keypair.
+        #[arg(long)]
+        sender: Option<Pubkey>,
+    },
+}
+
+#[derive(clap::Args, Clone)]
+struct SendArgs {
+    /// Seconds until the sender can take the funds back.
+    #[arg(long, default_value_t = DEFAULT_EXPIRES_IN)]
+    expires_in: i64,
+    /// Note shown to the recipient.
+    #[arg(long)]
+    memo: Option<String>,
+    /// Mints a receipt token; whoever holds it, rather than the sender, refunds the escrow.
+    #[arg(long)]
+    receipt: bool,
+    /// Prefix of the printed link; the escrow address and `#<secret>` are appended.
+    #[arg(long, default_value = DEFAULT_LINK_PREFIX)]
+    link_prefix: String,
+}
+
+fn default_keypair() -> String {
+    let home = std::env::var("HOME").unwrap_or_default();
+    PathBuf::from(home)
+        .join(".config/solana/id.json")
+        .display()
+        .to_string()
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
+
+struct App {
+    client: RpcClient,
+    keypair: Keypair,
+}
+
+impl App {
+    fn send(&self, instruction: Instruction) -> Result<String> {
+        let blockhash = self.client.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&self.keypair.pubkey()),
+            &[&self.keypair],
+            blockhash,
+        );
+        let signature = self
+            .client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(error::explain)?;
+        Ok(signature.to_string())
+    }
+
+    /// Owner of `mint`, i.e. the token program (Token or Token-2022) it belongs to.
+    fn token_program(&self, mint: &Pubkey) -> Result<Pubkey> {
+        Ok(fetch(&self.client, mint)?
+            .ok_or_else(|| anyhow!("mint {mint} does not exist"))?
+            .owner)
+    }
+
+    fn send_escrow(&self, mint: Option<Pubkey>, amount: u64, args: SendArgs) -> Result<Value> {
+        let asset = match mint {
+            Some(mint) => Asset::Spl {
+                mint,
+                token_program: self.token_program(&mint)?,
+            },
+            None => Asset::Sol,
+        };
+        let transfer = Transfer {
+            asset,
+            amount,
+            expiration_time: now() + args.expires_in,
+            memo: args.memo,
+            receipt: args.receipt,
+        };
+        let sender = self.keypair.pubkey();
+        let secret = Secret::generate();
+        let (escrow, instruction) =
+            transfer.instruction(&sender, next_nonce(&self.client, &sender)?, &secret);
+        let signature = self.send(instruction)?;
+        let link = Link { escrow, secret };
+        Ok(json!({
+            "link": link.format(&args.link_prefix),
+            "escrow": escrow.to_string(),
+            "expiration_time": transfer.expiration_time,
+            "signature": signature,
+        }))
+    }
+
+    fn redeem(
+        &self,
+        link: &str,
+        recipient: Option<Pubkey>,
+        pin: Option<PinProof>,
+    ) -> Result<Value> {
+        let link = Link::parse(link)?;
+        let escrow = Escrow::load(&self.client, &link.escrow)?;
+        let signer = self.keypair.pubkey();
+        let recipient = recipient.unwrap_or(signer);
+        let sender = escrow.sender();
+        let rent_receiver = escrow.rent_policy().receiver(&sender, &recipient, &signer);
+        let (escrow_memo, memo_program) = match escrow.has_memo() {
+            true => (Some(escrow_memo(&link.escrow)), Some(anchor_spl::memo::ID)),
+            false => (None, None),
+        };
+        // The receipt is burned out of whichever account holds it, if it wasn't already.
+        let (receipt_mint, receipt_account, receipt_token_program) = match escrow.has_receipt() {
+            true => (
+                Some(receipt_mint(&link.escrow)),
+                receipt_account(&self.client, &link.escrow)?,
+                Some(anchor_spl::token_2022::ID),
+            ),
+            false => (None, None, None),
+        };
+
+        // Text secrets use the legacy instructions unless there is a PIN, which only `_v2` takes.
+        let instruction = match &escrow {
+            Escrow::Spl(account) => {
+                let mint = account.token_mint;
+                let token_program = self.token_program(&mint)?;
+                Instruction {
+                    program_id: instant_send_program::ID,
+                    accounts: accounts::RedeemFundsSPL {
+                        signer,
+                        recipient,
+                        escrow_account: link.escrow,
+                        escrow_token_account: get_associated_token_address_with_program_id(
+                            &link.escrow,
+                            &mint,
+                            &token_program,
+                        ),
+                        recipient_token_account: get_associated_token_address_with_program_id(
+                            &recipient,
+                            &mint,
+                            &token_program,
+                        ),
+                        token_mint: mint,
+                        sender,
+                        rent_receiver,
+                        sender_registry: sender_registry(&sender),
+                        condition_program: None,
+                        condition_config: None,
+                        escrow_memo,
+                        memo_program,
+                        receipt_mint,
+                        receipt_account,
+                        receipt_token_program,
+                        config: pda(&[SEED_CONFIG]),
+                        token_program,
+                        associated_token_program: anchor_spl::associated_token::ID,
+                        system_program: system_program::ID,
+                        rent: sysvar::rent::ID,
+                    }
+                    .to_account_metas(None),
+                    data: match link.secret {
+                        Secret::Text(secret) if pin.is_none() => {
+                            instruction::RedeemFundsSpl { secret }.data()
+                        }
+                        secret => instruction::RedeemFundsSplV2 {
+                            secret: secret.as_bytes().to_vec(),
+                            pin,
+                        }
+                        .data(),
+                    },
+                }
+            }
+            Escrow::Sol(_) => Instruction {
+                program_id: instant_send_program::ID,
+                accounts: accounts::RedeemFundsSOL {
+                    signer,
+                    recipient,
+                    escrow_account: link.escrow,
+                    sender,
+                    rent_receiver,
+                    sender_registry: sender_registry(&sender),
+                    condition_program: None,
+                    condition_config: None,
+                    escrow_memo,
+                    memo_program,
+                    receipt_mint,
+                    receipt_account,
+                    receipt_token_program,
+                    config: pda(&[SEED_CONFIG]),
+                    system_program: system_program::ID,
+                }
+                .to_account_metas(None),
+                data: match link.secret {
+                    Secret::Text(secret) if pin.is_none() => {
+                        instruction::RedeemFundsSol { secret }.data()
+                    }
+                    secret => instruction::RedeemFundsSolV2 {
+                        secret: secret.as_bytes().to_vec(),
+                        pin,
+                    }
+                    .data(),
+                },
+            },
+        };
+        let signature = self.send(instruction)?;
+        Ok(json!({
+            "escrow": link.escrow.to_string(),
+            "recipient": recipient.to_string(),
+            "signature": signature,
+        }))
+    }
+
+    fn refund(&self, target: &str, secret: Option<String>) -> Result<Value> {
+        let (address, link_secret) = parse_target(target)?;
+        let escrow = Escrow::load(&self.client, &address)?;
+        if escrow.has_receipt() {
+            return self.refund_with_receipt(&address, &escrow);
+        }
+        let secret = secret
+            .map(|secret| Secret::parse(&secret))
+            .or(link_secret)
+            .ok_or_else(|| anyhow!("refunds need the secret: pass a link or --secret"))?;
+        let signer = self.keypair.pubkey();
+        let sender = escrow.sender();
+
+        let instruction = match &escrow {
+            Escrow::Spl(account) => {
+                let mint = account.token_mint;
+                let token_program = self.token_program(&mint)?;
+                Instruction {
+                    program_id: instant_send_program::ID,
+                    accounts: accounts::RefundFundsSPL {
+                        signer,
+                        escrow_account: address,
+                        escrow_token_account: get_associated_token_address_with_program_id(
+                            &address,
+                            &mint,
+                            &token_program,
+                        ),
+                        sender,
+                        sender_token_account: get_associated_token_address_with_program_id(
+                            &sender,
+                            &mint,
+                            &token_program,
+                        ),
+                        token_mint: mint,
+                        sender_registry: sender_registry(&sender),
+                        token_program,
+                        system_program: system_program::ID,
+                        associated_token_program: anchor_spl::associated_token::ID,
+                        rent: sysvar::rent::ID,
+                    }
+                    .to_account_metas(None),
+                    data: match secret {
+                        Secret::Bytes(secret) => instruction::RefundFundsSplV2 {
+                            secret: secret.to_vec(),
+                        }
+                        .data(),
+                        Secret::Text(secret) => instruction::RefundFundsSpl { secret }.data(),
+                    },
+                }
+            }
+            Escrow::Sol(_) => Instruction {
+                program_id: instant_send_program::ID,
+                accounts: accounts::RefundFundsSOL {
+                    signer,
+                    escrow_account: address,
+                    sender,
+                    sender_registry: sender_registry(&sender),
+                    system_program: system_program::ID,
+                }
+                .to_account_metas(None),
+                data: match secret {
+                    Secret::Bytes(secret) => instruction::RefundFundsSolV2 {
+                        secret: secret.to_vec(),
+                    }
+                    .data(),
+                    Secret::Text(secret) => instruction::RefundFundsSol { secret }.data(),
+                },
+            },
+        };
+        let signature = self.send(instruction)?;
+        Ok(json!({
+            "escrow": address.to_string(),
+            "sender": sender.to_string(),
+            "signature": signature,
+        }))
+    }
+
+    /// Refunds an escrow with a receipt to the keypair, which must hold the receipt, or be the
+    /// sender once the receipt was burned.
+    fn refund_with_receipt(&self, address: &Pubkey, escrow: &Escrow) -> Result<Value> {
+        let holder = self.keypair.pubkey();
+        let sender = escrow.sender();
+        let receipt_mint = receipt_mint(address);
+        let receipt_account = receipt_account(&self.client, address)?;
+        let instruction = match escrow {
+            Escrow::Spl(account) => {
+                let mint = account.token_mint;
+                let token_program = self.token_program(&mint)?;
+                Instruction {
+                    program_id: instant_send_program::ID,
+                    accounts: accounts::RefundWithReceiptSPL {
+                        holder,
+                        escrow_account: *address,
+                        escrow_token_account: get_associated_token_address_with_program_id(
+                            address,
+                            &mint,
+                            &token_program,
+                        ),
+                        holder_token_account: get_associated_token_address_with_program_id(
+                            &holder,
+                            &mint,
+                            &token_program,
+                        ),
+                        token_mint: mint,
+                        receipt_mint,
+                        receipt_account,
+                        sender_registry: sender_registry(&sender),
+                        token_program,
+                        receipt_token_program: anchor_spl::token_2022::ID,
+                        associated_token_program: anchor_spl::associated_token::ID,
+                        system_program: system_program::ID,
+                    }
+                    .to_account_metas(None),
+                    data: instruction::RefundWithReceiptSpl {}.data(),
+                }
+            }
+            Escrow::Sol(_) => Instruction {
+                program_id: instant_send_program::ID,
+                accounts: accounts::RefundWithReceiptSOL {
+                    holder,
+                    escrow_account: *address,
+                    receipt_mint,
+                    receipt_account,
+                    sender_registry: sender_registry(&sender),
+                    receipt_token_program: anchor_spl::token_2022::ID,
+                }
+                .to_account_metas(None),
+                data: instruction::RefundWithReceiptSol {}.data(),
+            },
+        };
+        let signature = self.send(instruction)?;
+        Ok(json!({
+            "escrow": address.to_string(),
+            "holder": holder.to_string(),
+            "signature": signature,
+        }))
+    }
+
+    fn inspect(&self, target: &str) -> Result<Value> {
+        let (address, _) = parse_target(target)?;
+        Escrow::load(&self.client, &address)?.to_json(&self.client, &address, now())
+    }
+
+    fn status(&self, target: &str) -> Result<Value> {
+        let (address, secret) = parse_target(target)?;
+        let payer = self.keypair.pubkey();
+        let status = view::escrow_status(&self.client, &payer, &address)?;
+        let secret_valid = secret
+            .map(|secret| view::check_secret(&self.client, &payer, &address, secret.as_bytes()))
+            .transpose()?;
+        Ok(json!({
+            "escrow": address.to_string(),
+            "sender": status.sender.to_string(),
+            "mint": status.mint.to_string(),
+            "amount": status.amount,
+            "remaining": status.remaining,
+            "expiration_time": status.expiration_time,
+            "expires_in": status.expires_in,
+            "redeemed": status.is_redeemed,
+            "pin_required": status.pin_required,
+            "has_condition": status.has_condition,
+            "approvals_met": status.approvals_met,
+            "stream": status.is_stream,
+            "allowance": status.is_allowance,
+            "has_memo": status.has_memo,
+            "has_receipt": status.has_receipt,
+            "risk_flags": status.risk_flags,
+            "version": status.version,
+            "secret_valid": secret_valid,
+        }))
+    }
+
+    fn list(&self, sender: Option<Pubkey>) -> Result<Value> {
+        let sender = sender.unwrap_or_else(|| self.keypair.pubkey());
+        let escrows = match fetch(&self.client, &sender_registry(&sender))? {
+            Some(account) => SenderRegistry::try_deserialize(&mut account.data.as_slice())?.escrows,
+            None => Vec::new(),
+        };
+        let now = now();
+        let accounts = self.client.get_multiple_accounts(&escrows)?;
+        let rows = escrows
+            .iter()
+            .zip(accounts)
+            .filter_map(|(address, account)| Some((address, Escrow::decode(&account?).ok()?)))
+            .map(|(address, escrow)| escrow.to_json(&self.client, address, now))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Value::Array(rows))
+    }
+}
+
+fn print(value: &Value, as_json: bool) {
+    if as_json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(value).unwrap_or_default()
+        );
+        return;
+    }
+    match value {
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    println!();
+                }
+                print(item, false);
+            }
+        }
+        Value::Object(fields) => {
+            let width = fields.keys().map(String::len).max().unwrap_or_default();
+            for (key, field) in fields {
+                let field = match field {
+                    Value::String(text) => text.clone(),
+                    Value::Null => "-".to_string(),
+                    other => other.to_string(),
+                };
+                println!("{key:width$}  {field}");
+            }
+        }
+        other => println!("{other}"),
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if let Err(error) = run(&cli) {
+        if cli.json {
+            println!("{}", json!({ "error": error.to_string() }));
+        } else {
+            eprintln!("error: {error}");
+        }
+        std::process::exit(1);
+    }
+}
+
+fn run(cli: &Cli) -> Result<()> {
+    let keypair = read_keypair_file(&cli.keypair)
+        .map_err(|error| anyhow!("could not read keypair {}: {error}", cli.keypair))?;
+    let app = App {
+        client: RpcClient::new_with_commitment(cli.url.clone(), CommitmentConfig::confirmed()),
+        keypair,
+    };
+
+    let output = match &cli.command {
+        Command::SendSol { amount, send } => app.send_escrow(None, *amount, send.clone())?,
+        Command::SendSpl { mint, amount, send } => {
+            app.send_escrow(Some(*mint), *amount, send.clone())?
+        }
+        Command::BulkSend(args) => app.bulk_send(args)?,
+        Command::Redeem {
+            link,
+            recipient,
+            pin,
+            pin_salt,
+        } => {
+            let pin = pin
+                .clone()
+                .zip(*pin_salt)
+                .map(|(pin, salt)| PinProof { salt, pin });
+            app.redeem(link, *recipient, pin)?
+        }
+        Command::Refund { target, secret } => app.refund(target, secret.clone())?,
+        Command::Inspect { target } => app.inspect(target)?,
+        Command::Status { target } => app.status(target)?,
+        Command::List { sender } => app.list(*sender)?,
+    };
+    print(&output, cli.json);
+    Ok(())
+}
@@ -0,0 +1,150 @@
+//! `initialize_transfer_*` instructions.
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::token_2022;
+use instant_send_program::instructions::initialize_transfer::EscrowOptions;
+use instant_send_program::{
+    accounts, instruction, MintListPage, RentPolicy, SEED_CONFIG, SEED_ESCROW_SOL, SEED_ESCROW_SPL,
+    SEED_MINT_LIMITS, SEED_MINT_LIST, SEED_SENDER_USAGE,
+};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::{system_program, sysvar};
+
+use crate::escrow::{escrow_memo, pda, receipt_mint, sender_nonce, sender_registry};
+use crate::link::Secret;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Asset {
+    Sol,
+    Spl { mint: Pubkey, token_program: Pubkey },
+}
+
+/// One escrow to create.
+#[derive(Clone, Debug)]
+pub struct Transfer {
+    pub asset: Asset,
+    pub amount: u64,
+    pub expiration_time: i64,
+    pub memo: Option<String>,
+    /// Mints the sender a receipt token; whoever holds it refunds the escrow.
+    pub receipt: bool,
+}
+
+impl Transfer {
+    /// The escrow the sender's `nonce` derives and the instruction creating it, locked to
+    /// `secret`.
+    pub fn instruction(
+        &self,
+        sender: &Pubkey,
+        nonce: u64,
+        secret: &Secret,
+    ) -> (Pubkey, Instruction) {
+        let seed = match self.asset {
+            Asset::Sol => SEED_ESCROW_SOL,
+            Asset::Spl { .. } => SEED_ESCROW_SPL,
+        };
+        let escrow = pda(&[seed, sender.as_ref(), &nonce.to_le_bytes()]);
+        let options = EscrowOptions {
+            rent_policy: RentPolicy::Sender,
+            pin_hash: None,
+            condition: None,
+            approval: None,
+            vesting: None,
+            allowance: None,
+            memo: self.memo.clone(),
+            receipt: self.receipt,
+        };
+        let memo_account = self.memo.as_ref().map(|_| escrow_memo(&escrow));
+        let receipt_mint = self.receipt.then(|| receipt_mint(&escrow));
+        let receipt_account = receipt_mint.map(|mint| {
+            get_associated_token_address_with_program_id(sender, &mint, &token_2022::ID)
+        });
+        let receipt_token_program = self.receipt.then_some(token_2022::ID);
+        let config = pda(&[SEED_CONFIG]);
+        let hash_of_secret = secret.hash();
+
+        let (accounts, data) = match self.asset {
+            Asset::Spl {
+                mint,
+                token_program,
+            } => (
+                accounts::InitializeTransferSPL {
+                    sender: *sender,
+                    sender_nonce: sender_nonce(sender),
+                    sender_registry: sender_registry(sender),
+                    escrow_account: escrow,
+                    escrow_memo: memo_account,
+                    escrow_token_account: get_associated_token_address_with_program_id(
+                        &escrow,
+                        &mint,
+                        &token_program,
+                    ),
+                    sender_token_account: get_associated_token_address_with_program_id(
+                        sender,
+                        &mint,
+                        &token_program,
+                    ),
+                    token_mint: mint,
+                    config,
+                    mint_list_page: pda(&[SEED_MINT_LIST, &MintListPage::bucket(&mint)]),
+                    mint_limits: pda(&[SEED_MINT_LIMITS, mint.as_ref()]),
+                    sender_usage: pda(&[SEED_SENDER_USAGE, sender.as_ref(), mint.as_ref()]),
+                    system_program: system_program::ID,
+                    token_program,
+                    associated_token_program: anchor_spl::associated_token::ID,
+                    rent: sysvar::rent::ID,
+                    receipt_mint,
+                    receipt_account,
+                    receipt_token_program,
+                }
+                .to_account_metas(None),
+                instruction::InitializeTransferSpl {
+                    amount: self.amount,
+                    expiration_time: self.expiration_time,
+                    hash_of_secret,
+                    options,
+                }
+                .data(),
+            ),
+            Asset::Sol => (
+                accounts::InitializeTransferSOL {
+                    sender: *sender,
+                    sender_nonce: sender_nonce(sender),
+                    sender_registry: sender_registry(sender),
+                    escrow_account: escrow,
+                    escrow_memo: memo_account,
+                    config,
+                    mint_limits: pda(&[SEED_MINT_LIMITS, system_program::ID.as_ref()]),
+                    sender_usage: pda(&[
+                        SEED_SENDER_USAGE,
+                        sender.as_ref(),
+                        system_program::ID.as_ref(),
+                    ]),
+                    system_program: system_program::ID,
+                    associated_token_program: self
+                        .receipt
+                        .then_some(anchor_spl::associated_token::ID),
+                    rent: sysvar::rent::ID,
+                    receipt_mint,
+                    receipt_account,
+                    receipt_token_program,
+                }
+                .to_account_metas(None),
+                instruction::InitializeTransferSol {
+                    amount: self.amount,
+                    expiration_time: self.expiration_time,
+                    hash_of_secret,
+                    options,
+                }
+                .data(),
+            ),
+        };
+        let instruction = Instruction {
+            program_id: instant_send_program::ID,
+            accounts,
+            data,
+        };
+        (escrow, instruction)
+    }
+}
@@ -0,0 +1,88 @@
+//! Read-only calls, answered by simulating the program's view instructions.
+use anchor_lang::{AnchorDeserialize, InstructionData, ToAccountMetas};
+use anyhow::{anyhow, bail, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use instant_send_program::instructions::escrow_view::EscrowStatus;
+use instant_send_program::{accounts, instruction};
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::config::RpcSimulateTransactionConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::Transaction;
+
+use crate::error::explain_logs;
+
+/// Simulation drops trailing zero bytes of return data, all of it for a `false`; they are put
+/// back before decoding.
+const MAX_RETURN_DATA: usize = 1024;
+
+/// What the program reports for `escrow`; fails with `EscrowNotFound` once it is closed.
+pub fn escrow_status(client: &RpcClient, payer: &Pubkey, escrow: &Pubkey) -> Result<EscrowStatus> {
+    simulate(
+        client,
+        payer,
+        view(escrow, instruction::GetEscrowStatus {}.data()),
+    )
+}
+
+/// Whether `secret` opens `escrow`, checked by the program without redeeming anything.
+pub fn check_secret(
+    client: &RpcClient,
+    payer: &Pubkey,
+    escrow: &Pubkey,
+    secret: &[u8],
+) -> Result<bool> {
+    let data = instruction::CheckSecret {
+        secret: secret.to_vec(),
+    }
+    .data();
+    simulate(client, payer, view(escrow, data))
+}
+
+fn view(escrow: &Pubkey, data: Vec<u8>) -> Instruction {
+    Instruction {
+        program_id: instant_send_program::ID,
+        accounts: accounts::ViewEscrow {
+            escrow_account: *escrow,
+        }
+        .to_account_metas(None),
+        data,
+    }
+}
+
+/// Simulates `instruction` without signing it and decodes what the program returned. `payer`
+/// only has to exist; nothing is sent.
+fn simulate<T: AnchorDeserialize>(
+    client: &RpcClient,
+    payer: &Pubkey,
+    instruction: Instruction,
+) -> Result<T> {
+    let transaction = Transaction::new_with_payer(&[instruction], Some(payer));
+    let result = client
+        .simulate_transaction_with_config(
+            &transaction,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                commitment: Some(client.commitment()),
+                ..RpcSimulateTransactionConfig::default()
+            },
+        )?
+        .value;
+    if let Some(error) = result.err {
+        return Err(result
+            .logs
+            .as_deref()
+            .and_then(explain_logs)
+            .unwrap_or_else(|| anyhow!(error)));
+    }
+    let mut data = match result.return_data {
+        Some(return_data) if return_data.program_id != instant_send_program::ID.to_string() => {
+            bail!("unexpected return data from {}", return_data.program_id)
+        }
+        Some(return_data) => STANDARD.decode(&return_data.data.0)?,
+        None => Vec::new(),
+    };
+    data.resize(MAX_RETURN_DATA.max(data.len()), 0);
+    Ok(T::deserialize(&mut data.as_slice())?)
+}
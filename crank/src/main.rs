@@ -0,0 +1,367 @@
+//! `instant-send-crank`: sweeps escrows that expired more than the configured delay ago
+//! back to their senders, or to the holders of their receipts, collecting the sweep bounty
+//! for each.
+//!
+//! Every pass reads the sweep settings from the config, lists the program's escrow
+//! accounts and sends one `sweep_expired_*` or `sweep_expired_with_receipt_*` transaction
+//! per eligible escrow.
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use anchor_lang::{AccountDeserialize, Discriminator, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::token_interface::TokenAccount;
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use instant_send_program::{
+    accounts, instruction, Config, EscrowAccount, EscrowSOLAccount, SEED_CONFIG, SEED_RECEIPT_MINT,
+    SEED_SENDER_REGISTRY,
+};
+use log::{info, warn};
+use solana_account_decoder::UiAccountEncoding;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_rpc_client_api::filter::{Memcmp, RpcFilterType};
+use solana_sdk::account::{from_account, Account};
+use solana_sdk::clock::Clock;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use solana_sdk::{system_program, sysvar};
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Keypair that signs, pays the fees and receives the bounties.
+    #[arg(long, short = 'k', default_value_t = default_keypair())]
+    keypair: String,
+    /// RPC endpoint of the cluster.
+    #[arg(long, short = 'u', default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// Delay between passes, in seconds.
+    #[arg(long, default_value_t = 60)]
+    interval: u64,
+    /// Sweep once and exit instead of polling.
+    #[arg(long)]
+    once: bool,
+    /// Logs the escrows that would be swept without sending anything.
+    #[arg(long)]
+    dry_run: bool,
+    /// Skips escrows whose bounty is below this many lamports, e.g. the transaction fee.
+    #[arg(long, default_value_t = 0)]
+    min_bounty: u64,
+}
+
+fn default_keypair() -> String {
+    let home = std::env::var("HOME").unwrap_or_default();
+    PathBuf::from(home)
+        .join(".config/solana/id.json")
+        .display()
+        .to_string()
+}
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &instant_send_program::ID).0
+}
+
+/// An escrow that can be swept, with the bounty sweeping it pays.
+struct Eligible {
+    address: Pubkey,
+    sender: Pubkey,
+    /// `None` for SOL escrows.
+    mint: Option<Pubkey>,
+    has_receipt: bool,
+    bounty: u64,
+}
+
+/// Where a receipt escrow's funds go: the receipt's current holder and their account with it,
+/// or the sender once the receipt was burned.
+struct Holder {
+    owner: Pubkey,
+    receipt_account: Option<Pubkey>,
+}
+
+struct Crank {
+    client: RpcClient,
+    keypair: Keypair,
+    dry_run: bool,
+    min_bounty: u64,
+    /// Token program of each mint seen so far.
+    token_programs: HashMap<Pubkey, Pubkey>,
+}
+
+impl Crank {
+    /// Runs one pass and returns how many escrows were swept.
+    fn pass(&mut self) -> Result<usize> {
+        let config = self
+            .client
+            .get_account_with_commitment(&pda(&[SEED_CONFIG]), self.client.commitment())?
+            .value
+            .ok_or_else(|| anyhow!("the program has no config, so nothing can be swept"))?;
+        let settings = Config::try_deserialize(&mut config.data.as_slice())?.sweep;
+        if !settings.enabled {
+            info!("sweeping is disabled in the config");
+            return Ok(0);
+        }
+        let clock: Clock = from_account(&self.client.get_account(&sysvar::clock::ID)?)
+            .ok_or_else(|| anyhow!("could not read the clock sysvar"))?;
+        let sweepable = |expiration_time: i64| {
+            clock.unix_timestamp > expiration_time.saturating_add(settings.delay as i64)
+        };
+
+        let mut eligible = Vec::new();
+        for (address, account) in self.escrows(&EscrowAccount::DISCRIMINATOR)? {
+            let Ok(escrow) = EscrowAccount::try_deserialize(&mut account.data.as_slice()) else {
+                continue;
+            };
+            if sweepable(escrow.expiration_time) {
+                eligible.push(Eligible {
+                    address,
+                    sender: escrow.sender,
+                    mint: Some(escrow.token_mint),
+                    has_receipt: escrow.has_receipt,
+                    bounty: settings.bounty.min(account.lamports),
+                });
+            }
+        }
+        for (address, account) in self.escrows(&EscrowSOLAccount::DISCRIMINATOR)? {
+            let Ok(escrow) = EscrowSOLAccount::try_deserialize(&mut account.data.as_slice()) else {
+                continue;
+            };
+            if sweepable(escrow.expiration_time) {
+                // The escrowed lamports go to the sender or receipt holder; the bounty comes out of
+                // the rent.
+                let rent = account.lamports.saturating_sub(escrow.remaining());
+                eligible.push(Eligible {
+                    address,
+                    sender: escrow.sender,
+                    mint: None,
+                    has_receipt: escrow.has_receipt,
+                    bounty: settings.bounty.min(rent),
+                });
+            }
+        }
+
+        let mut swept = 0;
+        for escrow in eligible {
+            if escrow.bounty < self.min_bounty {
+                continue;
+            }
+            if self.dry_run {
+                info!(
+                    "would sweep {} for {} lamports",
+                    escrow.address, escrow.bounty
+                );
+                continue;
+            }
+            // One failure, e.g. a sender refunding at the same time, must not stop the pass.
+            match self.sweep(&escrow) {
+                Ok((refunded_to, signature)) => {
+                    info!(
+                        "swept {} to {refunded_to} for {} lamports: {signature}",
+                        escrow.address, escrow.bounty
+                    );
+                    swept += 1;
+                }
+                Err(error) => warn!("could not sweep {}: {error}", escrow.address),
+            }
+        }
+        Ok(swept)
+    }
+
+    /// The program's accounts starting with `discriminator`.
+    fn escrows(&self, discriminator: &[u8]) -> Result<Vec<(Pubkey, Account)>> {
+        Ok(self.client.get_program_accounts_with_config(
+            &instant_send_program::ID,
+            RpcProgramAccountsConfig {
+                filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                    0,
+                    discriminator.to_vec(),
+                ))]),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    ..RpcAccountInfoConfig::default()
+                },
+                ..RpcProgramAccountsConfig::default()
+            },
+        )?)
+    }
+
+    /// Owner of `mint`, i.e. the token program (Token or Token-2022) it belongs to.
+    fn token_program(&mut self, mint: &Pubkey) -> Result<Pubkey> {
+        if let Some(program) = self.token_programs.get(mint) {
+            return Ok(*program);
+        }
+        let program = self.client.get_account(mint)?.owner;
+        self.token_programs.insert(*mint, program);
+        Ok(program)
+    }
+
+    /// Current holder of the receipt of `escrow`, wherever it was transferred to.
+    fn receipt_holder(&self, escrow: &Eligible) -> Result<Holder> {
+        let receipt_mint = pda(&[SEED_RECEIPT_MINT, escrow.address.as_ref()]);
+        let Some(held) = self
+            .client
+            .get_token_largest_accounts(&receipt_mint)?
+            .into_iter()
+            .find(|balance| balance.amount.amount == "1")
+        else {
+            return Ok(Holder {
+                owner: escrow.sender,
+                receipt_account: None,
+            });
+        };
+        let receipt_account: Pubkey = held
+            .address
+            .parse()
+            .map_err(|_| anyhow!("invalid receipt account address"))?;
+        let account = self.client.get_account(&receipt_account)?;
+        Ok(Holder {
+            owner: TokenAccount::try_deserialize(&mut account.data.as_slice())?.owner,
+            receipt_account: Some(receipt_account),
+        })
+    }
+
+    /// Sweeps `escrow` and returns who was refunded, with the transaction signature.
+    fn sweep(&mut self, escrow: &Eligible) -> Result<(Pubkey, String)> {
+        let cranker = self.keypair.pubkey();
+        let sender_registry = pda(&[SEED_SENDER_REGISTRY, escrow.sender.as_ref()]);
+        let config = pda(&[SEED_CONFIG]);
+        let holder = match escrow.has_receipt {
+            true => Some(self.receipt_holder(escrow)?),
+            false => None,
+        };
+        let receipt_mint = pda(&[SEED_RECEIPT_MINT, escrow.address.as_ref()]);
+        let instruction = match (escrow.mint, &holder) {
+            (Some(mint), None) => {
+                let token_program = self.token_program(&mint)?;
+                Instruction {
+                    program_id: instant_send_program::ID,
+                    accounts: accounts::SweepExpiredSPL {
+                        cranker,
+                        escrow_account: escrow.address,
+                        escrow_token_account: get_associated_token_address_with_program_id(
+                            &escrow.address,
+                            &mint,
+                            &token_program,
+                        ),
+                        sender: escrow.sender,
+                        sender_token_account: get_associated_token_address_with_program_id(
+                            &escrow.sender,
+                            &mint,
+                            &token_program,
+                        ),
+                        token_mint: mint,
+                        sender_registry,
+                        config,
+                        token_program,
+                        associated_token_program: anchor_spl::associated_token::ID,
+                        system_program: system_program::ID,
+                    }
+                    .to_account_metas(None),
+                    data: instruction::SweepExpiredSpl {}.data(),
+                }
+            }
+            (Some(mint), Some(holder)) => {
+                let token_program = self.token_program(&mint)?;
+                Instruction {
+                    program_id: instant_send_program::ID,
+                    accounts: accounts::SweepExpiredWithReceiptSPL {
+                        cranker,
+                        escrow_account: escrow.address,
+                        escrow_token_account: get_associated_token_address_with_program_id(
+                            &escrow.address,
+                            &mint,
+                            &token_program,
+                        ),
+                        holder: holder.owner,
+                        holder_token_account: get_associated_token_address_with_program_id(
+                            &holder.owner,
+                            &mint,
+                            &token_program,
+                        ),
+                        token_mint: mint,
+                        receipt_mint,
+                        receipt_account: holder.receipt_account,
+                        sender_registry,
+                        config,
+                        token_program,
+                        receipt_token_program: anchor_spl::token_2022::ID,
+                        associated_token_program: anchor_spl::associated_token::ID,
+                        system_program: system_program::ID,
+                    }
+                    .to_account_metas(None),
+                    data: instruction::SweepExpiredWithReceiptSpl {}.data(),
+                }
+            }
+            (None, None) => Instruction {
+                program_id: instant_send_program::ID,
+                accounts: accounts::SweepExpiredSOL {
+                    cranker,
+                    escrow_account: escrow.address,
+                    sender: escrow.sender,
+                    sender_registry,
+                    config,
+                    system_program: system_program::ID,
+                }
+                .to_account_metas(None),
+                data: instruction::SweepExpiredSol {}.data(),
+            },
+            (None, Some(holder)) => Instruction {
+                program_id: instant_send_program::ID,
+                accounts: accounts::SweepExpiredWithReceiptSOL {
+                    cranker,
+                    escrow_account: escrow.address,
+                    holder: holder.owner,
+                    receipt_mint,
+                    receipt_account: holder.receipt_account,
+                    sender_registry,
+                    config,
+                    receipt_token_program: anchor_spl::token_2022::ID,
+                }
+                .to_account_metas(None),
+                data: instruction::SweepExpiredWithReceiptSol {}.data(),
+            },
+        };
+
+        let blockhash = self.client.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&cranker),
+            &[&self.keypair],
+            blockhash,
+        );
+        let signature = self.client.send_and_confirm_transaction(&transaction)?;
+        let refunded_to = holder.map_or(escrow.sender, |holder| holder.owner);
+        Ok((refunded_to, signature.to_string()))
+    }
+}
+
+fn main() -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let cli = Cli::parse();
+    let keypair = read_keypair_file(&cli.keypair)
+        .map_err(|error| anyhow!("could not read keypair {}: {error}", cli.keypair))?;
+    let mut crank = Crank {
+        client: RpcClient::new_with_commitment(cli.url, CommitmentConfig::confirmed()),
+        keypair,
+        dry_run: cli.dry_run,
+        min_bounty: cli.min_bounty,
+        token_programs: HashMap::new(),
+    };
+
+    loop {
+        let swept = crank.pass()?;
+        if swept > 0 {
+            info!("swept {swept} escrow(s)");
+        }
+        if cli.once {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_secs(cli.interval));
+    }
+}
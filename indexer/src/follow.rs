@@ -0,0 +1,248 @@
+//! Follows the program's transactions over RPC and feeds them into the store.
+//!
+//! Transactions are indexed once `confirmed`. A confirmed block can still be dropped by a
+//! fork switch, so every indexed transaction is re-checked until it is `finalized`; those
+//! that vanish are removed again along with their escrow events.
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+use solana_rpc_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_rpc_client_api::config::RpcTransactionConfig;
+use solana_rpc_client_api::response::RpcConfirmedTransactionStatusWithSignature;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, TransactionConfirmationStatus, UiInstruction,
+    UiTransactionEncoding,
+};
+
+use crate::decode::{
+    apply_created_events, decode_created_events, decode_instruction, EscrowEvent, EventKind,
+};
+use crate::store::Store;
+
+/// Most signatures `getSignaturesForAddress` returns per page.
+const SIGNATURE_PAGE_SIZE: usize = 1000;
+/// Most signatures `getSignatureStatuses` accepts per call.
+const STATUS_BATCH_SIZE: usize = 256;
+
+pub struct Follower {
+    client: RpcClient,
+    program_id: Pubkey,
+    store: Store,
+}
+
+impl Follower {
+    pub fn new(url: String, program_id: Pubkey, store: Store) -> Self {
+        Self {
+            client: RpcClient::new_with_commitment(url, CommitmentConfig::confirmed()),
+            program_id,
+            store,
+        }
+    }
+
+    /// Indexes every confirmed transaction since the saved cursor, or since `start_slot` on
+    /// the first run. Returns how many transactions were indexed.
+    pub fn sync(&mut self, start_slot: Option<u64>) -> Result<usize> {
+        let from_slot = self.store.cursor()?.or(start_slot).unwrap_or_default();
+        let signatures = self.signatures_since(from_slot)?;
+
+        let mut indexed = 0;
+        for status in &signatures {
+            // Failed transactions changed nothing; already indexed ones are being re-scanned
+            // because they share a slot with the cursor.
+            if status.err.is_some() || self.store.has_transaction(&status.signature)? {
+                continue;
+            }
+            self.index_transaction(&status.signature)?;
+            indexed += 1;
+        }
+        if let Some(last) = signatures.last() {
+            self.store.set_cursor(last.slot)?;
+        }
+        Ok(indexed)
+    }
+
+    /// Signatures of the program's transactions at or after `from_slot`, oldest first.
+    fn signatures_since(
+        &self,
+        from_slot: u64,
+    ) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>> {
+        let mut signatures = Vec::new();
+        let mut before = None;
+        loop {
+            let page = self.client.get_signatures_for_address_with_config(
+                &self.program_id,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    until: None,
+                    limit: Some(SIGNATURE_PAGE_SIZE),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                },
+            )?;
+            let exhausted = page.len() < SIGNATURE_PAGE_SIZE;
+            before = match page.last() {
+                Some(last) => Some(Signature::from_str(&last.signature)?),
+                None => None,
+            };
+            let reached_cursor = page.iter().any(|status| status.slot < from_slot);
+            signatures.extend(page.into_iter().filter(|status| status.slot >= from_slot));
+            if exhausted || reached_cursor || before.is_none() {
+                break;
+            }
+        }
+        signatures.reverse();
+        Ok(signatures)
+    }
+
+    fn index_transaction(&mut self, signature: &str) -> Result<()> {
+        let transaction = self.client.get_transaction_with_config(
+            &Signature::from_str(signature)?,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )?;
+        let events = self.decode_transaction(&transaction)?;
+        if !events.is_empty() {
+            info!("{signature}: {} escrow event(s)", events.len());
+        }
+        self.store
+            .record_transaction(signature, transaction.slot, transaction.block_time, &events)
+    }
+
+    /// Escrow events from the program's top-level and inner instructions, in execution order.
+    fn decode_transaction(
+        &self,
+        transaction: &EncodedConfirmedTransactionWithStatusMeta,
+    ) -> Result<Vec<EscrowEvent>> {
+        let decoded = transaction
+            .transaction
+            .transaction
+            .decode()
+            .ok_or_else(|| anyhow!("transaction could not be decoded"))?;
+        let meta = transaction
+            .transaction
+            .meta
+            .as_ref()
+            .ok_or_else(|| anyhow!("transaction has no status meta"))?;
+
+        // Static keys first, then keys loaded from lookup tables: writable, then readonly.
+        let mut keys = decoded.message.static_account_keys().to_vec();
+        if let OptionSerializer::Some(loaded) = &meta.loaded_addresses {
+            for key in loaded.writable.iter().chain(&loaded.readonly) {
+                keys.push(Pubkey::from_str(key)?);
+            }
+        }
+        let inner = match &meta.inner_instructions {
+            OptionSerializer::Some(inner) => inner.as_slice(),
+            _ => &[],
+        };
+
+        // Accounts left without lamports were closed by the transaction.
+        let closed = |account: &Pubkey| {
+            keys.iter()
+                .position(|key| key == account)
+                .and_then(|index| meta.post_balances.get(index))
+                == Some(&0)
+        };
+
+        let mut events = Vec::new();
+        let mut decode = |program_index: u8, accounts: &[u8], data: &[u8]| {
+            if keys.get(program_index as usize) != Some(&self.program_id) {
+                return;
+            }
+            let accounts: Vec<Pubkey> = accounts
+                .iter()
+                .filter_map(|index| keys.get(*index as usize).copied())
+                .collect();
+            events.extend(decode_instruction(data, &accounts, closed));
+        };
+        for (index, instruction) in decoded.message.instructions().iter().enumerate() {
+            decode(
+                instruction.program_id_index,
+                &instruction.accounts,
+                &instruction.data,
+            );
+            // Calls made through other programs (CPI).
+            for group in inner.iter().filter(|group| group.index as usize == index) {
+                for instruction in &group.instructions {
+                    if let UiInstruction::Compiled(instruction) = instruction {
+                        let data = solana_sdk::bs58::decode(&instruction.data).into_vec()?;
+                        decode(instruction.program_id_index, &instruction.accounts, &data);
+                    }
+                }
+            }
+        }
+
+        // Every withdrawal from an escrow drained in this transaction decodes as closing it;
+        // keep only the last, which did.
+        let mut closing = HashSet::new();
+        events.reverse();
+        events.retain(|event| event.kind == EventKind::Created || closing.insert(event.escrow));
+        events.reverse();
+
+        if let OptionSerializer::Some(logs) = &meta.log_messages {
+            apply_created_events(&mut events, &decode_created_events(logs, &self.program_id));
+        }
+        Ok(events)
+    }
+
+    /// Re-checks transactions that are not finalized yet, dropping those that were rolled
+    /// back and rewinding the cursor so their slots are scanned again.
+    pub fn check_reorgs(&mut self) -> Result<()> {
+        let pending = self.store.unfinalized()?;
+        for batch in pending.chunks(STATUS_BATCH_SIZE) {
+            let signatures = batch
+                .iter()
+                .map(|(signature, _)| Signature::from_str(signature))
+                .collect::<Result<Vec<_>, _>>()?;
+            let statuses = self
+                .client
+                .get_signature_statuses_with_history(&signatures)?
+                .value;
+
+            for ((signature, slot), status) in batch.iter().zip(statuses) {
+                match status {
+                    None => {
+                        warn!("{signature} was rolled back at slot {slot}");
+                        self.store.drop_transaction(signature)?;
+                        self.rewind(*slot)?;
+                    }
+                    Some(status) if status.err.is_some() => {
+                        warn!("{signature} failed after a fork switch");
+                        self.store.drop_transaction(signature)?;
+                        self.rewind(*slot)?;
+                    }
+                    Some(status)
+                        if status.confirmation_status
+                            == Some(TransactionConfirmationStatus::Finalized) =>
+                    {
+                        self.store.mark_finalized(signature, status.slot)?;
+                    }
+                    Some(status) if status.slot != *slot => {
+                        self.store.move_transaction(signature, status.slot)?;
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn rewind(&self, slot: u64) -> Result<()> {
+        if self.store.cursor()?.is_none_or(|cursor| cursor > slot) {
+            self.store.set_cursor(slot)?;
+        }
+        Ok(())
+    }
+
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+}
@@ -0,0 +1,126 @@
+//! Indexes instant-send escrows into a SQLite database, so their history survives the
+//! accounts being closed.
+//!
+//! `sync` follows the program's transactions from an RPC endpoint; `escrows` queries what
+//! has been indexed and prints one JSON object per line.
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use log::info;
+use solana_sdk::pubkey::Pubkey;
+
+mod decode;
+mod follow;
+mod store;
+
+use follow::Follower;
+use store::{EscrowFilter, Store};
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// SQLite database file, created on first use.
+    #[arg(long, default_value = "escrows.db")]
+    db: PathBuf,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Follows the program's transactions and indexes them.
+    Sync {
+        #[arg(long, default_value = "http://127.0.0.1:8899")]
+        url: String,
+        #[arg(long, default_value_t = instant_send_program::ID)]
+        program_id: Pubkey,
+        /// Slot to start from on the first run; later runs resume from the saved cursor.
+        #[arg(long)]
+        start_slot: Option<u64>,
+        /// Delay between polls.
+        #[arg(long, default_value_t = 2000)]
+        poll_ms: u64,
+        /// Catch up once and exit instead of following.
+        #[arg(long)]
+        once: bool,
+    },
+    /// Prints indexed escrows, oldest first.
+    Escrows {
+        #[arg(long)]
+        sender: Option<Pubkey>,
+        #[arg(long)]
+        recipient: Option<Pubkey>,
+        /// Token mint, or the system program id for SOL escrows.
+        #[arg(long)]
+        mint: Option<Pubkey>,
+        /// `open`, `redeemed` or `refunded`.
+        #[arg(long)]
+        status: Option<String>,
+        /// Created at or after this unix timestamp.
+        #[arg(long)]
+        since: Option<i64>,
+        /// Created before this unix timestamp.
+        #[arg(long)]
+        until: Option<i64>,
+        #[arg(long)]
+        limit: Option<u32>,
+    },
+}
+
+fn main() -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let cli = Cli::parse();
+    let store = Store::open(&cli.db)?;
+
+    match cli.command {
+        Command::Sync {
+            url,
+            program_id,
+            start_slot,
+            poll_ms,
+            once,
+        } => {
+            let mut follower = Follower::new(url, program_id, store);
+            loop {
+                let indexed = follower.sync(start_slot)?;
+                follower.check_reorgs()?;
+                if indexed > 0 {
+                    info!(
+                        "indexed {indexed} transaction(s), cursor at slot {:?}",
+                        follower.store().cursor()?
+                    );
+                }
+                if once {
+                    return Ok(());
+                }
+                thread::sleep(Duration::from_millis(poll_ms));
+            }
+        }
+        Command::Escrows {
+            sender,
+            recipient,
+            mint,
+            status,
+            since,
+            until,
+            limit,
+        } => {
+            let filter = EscrowFilter {
+                sender: sender.map(|key| key.to_string()),
+                recipient: recipient.map(|key| key.to_string()),
+                mint: mint.map(|key| key.to_string()),
+                status,
+                created_after: since,
+                created_before: until,
+                limit,
+            };
+            for escrow in store.escrows(&filter)? {
+                println!("{}", serde_json::to_string(&escrow)?);
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//! SQLite storage for indexed transactions and the escrow lifecycle built from them.
+//!
+//! Only raw lifecycle events are stored; the `escrows` view derives each escrow's current
+//! state from them. Undoing a rolled-back transaction is then just deleting its rows.
+use std::path::Path;
+
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::decode::EscrowEvent;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS cursor (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    slot INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS transactions (
+    signature TEXT PRIMARY KEY,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    finalized INTEGER NOT NULL DEFAULT 0
+);
+CREATE TABLE IF NOT EXISTS escrow_events (
+    signature TEXT NOT NULL REFERENCES transactions (signature) ON DELETE CASCADE,
+    ordinal INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    escrow TEXT NOT NULL,
+    asset TEXT NOT NULL,
+    sender TEXT,
+    mint TEXT,
+    amount INTEGER,
+    expiration_time INTEGER,
+    memo TEXT,
+    recipient TEXT,
+    PRIMARY KEY (signature, ordinal)
+);
+CREATE INDEX IF NOT EXISTS escrow_events_escrow ON escrow_events (escrow);
+CREATE INDEX IF NOT EXISTS transactions_unfinalized ON transactions (finalized) WHERE finalized = 0;
+CREATE VIEW IF NOT EXISTS escrows AS
+SELECT
+    created.escrow, created.asset, created.sender, created.mint, created.amount,
+    created.expiration_time, created.memo,
+    created_tx.slot AS created_slot, created_tx.block_time AS created_at,
+    created.signature AS create_signature,
+    COALESCE(closed.kind, 'open') AS status, closed.recipient,
+    closed_tx.slot AS closed_slot, closed_tx.block_time AS closed_at,
+    closed.signature AS close_signature
+FROM escrow_events created
+JOIN transactions created_tx ON created_tx.signature = created.signature
+LEFT JOIN escrow_events closed ON closed.escrow = created.escrow AND closed.kind <> 'created'
+LEFT JOIN transactions closed_tx ON closed_tx.signature = closed.signature
+WHERE created.kind = 'created';
+";
+
+/// Filters for `Store::escrows`; unset fields match everything.
+#[derive(Debug, Default)]
+pub struct EscrowFilter {
+    pub sender: Option<String>,
+    pub recipient: Option<String>,
+    pub mint: Option<String>,
+    pub status: Option<String>,
+    /// Created at or after this unix timestamp.
+    pub created_after: Option<i64>,
+    /// Created before this unix timestamp.
+    pub created_before: Option<i64>,
+    pub limit: Option<u32>,
+}
+
+/// One row of the `escrows` view.
+#[derive(Debug, Serialize)]
+pub struct EscrowRow {
+    pub escrow: String,
+    pub asset: String,
+    pub sender: Option<String>,
+    pub mint: Option<String>,
+    pub amount: Option<u64>,
+    pub expiration_time: Option<i64>,
+    pub memo: Option<String>,
+    pub created_slot: u64,
+    pub created_at: Option<i64>,
+    pub create_signature: String,
+    pub status: String,
+    pub recipient: Option<String>,
+    pub closed_slot: Option<u64>,
+    pub closed_at: Option<i64>,
+    pub close_signature: Option<String>,
+}
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Highest slot fully indexed so far.
+    pub fn cursor(&self) -> Result<Option<u64>> {
+        Ok(self
+            .conn
+            .query_row("SELECT slot FROM cursor WHERE id = 0", [], |row| row.get(0))
+            .optional()?)
+    }
+
+    pub fn set_cursor(&self, slot: u64) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO cursor (id, slot) VALUES (0, ?1)
+             ON CONFLICT (id) DO UPDATE SET slot = excluded.slot",
+            params![slot],
+        )?;
+        Ok(())
+    }
+
+    pub fn has_transaction(&self, signature: &str) -> Result<bool> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT 1 FROM transactions WHERE signature = ?1",
+                params![signature],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some())
+    }
+
+    /// Stores a transaction and the escrow events it produced, atomically.
+    pub fn record_transaction(
+        &mut self,
+        signature: &str,
+        slot: u64,
+        block_time: Option<i64>,
+        events: &[EscrowEvent],
+    ) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT OR IGNORE INTO transactions (signature, slot, block_time) VALUES (?1, ?2, ?3)",
+            params![signature, slot, block_time],
+        )?;
+        for (ordinal, event) in events.iter().enumerate() {
+            tx.execute(
+                "INSERT OR IGNORE INTO escrow_events
+                 (signature, ordinal, kind, escrow, asset, sender, mint, amount,
+                  expiration_time, memo, recipient)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    signature,
+                    ordinal,
+                    event.kind.as_str(),
+                    event.escrow.to_string(),
+                    event.asset.as_str(),
+                    event.sender.map(|key| key.to_string()),
+                    event.mint.map(|key| key.to_string()),
+                    // Stored bit for bit; amounts above i64::MAX read back correctly as u64.
+                    event.amount.map(|amount| amount as i64),
+                    event.expiration_time,
+                    event.memo,
+                    event.recipient.map(|key| key.to_string()),
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Signatures and slots of transactions that could still be rolled back.
+    pub fn unfinalized(&self) -> Result<Vec<(String, u64)>> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT signature, slot FROM transactions WHERE finalized = 0")?;
+        let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn mark_finalized(&self, signature: &str, slot: u64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE transactions SET finalized = 1, slot = ?2 WHERE signature = ?1",
+            params![signature, slot],
+        )?;
+        Ok(())
+    }
+
+    /// Records that a transaction landed in a different slot after a fork switch.
+    pub fn move_transaction(&self, signature: &str, slot: u64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE transactions SET slot = ?2 WHERE signature = ?1",
+            params![signature, slot],
+        )?;
+        Ok(())
+    }
+
+    /// Forgets a transaction that was rolled back, along with its escrow events.
+    pub fn drop_transaction(&self, signature: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM transactions WHERE signature = ?1",
+            params![signature],
+        )?;
+        Ok(())
+    }
+
+    pub fn escrows(&self, filter: &EscrowFilter) -> Result<Vec<EscrowRow>> {
+        let mut statement = self.conn.prepare(
+            "SELECT escrow, asset, sender, mint, amount, expiration_time, memo, created_slot,
+                    created_at, create_signature, status, recipient, closed_slot, closed_at,
+                    close_signature
+             FROM escrows
+             WHERE (?1 IS NULL OR sender = ?1)
+               AND (?2 IS NULL OR recipient = ?2)
+               AND (?3 IS NULL OR mint = ?3)
+               AND (?4 IS NULL OR status = ?4)
+               AND (?5 IS NULL OR created_at >= ?5)
+               AND (?6 IS NULL OR created_at < ?6)
+             ORDER BY created_slot, escrow
+             LIMIT ?7",
+        )?;
+        let rows = statement.query_map(
+            params![
+                filter.sender,
+                filter.recipient,
+                filter.mint,
+                filter.status,
+                filter.created_after,
+                filter.created_before,
+                filter.limit.map_or(-1, i64::from),
+            ],
+            |row| {
+                Ok(EscrowRow {
+                    escrow: row.get(0)?,
+                    asset: row.get(1)?,
+                    sender: row.get(2)?,
+                    mint: row.get(3)?,
+                    amount: row.get::<_, Option<i64>>(4)?.map(|amount| amount as u64),
+                    expiration_time: row.get(5)?,
+                    memo: row.get(6)?,
+                    created_slot: row.get(7)?,
+                    created_at: row.get(8)?,
+                    create_signature: row.get(9)?,
+                    status: row.get(10)?,
+                    recipient: row.get(11)?,
+                    closed_slot: row.get(12)?,
+                    closed_at: row.get(13)?,
+                    close_signature: row.get(14)?,
+                })
+            },
+        )?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
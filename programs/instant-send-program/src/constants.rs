@@ -5,4 +5,66 @@ use anchor_lang::prelude::*;
 //pub const SEED: &str = "anchor"
 pub const SEED_ESCROW_SPL: &[u8] = b"escrow_spl";
 pub const SEED_ESCROW_SOL: &[u8] = b"escrow_sol";
+#[constant]
+pub const SEED_SENDER_NONCE: &[u8] = b"sender_nonce";
+#[constant]
+pub const SEED_SENDER_REGISTRY: &[u8] = b"sender_registry";
+#[constant]
+pub const SEED_CONFIG: &[u8] = b"config";
+#[constant]
+pub const SEED_WHITELISTED_PROGRAM: &[u8] = b"whitelisted_program";
+#[constant]
+pub const SEED_MINT_LIST: &[u8] = b"mint_list";
+#[constant]
+pub const SEED_MINT_LIMITS: &[u8] = b"mint_limits";
+#[constant]
+pub const SEED_SENDER_USAGE: &[u8] = b"sender_usage";
+#[constant]
+pub const SEED_ESCROW_MEMO: &[u8] = b"escrow_memo";
+#[constant]
+pub const SEED_RECEIPT_MINT: &[u8] = b"receipt_mint";
+/// Stands in for the mint of SOL escrows in limit and usage seeds.
+pub const NATIVE_MINT_KEY: Pubkey = anchor_lang::system_program::ID;
 pub const ANCHOR_DISCRIMINATOR_SIZE: usize = 8;
+
+/// Layout version written to newly created escrow accounts. Legacy accounts read as 0.
+#[constant]
+pub const ESCROW_VERSION: u8 = 1;
+/// Zeroed bytes kept at the end of SPL escrow accounts for future fields. New fields take
+/// their bytes from here, so `INIT_SPACE` stays the same and existing escrows decode them
+/// as zero without a migration.
+pub const ESCROW_RESERVED_SPACE: usize = 64;
+/// Zeroed bytes kept at the end of SOL escrow accounts, used like `ESCROW_RESERVED_SPACE`.
+pub const ESCROW_SOL_RESERVED_SPACE: usize = 64;
+/// Shortest secret, in bytes, accepted by the `_v2` redeem and refund instructions.
+#[constant]
+pub const MIN_SECRET_LEN: u32 = 16;
+/// Longest secret, in bytes, accepted by the `_v2` redeem and refund instructions.
+#[constant]
+pub const MAX_SECRET_LEN: u32 = 64;
+/// Longest memo, in bytes, that can be attached to an escrow.
+#[constant]
+pub const MAX_MEMO_LEN: u32 = 200;
+/// Most approvers an escrow can require sign-off from; approvals are tracked in a `u8` bitmask.
+pub const MAX_APPROVERS: usize = 5;
+/// Layout version written to the config account.
+pub const CONFIG_VERSION: u8 = 1;
+/// Zeroed bytes kept at the end of the config account for future settings.
+pub const CONFIG_RESERVED_SPACE: usize = 70;
+/// Most escrow addresses returned by one `list_sender_escrows` call (1024-byte return data limit).
+#[constant]
+pub const REGISTRY_PAGE_SIZE: u32 = 31;
+
+// Risk flags stored on SPL escrows, see `mint_risk_flags`.
+#[constant]
+pub const MINT_RISK_FREEZE_AUTHORITY: u16 = 1 << 0;
+#[constant]
+pub const MINT_RISK_PERMANENT_DELEGATE: u16 = 1 << 1;
+#[constant]
+pub const MINT_RISK_NON_TRANSFERABLE: u16 = 1 << 2;
+#[constant]
+pub const MINT_RISK_DEFAULT_FROZEN: u16 = 1 << 3;
+#[constant]
+pub const MINT_RISK_TRANSFER_FEE: u16 = 1 << 4;
+#[constant]
+pub const MINT_RISK_TRANSFER_HOOK: u16 = 1 << 5;
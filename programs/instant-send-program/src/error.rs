@@ -10,4 +10,100 @@ pub enum CustomError {
     NotExpired,
     #[msg("The secret is invalid")]
     InvalidSecret,
+    #[msg("The escrow account already uses the current layout.")]
+    AlreadyMigrated,
+    #[msg("The sender registry still lists open escrows.")]
+    RegistryNotEmpty,
+    #[msg("The rent receiver does not match the escrow's rent policy.")]
+    InvalidRentReceiver,
+    #[msg("The PIN is missing or invalid")]
+    InvalidPin,
+    #[msg("Only the admin can perform this action.")]
+    Unauthorized,
+    #[msg("The recipient must sign to redeem and invoke.")]
+    RecipientMustSign,
+    #[msg("The invoked program returned less than the minimum output.")]
+    InsufficientOutput,
+    #[msg("The escrow has a claim condition but its program or config account is missing.")]
+    ConditionAccountsMissing,
+    #[msg("The condition program does not match the escrow.")]
+    InvalidConditionProgram,
+    #[msg("The condition config does not match the escrow.")]
+    InvalidConditionConfig,
+    #[msg("The approver set is invalid: too many approvers, duplicates or a bad threshold.")]
+    InvalidApprovers,
+    #[msg("The signer is not an approver of this escrow.")]
+    NotApprover,
+    #[msg("The approver has already approved this escrow.")]
+    AlreadyApproved,
+    #[msg("The approver has not approved this escrow.")]
+    ApprovalNotFound,
+    #[msg("Not enough approvers have approved the release.")]
+    ApprovalThresholdNotMet,
+    #[msg("The vesting schedule is invalid or ends after the escrow expires.")]
+    InvalidVestingSchedule,
+    #[msg("Streaming escrows are paid out with withdraw_vested.")]
+    StreamingEscrow,
+    #[msg("The escrow is not a streaming escrow.")]
+    NotStreamingEscrow,
+    #[msg("The recipient is not the stream's beneficiary.")]
+    InvalidBeneficiary,
+    #[msg("Nothing has vested since the last withdrawal.")]
+    NothingToWithdraw,
+    #[msg("The allowance settings are invalid.")]
+    InvalidAllowance,
+    #[msg("The withdrawal exceeds the allowance left for this period.")]
+    AllowanceExceeded,
+    #[msg("The escrow is not an allowance escrow.")]
+    NotAllowanceEscrow,
+    #[msg("Allowance escrows are paid out with withdraw_allowance.")]
+    AllowanceEscrow,
+    #[msg("The escrow holds less than the requested amount.")]
+    InsufficientEscrowBalance,
+    #[msg("This instruction is paused.")]
+    ProgramPaused,
+    #[msg("The token mint is not allowed by the mint policy.")]
+    MintNotAllowed,
+    #[msg("The mint is already on the list.")]
+    MintAlreadyListed,
+    #[msg("The mint is not on the list.")]
+    MintNotListed,
+    #[msg("The token mint has extensions or authorities rejected by the risk policy.")]
+    MintRiskRejected,
+    #[msg("The limit settings are invalid.")]
+    InvalidLimits,
+    #[msg("The escrow amount exceeds the cap for this mint.")]
+    EscrowAmountCapExceeded,
+    #[msg("The sender has escrowed the most allowed for this mint in the current window.")]
+    WindowVolumeCapExceeded,
+    #[msg("The sender has reached the maximum number of open escrows.")]
+    TooManyActiveEscrows,
+    #[msg("The memo is longer than the maximum length.")]
+    MemoTooLong,
+    #[msg("The escrow memo account must be passed exactly when a memo is set.")]
+    InvalidMemoAccount,
+    #[msg("The escrow has a memo; the memo account and memo program are required.")]
+    MemoAccountsMissing,
+    #[msg("The escrow is still open.")]
+    EscrowStillOpen,
+    #[msg("The secret must be between 16 and 64 bytes long.")]
+    InvalidSecretLength,
+    #[msg("Sweeping expired escrows is not enabled.")]
+    SweepDisabled,
+    #[msg("The escrow can only be swept once the sweep delay after its expiration has passed.")]
+    NotSweepable,
+    #[msg("The escrow does not exist; it was redeemed, refunded or never created.")]
+    EscrowNotFound,
+    #[msg("The receipt accounts must be passed exactly when a receipt is requested.")]
+    InvalidReceiptAccounts,
+    #[msg("The escrow has a receipt; the receipt mint, its holder's account and the Token-2022 program are required.")]
+    ReceiptAccountsMissing,
+    #[msg("The signer does not hold the escrow's receipt.")]
+    ReceiptNotHeld,
+    #[msg("The escrow has a receipt; only its holder can refund it, with refund_with_receipt.")]
+    RefundRequiresReceipt,
+    #[msg("The escrow has no receipt; it is refunded with refund_funds.")]
+    NoReceipt,
+    #[msg("The escrow has a receipt; it is swept to its holder with sweep_expired_with_receipt.")]
+    SweepRequiresReceipt,
 }
@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+
+use crate::{AmountCaps, MintPolicyMode};
+
+#[event]
+pub struct EscrowCreated {
+    pub escrow: Pubkey,
+    pub sender: Pubkey,
+    /// `NATIVE_MINT_KEY` for SOL escrows.
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub expiration_time: i64,
+    pub memo: Option<String>,
+}
+
+#[event]
+pub struct MintPolicyChanged {
+    pub mode: MintPolicyMode,
+}
+
+#[event]
+pub struct MintListUpdated {
+    pub mint: Pubkey,
+    /// `true` when the mint was added, `false` when it was removed.
+    pub listed: bool,
+}
+
+#[event]
+pub struct MintRiskPolicyChanged {
+    pub reject_mask: u16,
+    pub warn_mask: u16,
+}
+
+#[event]
+pub struct MintRiskDetected {
+    pub escrow: Pubkey,
+    pub mint: Pubkey,
+    /// The flags that matched the warn mask.
+    pub flags: u16,
+}
+
+#[event]
+pub struct MintLimitsChanged {
+    pub mint: Pubkey,
+    pub caps: AmountCaps,
+}
+
+#[event]
+pub struct EscrowSwept {
+    pub escrow: Pubkey,
+    pub sender: Pubkey,
+    pub cranker: Pubkey,
+    /// Lamports paid to the cranker out of the escrow account's rent.
+    pub bounty: u64,
+    /// Holder of the escrow's receipt, who was paid instead of `sender`.
+    pub receipt_holder: Option<Pubkey>,
+}
@@ -0,0 +1,150 @@
+//file: src/instructions/admin.rs
+use anchor_lang::prelude::*;
+
+use crate::error::CustomError;
+use crate::program::InstantSendProgram;
+use crate::{
+    Config, PauseFlags, SweepSettings, WhitelistedProgram, ANCHOR_DISCRIMINATOR_SIZE,
+    CONFIG_VERSION, SEED_CONFIG, SEED_WHITELISTED_PROGRAM,
+};
+
+// The config can only be created by the program's upgrade authority, who picks the admin.
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        init,
+        payer = authority,
+        space = ANCHOR_DISCRIMINATOR_SIZE + Config::INIT_SPACE,
+        seeds = [SEED_CONFIG],
+        bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, InstantSendProgram>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(authority.key()) @ CustomError::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn initialize_config(ctx: Context<InitializeConfig>, admin: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+
+    config.admin = admin;
+    config.bump = ctx.bumps.config;
+    config.version = CONFIG_VERSION;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetAdmin<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [SEED_CONFIG],
+        bump = config.bump,
+        has_one = admin @ CustomError::Unauthorized,
+        constraint = !config.paused.admin @ CustomError::ProgramPaused,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+pub fn set_admin(ctx: Context<SetAdmin>, new_admin: Pubkey) -> Result<()> {
+    ctx.accounts.config.admin = new_admin;
+    Ok(())
+}
+
+// Shares the admin-only accounts of `set_admin`; `Pubkey::default()` removes the guardian.
+pub fn set_guardian(ctx: Context<SetAdmin>, guardian: Pubkey) -> Result<()> {
+    ctx.accounts.config.guardian = guardian;
+    Ok(())
+}
+
+// Shares the admin-only accounts of `set_admin`; 0 removes the cap.
+pub fn set_max_active_escrows(ctx: Context<SetAdmin>, max_active_escrows: u32) -> Result<()> {
+    ctx.accounts.config.max_active_escrows = max_active_escrows;
+    Ok(())
+}
+
+// Shares the admin-only accounts of `set_admin`.
+pub fn set_sweep_settings(ctx: Context<SetAdmin>, settings: SweepSettings) -> Result<()> {
+    ctx.accounts.config.sweep = settings;
+    Ok(())
+}
+
+// Never paused itself, so a paused program can always be resumed.
+#[derive(Accounts)]
+pub struct SetPause<'info> {
+    #[account(
+        constraint = authority.key() == config.admin || authority.key() == config.guardian
+            @ CustomError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    #[account(mut, seeds = [SEED_CONFIG], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+pub fn set_pause(ctx: Context<SetPause>, paused: PauseFlags) -> Result<()> {
+    ctx.accounts.config.paused = paused;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AddWhitelistedProgram<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [SEED_CONFIG],
+        bump = config.bump,
+        has_one = admin @ CustomError::Unauthorized,
+        constraint = !config.paused.admin @ CustomError::ProgramPaused,
+    )]
+    pub config: Account<'info, Config>,
+    /// CHECK: Only its address is recorded; it must be an executable program.
+    #[account(executable)]
+    pub target_program: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = admin,
+        space = ANCHOR_DISCRIMINATOR_SIZE + WhitelistedProgram::INIT_SPACE,
+        seeds = [SEED_WHITELISTED_PROGRAM, target_program.key().as_ref()],
+        bump,
+    )]
+    pub whitelisted_program: Account<'info, WhitelistedProgram>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn add_whitelisted_program(ctx: Context<AddWhitelistedProgram>) -> Result<()> {
+    let whitelisted_program = &mut ctx.accounts.whitelisted_program;
+
+    whitelisted_program.program_id = ctx.accounts.target_program.key();
+    whitelisted_program.bump = ctx.bumps.whitelisted_program;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RemoveWhitelistedProgram<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [SEED_CONFIG],
+        bump = config.bump,
+        has_one = admin @ CustomError::Unauthorized,
+        constraint = !config.paused.admin @ CustomError::ProgramPaused,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [SEED_WHITELISTED_PROGRAM, whitelisted_program.program_id.as_ref()],
+        bump = whitelisted_program.bump,
+        close = admin,
+    )]
+    pub whitelisted_program: Account<'info, WhitelistedProgram>,
+}
+
+pub fn remove_whitelisted_program(_ctx: Context<RemoveWhitelistedProgram>) -> Result<()> {
+    Ok(())
+}
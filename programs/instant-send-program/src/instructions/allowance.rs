@@ -0,0 +1,426 @@
+//file: src/instructions/allowance.rs
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{
+    CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+use crate::error::CustomError;
+use crate::instructions::stream::*;
+use crate::receipt::{check_refund_authority, Receipt};
+use crate::secret::PinProof;
+use crate::{
+    Config, EscrowAccount, EscrowSOLAccount, MintLimits, SenderRegistry, SenderUsage,
+    ANCHOR_DISCRIMINATOR_SIZE, NATIVE_MINT_KEY, SEED_CONFIG, SEED_ESCROW_SOL, SEED_ESCROW_SPL,
+    SEED_MINT_LIMITS, SEED_RECEIPT_MINT, SEED_SENDER_REGISTRY, SEED_SENDER_USAGE,
+};
+
+// Allowance escrows are a pot the recipient draws from at a capped rate. Withdrawals share
+// the streaming accounts: the first one is a full claim that binds the beneficiary. The
+// escrow stays open when drained so the sender can top it up; only the sender, or the receipt
+// holder, closes it.
+pub fn withdraw_allowance_spl<'info>(
+    ctx: Context<'_, '_, '_, 'info, WithdrawVestedSPL<'info>>,
+    secret: Option<&[u8]>,
+    pin: Option<PinProof>,
+    amount: u64,
+) -> Result<()> {
+    require!(
+        !Config::paused(&ctx.accounts.config)?.redeem,
+        CustomError::ProgramPaused
+    );
+    require!(
+        ctx.accounts.escrow_account.allowance.is_set(),
+        CustomError::NotAllowanceEscrow
+    );
+    ctx.accounts
+        .verify_claim(secret, pin.as_ref(), ctx.remaining_accounts)?;
+
+    let escrow_account = &mut ctx.accounts.escrow_account;
+    require!(
+        amount <= escrow_account.remaining(),
+        CustomError::InsufficientEscrowBalance
+    );
+    escrow_account
+        .allowance
+        .withdraw(amount, Clock::get()?.unix_timestamp)?;
+    escrow_account.withdrawn += amount;
+
+    let nonce = ctx.accounts.escrow_account.seed_nonce();
+    let seeds = &[
+        SEED_ESCROW_SPL,
+        ctx.accounts.escrow_account.seed_key(),
+        &nonce,
+        &[ctx.accounts.escrow_account.bump],
+    ];
+    ctx.accounts
+        .transfer_tokens_to_recipient(amount, &[&seeds[..]])
+}
+
+pub fn withdraw_allowance_sol<'info>(
+    ctx: Context<'_, '_, '_, 'info, WithdrawVestedSOL<'info>>,
+    secret: Option<&[u8]>,
+    pin: Option<PinProof>,
+    amount: u64,
+) -> Result<()> {
+    require!(
+        !Config::paused(&ctx.accounts.config)?.redeem,
+        CustomError::ProgramPaused
+    );
+    require!(
+        ctx.accounts.escrow_account.allowance.is_set(),
+        CustomError::NotAllowanceEscrow
+    );
+    ctx.accounts
+        .verify_claim(secret, pin.as_ref(), ctx.remaining_accounts)?;
+
+    let escrow_account = &mut ctx.accounts.escrow_account;
+    require!(
+        amount <= escrow_account.remaining(),
+        CustomError::InsufficientEscrowBalance
+    );
+    escrow_account
+        .allowance
+        .withdraw(amount, Clock::get()?.unix_timestamp)?;
+    escrow_account.withdrawn += amount;
+
+    ctx.accounts.transfer_sol_to_recipient(amount)
+}
+
+#[derive(Accounts)]
+pub struct TopUpSPL<'info> {
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [SEED_ESCROW_SPL, escrow_account.seed_key(), &escrow_account.seed_nonce()],
+        bump = escrow_account.bump,
+        has_one = sender,
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = escrow_account,
+        associated_token::token_program = token_program
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// Any account of `token_mint` owned by the sender, as in `initialize_transfer_spl`.
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = sender,
+        token::token_program = token_program
+    )]
+    pub sender_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = escrow_account.token_mint, mint::token_program = token_program)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: Config PDA with the pause flags; nothing is paused when it does not exist.
+    #[account(seeds = [SEED_CONFIG], bump)]
+    pub config: UncheckedAccount<'info>,
+    /// CHECK: Caps for `token_mint`; the mint is uncapped when it does not exist.
+    #[account(seeds = [SEED_MINT_LIMITS, token_mint.key().as_ref()], bump)]
+    pub mint_limits: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = ANCHOR_DISCRIMINATOR_SIZE + SenderUsage::INIT_SPACE,
+        seeds = [SEED_SENDER_USAGE, sender.key().as_ref(), token_mint.key().as_ref()],
+        bump,
+    )]
+    pub sender_usage: Account<'info, SenderUsage>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn top_up_spl(ctx: Context<TopUpSPL>, amount: u64) -> Result<()> {
+    require!(
+        !Config::paused(&ctx.accounts.config)?.create,
+        CustomError::ProgramPaused
+    );
+    require!(
+        ctx.accounts.escrow_account.allowance.is_set(),
+        CustomError::NotAllowanceEscrow
+    );
+    let escrow_account = &mut ctx.accounts.escrow_account;
+    escrow_account.amount = escrow_account
+        .amount
+        .checked_add(amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    // Top-ups count against the same caps as creates.
+    let caps = MintLimits::caps(&ctx.accounts.mint_limits)?;
+    caps.check_escrow_amount(escrow_account.remaining())?;
+    ctx.accounts.sender_usage.record(
+        *ctx.accounts.sender.key,
+        ctx.accounts.token_mint.key(),
+        ctx.bumps.sender_usage,
+        &caps,
+        amount,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    let cpi_accounts = TransferChecked {
+        from: ctx.accounts.sender_token_account.to_account_info(),
+        to: ctx.accounts.escrow_token_account.to_account_info(),
+        authority: ctx.accounts.sender.to_account_info(),
+        mint: ctx.accounts.token_mint.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+    anchor_spl::token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.token_mint.decimals)
+}
+
+#[derive(Accounts)]
+pub struct TopUpSOL<'info> {
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [SEED_ESCROW_SOL, escrow_account.seed_key(), &escrow_account.seed_nonce()],
+        bump = escrow_account.bump,
+        has_one = sender,
+    )]
+    pub escrow_account: Account<'info, EscrowSOLAccount>,
+    /// CHECK: Config PDA with the pause flags; nothing is paused when it does not exist.
+    #[account(seeds = [SEED_CONFIG], bump)]
+    pub config: UncheckedAccount<'info>,
+    /// CHECK: Caps for SOL escrows, keyed by `NATIVE_MINT_KEY` (the system program id);
+    /// SOL is uncapped when it does not exist.
+    #[account(seeds = [SEED_MINT_LIMITS, system_program.key().as_ref()], bump)]
+    pub mint_limits: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = ANCHOR_DISCRIMINATOR_SIZE + SenderUsage::INIT_SPACE,
+        seeds = [SEED_SENDER_USAGE, sender.key().as_ref(), system_program.key().as_ref()],
+        bump,
+    )]
+    pub sender_usage: Account<'info, SenderUsage>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn top_up_sol(ctx: Context<TopUpSOL>, amount: u64) -> Result<()> {
+    require!(
+        !Config::paused(&ctx.accounts.config)?.create,
+        CustomError::ProgramPaused
+    );
+    require!(
+        ctx.accounts.escrow_account.allowance.is_set(),
+        CustomError::NotAllowanceEscrow
+    );
+    let escrow_account = &mut ctx.accounts.escrow_account;
+    escrow_account.amount = escrow_account
+        .amount
+        .checked_add(amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    // Top-ups count against the same caps as creates.
+    let caps = MintLimits::caps(&ctx.accounts.mint_limits)?;
+    caps.check_escrow_amount(escrow_account.remaining())?;
+    ctx.accounts.sender_usage.record(
+        *ctx.accounts.sender.key,
+        NATIVE_MINT_KEY,
+        ctx.bumps.sender_usage,
+        &caps,
+        amount,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    let cpi_ctx = CpiContext::new(
+        ctx.accounts.system_program.to_account_info(),
+        anchor_lang::system_program::Transfer {
+            from: ctx.accounts.sender.to_account_info(),
+            to: ctx.accounts.escrow_account.to_account_info(),
+        },
+    );
+    anchor_lang::system_program::transfer(cpi_ctx, amount)
+}
+
+#[derive(Accounts)]
+pub struct CloseAllowanceSPL<'info> {
+    /// The sender, or the receipt holder for escrows with a receipt.
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [SEED_ESCROW_SPL, escrow_account.seed_key(), &escrow_account.seed_nonce()],
+        bump = escrow_account.bump,
+        close = sender,
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = escrow_account,
+        associated_token::token_program = token_program
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = sender,
+        associated_token::mint = token_mint,
+        associated_token::authority = sender,
+        associated_token::token_program = token_program
+    )]
+    pub sender_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = escrow_account.token_mint, mint::token_program = token_program)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: The sender's registry PDA.
+    #[account(mut, seeds = [SEED_SENDER_REGISTRY, escrow_account.sender.as_ref()], bump)]
+    pub sender_registry: UncheckedAccount<'info>,
+    /// Only needed when `escrow_account.has_receipt` is set.
+    #[account(mut, seeds = [SEED_RECEIPT_MINT, escrow_account.key().as_ref()], bump)]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+    /// The signer's account holding the receipt.
+    #[account(mut, token::mint = receipt_mint, token::token_program = receipt_token_program)]
+    pub receipt_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub receipt_token_program: Option<Program<'info, Token2022>>,
+    /// CHECK: Config PDA with the pause flags; nothing is paused when it does not exist.
+    #[account(seeds = [SEED_CONFIG], bump)]
+    pub config: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn close_allowance_spl(ctx: Context<CloseAllowanceSPL>) -> Result<()> {
+    require!(
+        !Config::paused(&ctx.accounts.config)?.refund,
+        CustomError::ProgramPaused
+    );
+    require!(
+        ctx.accounts.escrow_account.allowance.is_set(),
+        CustomError::NotAllowanceEscrow
+    );
+    let receipt = Receipt::of(
+        ctx.accounts.escrow_account.has_receipt,
+        ctx.accounts.receipt_mint.as_ref(),
+        ctx.accounts.receipt_account.as_ref(),
+        ctx.accounts.receipt_token_program.as_ref(),
+    )?;
+    check_refund_authority(
+        receipt.as_ref(),
+        &ctx.accounts.escrow_account.sender,
+        ctx.accounts.sender.key,
+    )?;
+
+    let nonce = ctx.accounts.escrow_account.seed_nonce();
+    let seeds = &[
+        SEED_ESCROW_SPL,
+        ctx.accounts.escrow_account.seed_key(),
+        &nonce,
+        &[ctx.accounts.escrow_account.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    // Everything in the vault, not just `remaining()`, or a donation would block the close.
+    let remaining = ctx.accounts.escrow_token_account.amount;
+    if remaining > 0 {
+        anchor_spl::token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.escrow_token_account.to_account_info(),
+                    to: ctx.accounts.sender_token_account.to_account_info(),
+                    authority: ctx.accounts.escrow_account.to_account_info(),
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            remaining,
+            ctx.accounts.token_mint.decimals,
+        )?;
+    }
+    anchor_spl::token_interface::close_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.escrow_token_account.to_account_info(),
+            destination: ctx.accounts.sender.to_account_info(),
+            authority: ctx.accounts.escrow_account.to_account_info(),
+        },
+        signer_seeds,
+    ))?;
+    if let Some(receipt) = &receipt {
+        receipt.burn(
+            ctx.accounts.escrow_account.to_account_info(),
+            signer_seeds,
+            ctx.accounts.sender.to_account_info(),
+        )?;
+    }
+
+    SenderRegistry::unregister(
+        &ctx.accounts.sender_registry,
+        &ctx.accounts.escrow_account.key(),
+    )
+}
+
+// The remaining lamports go back to the sender with the rent when the account is closed.
+#[derive(Accounts)]
+pub struct CloseAllowanceSOL<'info> {
+    /// The sender, or the receipt holder for escrows with a receipt.
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [SEED_ESCROW_SOL, escrow_account.seed_key(), &escrow_account.seed_nonce()],
+        bump = escrow_account.bump,
+        close = sender,
+    )]
+    pub escrow_account: Account<'info, EscrowSOLAccount>,
+    /// CHECK: The sender's registry PDA.
+    #[account(mut, seeds = [SEED_SENDER_REGISTRY, escrow_account.sender.as_ref()], bump)]
+    pub sender_registry: UncheckedAccount<'info>,
+    /// Only needed when `escrow_account.has_receipt` is set.
+    #[account(mut, seeds = [SEED_RECEIPT_MINT, escrow_account.key().as_ref()], bump)]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+    /// The signer's account holding the receipt.
+    #[account(mut, token::mint = receipt_mint, token::token_program = receipt_token_program)]
+    pub receipt_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub receipt_token_program: Option<Program<'info, Token2022>>,
+    /// CHECK: Config PDA with the pause flags; nothing is paused when it does not exist.
+    #[account(seeds = [SEED_CONFIG], bump)]
+    pub config: UncheckedAccount<'info>,
+}
+
+pub fn close_allowance_sol(ctx: Context<CloseAllowanceSOL>) -> Result<()> {
+    require!(
+        !Config::paused(&ctx.accounts.config)?.refund,
+        CustomError::ProgramPaused
+    );
+    require!(
+        ctx.accounts.escrow_account.allowance.is_set(),
+        CustomError::NotAllowanceEscrow
+    );
+    let receipt = Receipt::of(
+        ctx.accounts.escrow_account.has_receipt,
+        ctx.accounts.receipt_mint.as_ref(),
+        ctx.accounts.receipt_account.as_ref(),
+        ctx.accounts.receipt_token_program.as_ref(),
+    )?;
+    check_refund_authority(
+        receipt.as_ref(),
+        &ctx.accounts.escrow_account.sender,
+        ctx.accounts.sender.key,
+    )?;
+    if let Some(receipt) = &receipt {
+        let escrow_account = &ctx.accounts.escrow_account;
+        let nonce = escrow_account.seed_nonce();
+        let seeds = &[
+            SEED_ESCROW_SOL,
+            escrow_account.seed_key(),
+            &nonce,
+            &[escrow_account.bump],
+        ];
+        receipt.burn(
+            escrow_account.to_account_info(),
+            &[&seeds[..]],
+            ctx.accounts.sender.to_account_info(),
+        )?;
+    }
+
+    SenderRegistry::unregister(
+        &ctx.accounts.sender_registry,
+        &ctx.accounts.escrow_account.key(),
+    )
+}
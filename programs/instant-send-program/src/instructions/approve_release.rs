@@ -0,0 +1,56 @@
+//file: src/instructions/approve_release.rs
+use anchor_lang::prelude::*;
+
+use crate::{EscrowAccount, EscrowSOLAccount, SEED_ESCROW_SOL, SEED_ESCROW_SPL};
+
+// Approvals are recorded on the escrow itself and can be revoked until it is redeemed,
+// at which point the escrow is closed.
+#[derive(Accounts)]
+pub struct ApproveReleaseSPL<'info> {
+    pub approver: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [SEED_ESCROW_SPL, escrow_account.seed_key(), &escrow_account.seed_nonce()],
+        bump = escrow_account.bump,
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+}
+
+pub fn approve_release_spl(ctx: Context<ApproveReleaseSPL>) -> Result<()> {
+    ctx.accounts
+        .escrow_account
+        .approvers
+        .approve(ctx.accounts.approver.key)
+}
+
+pub fn revoke_approval_spl(ctx: Context<ApproveReleaseSPL>) -> Result<()> {
+    ctx.accounts
+        .escrow_account
+        .approvers
+        .revoke(ctx.accounts.approver.key)
+}
+
+#[derive(Accounts)]
+pub struct ApproveReleaseSOL<'info> {
+    pub approver: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [SEED_ESCROW_SOL, escrow_account.seed_key(), &escrow_account.seed_nonce()],
+        bump = escrow_account.bump,
+    )]
+    pub escrow_account: Account<'info, EscrowSOLAccount>,
+}
+
+pub fn approve_release_sol(ctx: Context<ApproveReleaseSOL>) -> Result<()> {
+    ctx.accounts
+        .escrow_account
+        .approvers
+        .approve(ctx.accounts.approver.key)
+}
+
+pub fn revoke_approval_sol(ctx: Context<ApproveReleaseSOL>) -> Result<()> {
+    ctx.accounts
+        .escrow_account
+        .approvers
+        .revoke(ctx.accounts.approver.key)
+}
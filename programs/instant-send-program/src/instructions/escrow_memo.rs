@@ -0,0 +1,31 @@
+//file: src/instructions/escrow_memo.rs
+use anchor_lang::prelude::*;
+
+use crate::error::CustomError;
+use crate::{EscrowMemo, SEED_ESCROW_MEMO};
+
+#[derive(Accounts)]
+pub struct CloseEscrowMemo<'info> {
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [SEED_ESCROW_MEMO, escrow_memo.escrow.as_ref()],
+        bump = escrow_memo.bump,
+        has_one = sender,
+        close = sender, // Refunds the memo rent to the sender
+    )]
+    pub escrow_memo: Account<'info, EscrowMemo>,
+    /// CHECK: Only checked to be closed.
+    #[account(address = escrow_memo.escrow)]
+    pub escrow_account: UncheckedAccount<'info>,
+}
+
+pub fn close_escrow_memo(ctx: Context<CloseEscrowMemo>) -> Result<()> {
+    // Redeeming logs the memo from this account, so it has to outlive the escrow.
+    require!(
+        ctx.accounts.escrow_account.data_is_empty(),
+        CustomError::EscrowStillOpen
+    );
+    Ok(())
+}
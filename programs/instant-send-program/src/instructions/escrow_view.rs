@@ -0,0 +1,116 @@
+//file: src/instructions/escrow_view.rs
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+use crate::error::CustomError;
+use crate::secret::hash_secret;
+use crate::{EscrowAccount, EscrowSOLAccount, NATIVE_MINT_KEY};
+
+/// What a claim page needs to show before anyone signs, returned by `get_escrow_status`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct EscrowStatus {
+    pub sender: Pubkey,
+    /// `NATIVE_MINT_KEY` for SOL escrows.
+    pub mint: Pubkey,
+    pub amount: u64,
+    /// What the escrow still holds, after stream and allowance withdrawals.
+    pub remaining: u64,
+    pub expiration_time: i64,
+    /// Seconds until the sender can refund; 0 once expired.
+    pub expires_in: i64,
+    pub is_redeemed: bool,
+    pub pin_required: bool,
+    /// Whether a claim condition program must approve the redemption.
+    pub has_condition: bool,
+    /// Whether every approver the escrow needs has signed off.
+    pub approvals_met: bool,
+    pub is_stream: bool,
+    pub is_allowance: bool,
+    pub has_memo: bool,
+    /// Whether refunds go to the holder of the escrow's receipt rather than the sender.
+    pub has_receipt: bool,
+    /// `MINT_RISK_*` flags found on the mint at creation; always 0 for SOL.
+    pub risk_flags: u16,
+    pub version: u8,
+}
+
+// Shared by the view instructions, which take SPL and SOL escrows alike.
+#[derive(Accounts)]
+pub struct ViewEscrow<'info> {
+    /// CHECK: An SPL or SOL escrow; the owner and discriminator are checked when decoding.
+    pub escrow_account: UncheckedAccount<'info>,
+}
+
+enum AnyEscrow {
+    Spl(EscrowAccount),
+    Sol(EscrowSOLAccount),
+}
+
+impl AnyEscrow {
+    fn load(info: &AccountInfo) -> Result<Self> {
+        require!(
+            info.owner == &crate::ID && !info.data_is_empty(),
+            CustomError::EscrowNotFound
+        );
+        let data = info.try_borrow_data()?;
+        if data.starts_with(&EscrowAccount::DISCRIMINATOR) {
+            Ok(AnyEscrow::Spl(EscrowAccount::try_deserialize(
+                &mut &data[..],
+            )?))
+        } else if data.starts_with(&EscrowSOLAccount::DISCRIMINATOR) {
+            Ok(AnyEscrow::Sol(EscrowSOLAccount::try_deserialize(
+                &mut &data[..],
+            )?))
+        } else {
+            err!(CustomError::EscrowNotFound)
+        }
+    }
+
+    fn hash_of_secret(&self) -> &[u8; 32] {
+        match self {
+            AnyEscrow::Spl(escrow) => &escrow.hash_of_secret,
+            AnyEscrow::Sol(escrow) => &escrow.hash_of_secret,
+        }
+    }
+
+    fn status(&self, now: i64) -> EscrowStatus {
+        // The two layouts share every field but the mint and the risk flags.
+        macro_rules! status {
+            ($escrow:expr, $mint:expr, $risk_flags:expr) => {
+                EscrowStatus {
+                    sender: $escrow.sender,
+                    mint: $mint,
+                    amount: $escrow.amount,
+                    remaining: $escrow.remaining(),
+                    expiration_time: $escrow.expiration_time,
+                    expires_in: $escrow.expiration_time.saturating_sub(now).max(0),
+                    is_redeemed: $escrow.is_redeemed,
+                    pin_required: $escrow.pin_hash != [0u8; 32],
+                    has_condition: $escrow.condition.is_set(),
+                    approvals_met: $escrow.approvers.is_met(),
+                    is_stream: $escrow.vesting.is_set(),
+                    is_allowance: $escrow.allowance.is_set(),
+                    has_memo: $escrow.has_memo,
+                    has_receipt: $escrow.has_receipt,
+                    risk_flags: $risk_flags,
+                    version: $escrow.version,
+                }
+            };
+        }
+        match self {
+            AnyEscrow::Spl(escrow) => status!(escrow, escrow.token_mint, escrow.risk_flags),
+            AnyEscrow::Sol(escrow) => status!(escrow, NATIVE_MINT_KEY, 0),
+        }
+    }
+}
+
+// View instruction: meant to be simulated, the status is returned through return data.
+pub fn get_escrow_status(ctx: Context<ViewEscrow>) -> Result<EscrowStatus> {
+    Ok(AnyEscrow::load(&ctx.accounts.escrow_account)?.status(Clock::get()?.unix_timestamp))
+}
+
+// View instruction: tells whether `secret` opens the escrow without redeeming it. Any length
+// is accepted, so secrets of legacy text links can be checked too.
+pub fn check_secret(ctx: Context<ViewEscrow>, secret: &[u8]) -> Result<bool> {
+    Ok(hash_secret(secret) == *AnyEscrow::load(&ctx.accounts.escrow_account)?.hash_of_secret())
+}
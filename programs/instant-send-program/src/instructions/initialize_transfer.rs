@@ -2,19 +2,132 @@
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
 // use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use anchor_spl::token_2022::Token2022;
 use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked};
 
+use crate::error::CustomError;
+use crate::receipt::Receipt;
 use crate::{
-    EscrowAccount, EscrowSOLAccount, ANCHOR_DISCRIMINATOR_SIZE, SEED_ESCROW_SOL, SEED_ESCROW_SPL,
+    mint_risk_flags, AllowanceSchedule, ApproverSet, ClaimCondition, Config, EscrowAccount,
+    EscrowCreated, EscrowMemo, EscrowSOLAccount, EscrowSeedScheme, MintLimits, MintListPage,
+    MintRiskDetected, RentPolicy, SenderNonce, SenderRegistry, SenderUsage, VestingSchedule,
+    ANCHOR_DISCRIMINATOR_SIZE, ESCROW_VERSION, NATIVE_MINT_KEY, SEED_CONFIG, SEED_ESCROW_MEMO,
+    SEED_ESCROW_SOL, SEED_ESCROW_SPL, SEED_MINT_LIMITS, SEED_MINT_LIST, SEED_RECEIPT_MINT,
+    SEED_SENDER_NONCE, SEED_SENDER_REGISTRY, SEED_SENDER_USAGE,
 };
 
+/// Optional behaviour chosen by the sender when creating an escrow.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct EscrowOptions {
+    pub rent_policy: RentPolicy,
+    /// Second factor delivered separately from the link: `sha256(secret || salt || pin)`.
+    pub pin_hash: Option<[u8; 32]>,
+    /// Program whose `check` instruction must succeed before the escrow can be redeemed.
+    pub condition: Option<ClaimCondition>,
+    /// Approvers who must sign off with `approve_release_*` before the escrow can be redeemed.
+    pub approval: Option<ApprovalPolicy>,
+    /// Releases the amount linearly through `withdraw_vested_*` instead of all at once.
+    pub vesting: Option<VestingOptions>,
+    /// Lets the recipient draw up to a cap per period through `withdraw_allowance_*`.
+    pub allowance: Option<AllowanceOptions>,
+    /// Note for the recipient, up to `MAX_MEMO_LEN` bytes; requires the `escrow_memo` account.
+    pub memo: Option<String>,
+    /// Mints a receipt token to the sender; its holder refunds and cancels the escrow instead
+    /// of the sender. Requires the receipt accounts, see `crate::receipt`.
+    pub receipt: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ApprovalPolicy {
+    pub approvers: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct VestingOptions {
+    pub start_time: i64,
+    pub end_time: i64,
+    pub cliff_time: Option<i64>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AllowanceOptions {
+    pub period_seconds: i64,
+    pub per_period_cap: u64,
+}
+
+impl EscrowOptions {
+    pub fn approver_set(&self) -> Result<ApproverSet> {
+        match &self.approval {
+            Some(policy) => ApproverSet::new(&policy.approvers, policy.threshold),
+            None => Ok(ApproverSet::default()),
+        }
+    }
+
+    pub fn vesting_schedule(&self, expiration_time: i64) -> Result<VestingSchedule> {
+        match &self.vesting {
+            Some(vesting) => VestingSchedule::new(
+                vesting.start_time,
+                vesting.cliff_time,
+                vesting.end_time,
+                expiration_time,
+            ),
+            None => Ok(VestingSchedule::default()),
+        }
+    }
+
+    pub fn allowance_schedule(&self) -> Result<AllowanceSchedule> {
+        match &self.allowance {
+            // Streams and allowances pay out differently, an escrow can only be one of them.
+            Some(_) if self.vesting.is_some() => err!(CustomError::InvalidAllowance),
+            Some(allowance) => AllowanceSchedule::new(
+                allowance.period_seconds,
+                allowance.per_period_cap,
+                Clock::get()?.unix_timestamp,
+            ),
+            None => Ok(AllowanceSchedule::default()),
+        }
+    }
+
+    pub fn memo_len(&self) -> usize {
+        self.memo.as_ref().map_or(0, String::len)
+    }
+}
+
 #[derive(Accounts)]
-#[instruction(amount: u64, expiration_time: i64, hash_of_secret: [u8; 32])]
+#[instruction(amount: u64, expiration_time: i64, hash_of_secret: [u8; 32], options: EscrowOptions)]
 pub struct InitializeTransferSPL<'info> {
+    /// Funds the escrow and pays its rent. A program can send from a PDA it signs for with
+    /// `invoke_signed`, as long as the PDA is a system account holding lamports.
     #[account(mut)]
     pub sender: Signer<'info>,
-    #[account(init, payer = sender, space = ANCHOR_DISCRIMINATOR_SIZE + EscrowAccount::INIT_SPACE, seeds = [SEED_ESCROW_SPL, &hash_of_secret[..]], bump)]
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = ANCHOR_DISCRIMINATOR_SIZE + SenderNonce::INIT_SPACE,
+        seeds = [SEED_SENDER_NONCE, sender.key().as_ref()],
+        bump,
+    )]
+    pub sender_nonce: Account<'info, SenderNonce>,
+    /// CHECK: The sender's registry PDA, created on first use by `SenderRegistry::register`.
+    #[account(mut, seeds = [SEED_SENDER_REGISTRY, sender.key().as_ref()], bump)]
+    pub sender_registry: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = sender,
+        space = ANCHOR_DISCRIMINATOR_SIZE + EscrowAccount::INIT_SPACE,
+        seeds = [SEED_ESCROW_SPL, sender.key().as_ref(), &sender_nonce.next_nonce.to_le_bytes()],
+        bump,
+    )]
     pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        init,
+        payer = sender,
+        space = EscrowMemo::space(options.memo_len()),
+        seeds = [SEED_ESCROW_MEMO, escrow_account.key().as_ref()],
+        bump,
+    )]
+    pub escrow_memo: Option<Account<'info, EscrowMemo>>,
     #[account(
         init,
         payer = sender,
@@ -23,17 +136,82 @@ pub struct InitializeTransferSPL<'info> {
         associated_token::token_program = token_program
     )]
     pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
-    #[account(mut, associated_token::mint = token_mint, associated_token::authority = sender, associated_token::token_program = token_program)]
+    /// Any account of `token_mint` owned by the sender, not only its associated one, so
+    /// program treasuries can fund escrows from the accounts they already hold.
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = sender,
+        token::token_program = token_program
+    )]
     pub sender_token_account: InterfaceAccount<'info, TokenAccount>,
     #[account(mint::token_program = token_program)]
     pub token_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: Config PDA with the pause flags; nothing is paused when it does not exist.
+    #[account(seeds = [SEED_CONFIG], bump)]
+    pub config: UncheckedAccount<'info>,
+    /// CHECK: The mint list page for `token_mint`; may not exist when the mint is not listed.
+    #[account(seeds = [SEED_MINT_LIST, &MintListPage::bucket(&token_mint.key())], bump)]
+    pub mint_list_page: UncheckedAccount<'info>,
+    /// CHECK: Caps for `token_mint`; the mint is uncapped when it does not exist.
+    #[account(seeds = [SEED_MINT_LIMITS, token_mint.key().as_ref()], bump)]
+    pub mint_limits: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = ANCHOR_DISCRIMINATOR_SIZE + SenderUsage::INIT_SPACE,
+        seeds = [SEED_SENDER_USAGE, sender.key().as_ref(), token_mint.key().as_ref()],
+        bump,
+    )]
+    pub sender_usage: Account<'info, SenderUsage>,
     pub system_program: Program<'info, System>,
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub rent: Sysvar<'info, Rent>,
+    /// Only passed with `options.receipt`: the escrow's receipt mint, see `crate::receipt`.
+    #[account(
+        init,
+        payer = sender,
+        seeds = [SEED_RECEIPT_MINT, escrow_account.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = escrow_account,
+        mint::token_program = receipt_token_program,
+        extensions::permanent_delegate::delegate = escrow_account,
+        extensions::close_authority::authority = escrow_account,
+    )]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+    /// The sender's associated account for the receipt.
+    #[account(
+        init,
+        payer = sender,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = sender,
+        associated_token::token_program = receipt_token_program
+    )]
+    pub receipt_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub receipt_token_program: Option<Program<'info, Token2022>>,
 }
 
 impl<'info> InitializeTransferSPL<'info> {
+    /// Applies the config's risk policy to `token_mint` and returns its risk flags.
+    pub fn screen_mint(&self) -> Result<u16> {
+        let flags = mint_risk_flags(&self.token_mint)?;
+        let (reject_mask, warn_mask) = Config::load(&self.config)?
+            .map(|config| (config.risk_reject_mask, config.risk_warn_mask))
+            .unwrap_or_default();
+
+        require!(flags & reject_mask == 0, CustomError::MintRiskRejected);
+        if flags & warn_mask != 0 {
+            emit!(MintRiskDetected {
+                escrow: self.escrow_account.key(),
+                mint: self.token_mint.key(),
+                flags: flags & warn_mask,
+            });
+        }
+        Ok(flags)
+    }
+
     pub fn into_transfer_to_escrow_context(
         &self,
     ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
@@ -70,7 +248,35 @@ pub fn initialize_transfer_spl(
     amount: u64,
     expiration_time: i64,
     hash_of_secret: [u8; 32],
-) -> Result<()> {
+    options: EscrowOptions,
+) -> Result<Pubkey> {
+    require!(
+        !Config::paused(&ctx.accounts.config)?.create,
+        CustomError::ProgramPaused
+    );
+    MintListPage::check_mint(
+        &ctx.accounts.config,
+        &ctx.accounts.mint_list_page,
+        &ctx.accounts.token_mint.key(),
+    )?;
+    let risk_flags = ctx.accounts.screen_mint()?;
+    let caps = MintLimits::caps(&ctx.accounts.mint_limits)?;
+    caps.check_escrow_amount(amount)?;
+    ctx.accounts.sender_usage.record(
+        *ctx.accounts.sender.key,
+        ctx.accounts.token_mint.key(),
+        ctx.bumps.sender_usage,
+        &caps,
+        amount,
+        Clock::get()?.unix_timestamp,
+    )?;
+    let has_memo = EscrowMemo::write(
+        ctx.accounts.escrow_memo.as_mut(),
+        ctx.bumps.escrow_memo,
+        ctx.accounts.escrow_account.key(),
+        *ctx.accounts.sender.key,
+        options.memo.as_ref(),
+    )?;
     let escrow_account = &mut ctx.accounts.escrow_account;
 
     escrow_account.sender = *ctx.accounts.sender.key;
@@ -80,6 +286,46 @@ pub fn initialize_transfer_spl(
     escrow_account.token_mint = ctx.accounts.token_mint.key();
     escrow_account.hash_of_secret = hash_of_secret;
     escrow_account.bump = ctx.bumps.escrow_account;
+    escrow_account.version = ESCROW_VERSION;
+    escrow_account.seed_scheme = EscrowSeedScheme::SenderNonce;
+    escrow_account.nonce = ctx.accounts.sender_nonce.next_nonce;
+    escrow_account.rent_policy = options.rent_policy;
+    escrow_account.pin_hash = options.pin_hash.unwrap_or_default();
+    escrow_account.condition = options.condition.unwrap_or_default();
+    escrow_account.approvers = options.approver_set()?;
+    escrow_account.vesting = options.vesting_schedule(expiration_time)?;
+    escrow_account.allowance = options.allowance_schedule()?;
+    escrow_account.risk_flags = risk_flags;
+    escrow_account.has_memo = has_memo;
+
+    let seeds = &[
+        SEED_ESCROW_SPL,
+        escrow_account.seed_key(),
+        &escrow_account.seed_nonce(),
+        &[escrow_account.bump],
+    ];
+    escrow_account.has_receipt = Receipt::issue(
+        options.receipt,
+        ctx.accounts.receipt_mint.as_ref(),
+        ctx.accounts.receipt_account.as_ref(),
+        ctx.accounts.receipt_token_program.as_ref(),
+        escrow_account.to_account_info(),
+        &[&seeds[..]],
+    )?;
+
+    ctx.accounts
+        .sender_nonce
+        .consume(*ctx.accounts.sender.key, ctx.bumps.sender_nonce)?;
+
+    let active_escrows = SenderRegistry::register(
+        &ctx.accounts.sender_registry,
+        ctx.accounts.sender.key,
+        ctx.bumps.sender_registry,
+        ctx.accounts.escrow_account.key(),
+        ctx.accounts.sender.to_account_info(),
+        ctx.accounts.system_program.to_account_info(),
+    )?;
+    Config::check_active_escrows(&ctx.accounts.config, active_escrows)?;
 
     anchor_spl::token_interface::transfer_checked(
         ctx.accounts.into_transfer_to_escrow_context(),
@@ -87,22 +333,97 @@ pub fn initialize_transfer_spl(
         ctx.accounts.token_mint.decimals,
     )?;
 
+    emit!(EscrowCreated {
+        escrow: ctx.accounts.escrow_account.key(),
+        sender: *ctx.accounts.sender.key,
+        mint: ctx.accounts.token_mint.key(),
+        amount,
+        expiration_time,
+        memo: options.memo,
+    });
+
     //if you for some reason want to claim the rent for creating a token account for the reciever
     //ctx.accounts.claim_rent_from_sender()?;
 
-    Ok(())
+    Ok(ctx.accounts.escrow_account.key())
 }
 
 // Account structures for SOL
 #[derive(Accounts)]
-#[instruction(amount: u64, expiration_time: i64, hash_of_secret: [u8; 32])]
+#[instruction(amount: u64, expiration_time: i64, hash_of_secret: [u8; 32], options: EscrowOptions)]
 pub struct InitializeTransferSOL<'info> {
+    /// Funds the escrow and pays its rent; may be a PDA, see `InitializeTransferSPL::sender`.
     #[account(mut)]
     pub sender: Signer<'info>,
-    #[account(init, payer = sender, space = ANCHOR_DISCRIMINATOR_SIZE + EscrowSOLAccount::INIT_SPACE, seeds = [SEED_ESCROW_SOL, &hash_of_secret[..]], bump)]
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = ANCHOR_DISCRIMINATOR_SIZE + SenderNonce::INIT_SPACE,
+        seeds = [SEED_SENDER_NONCE, sender.key().as_ref()],
+        bump,
+    )]
+    pub sender_nonce: Account<'info, SenderNonce>,
+    /// CHECK: The sender's registry PDA, created on first use by `SenderRegistry::register`.
+    #[account(mut, seeds = [SEED_SENDER_REGISTRY, sender.key().as_ref()], bump)]
+    pub sender_registry: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = sender,
+        space = ANCHOR_DISCRIMINATOR_SIZE + EscrowSOLAccount::INIT_SPACE,
+        seeds = [SEED_ESCROW_SOL, sender.key().as_ref(), &sender_nonce.next_nonce.to_le_bytes()],
+        bump,
+    )]
     pub escrow_account: Account<'info, EscrowSOLAccount>,
+    #[account(
+        init,
+        payer = sender,
+        space = EscrowMemo::space(options.memo_len()),
+        seeds = [SEED_ESCROW_MEMO, escrow_account.key().as_ref()],
+        bump,
+    )]
+    pub escrow_memo: Option<Account<'info, EscrowMemo>>,
+    /// CHECK: Config PDA with the pause flags; nothing is paused when it does not exist.
+    #[account(seeds = [SEED_CONFIG], bump)]
+    pub config: UncheckedAccount<'info>,
+    /// CHECK: Caps for SOL escrows, keyed by `NATIVE_MINT_KEY` (the system program id);
+    /// SOL is uncapped when it does not exist.
+    #[account(seeds = [SEED_MINT_LIMITS, system_program.key().as_ref()], bump)]
+    pub mint_limits: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = ANCHOR_DISCRIMINATOR_SIZE + SenderUsage::INIT_SPACE,
+        seeds = [SEED_SENDER_USAGE, sender.key().as_ref(), system_program.key().as_ref()],
+        bump,
+    )]
+    pub sender_usage: Account<'info, SenderUsage>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
+    /// Only passed with `options.receipt`: the escrow's receipt mint, see `crate::receipt`.
+    #[account(
+        init,
+        payer = sender,
+        seeds = [SEED_RECEIPT_MINT, escrow_account.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = escrow_account,
+        mint::token_program = receipt_token_program,
+        extensions::permanent_delegate::delegate = escrow_account,
+        extensions::close_authority::authority = escrow_account,
+    )]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+    /// The sender's associated account for the receipt.
+    #[account(
+        init,
+        payer = sender,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = sender,
+        associated_token::token_program = receipt_token_program
+    )]
+    pub receipt_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub receipt_token_program: Option<Program<'info, Token2022>>,
+    /// Only needed with `options.receipt`, to create `receipt_account`.
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
 }
 
 impl<'info> InitializeTransferSOL<'info> {
@@ -122,7 +443,29 @@ pub fn initialize_transfer_sol(
     amount: u64,
     expiration_time: i64,
     hash_of_secret: [u8; 32],
-) -> Result<()> {
+    options: EscrowOptions,
+) -> Result<Pubkey> {
+    require!(
+        !Config::paused(&ctx.accounts.config)?.create,
+        CustomError::ProgramPaused
+    );
+    let caps = MintLimits::caps(&ctx.accounts.mint_limits)?;
+    caps.check_escrow_amount(amount)?;
+    ctx.accounts.sender_usage.record(
+        *ctx.accounts.sender.key,
+        NATIVE_MINT_KEY,
+        ctx.bumps.sender_usage,
+        &caps,
+        amount,
+        Clock::get()?.unix_timestamp,
+    )?;
+    let has_memo = EscrowMemo::write(
+        ctx.accounts.escrow_memo.as_mut(),
+        ctx.bumps.escrow_memo,
+        ctx.accounts.escrow_account.key(),
+        *ctx.accounts.sender.key,
+        options.memo.as_ref(),
+    )?;
     let escrow_account = &mut ctx.accounts.escrow_account;
 
     escrow_account.sender = *ctx.accounts.sender.key;
@@ -131,8 +474,56 @@ pub fn initialize_transfer_sol(
     escrow_account.is_redeemed = false;
     escrow_account.hash_of_secret = hash_of_secret;
     escrow_account.bump = ctx.bumps.escrow_account;
+    escrow_account.version = ESCROW_VERSION;
+    escrow_account.seed_scheme = EscrowSeedScheme::SenderNonce;
+    escrow_account.nonce = ctx.accounts.sender_nonce.next_nonce;
+    escrow_account.rent_policy = options.rent_policy;
+    escrow_account.pin_hash = options.pin_hash.unwrap_or_default();
+    escrow_account.condition = options.condition.unwrap_or_default();
+    escrow_account.approvers = options.approver_set()?;
+    escrow_account.vesting = options.vesting_schedule(expiration_time)?;
+    escrow_account.allowance = options.allowance_schedule()?;
+    escrow_account.has_memo = has_memo;
+
+    let seeds = &[
+        SEED_ESCROW_SOL,
+        escrow_account.seed_key(),
+        &escrow_account.seed_nonce(),
+        &[escrow_account.bump],
+    ];
+    escrow_account.has_receipt = Receipt::issue(
+        options.receipt,
+        ctx.accounts.receipt_mint.as_ref(),
+        ctx.accounts.receipt_account.as_ref(),
+        ctx.accounts.receipt_token_program.as_ref(),
+        escrow_account.to_account_info(),
+        &[&seeds[..]],
+    )?;
+
+    ctx.accounts
+        .sender_nonce
+        .consume(*ctx.accounts.sender.key, ctx.bumps.sender_nonce)?;
+
+    let active_escrows = SenderRegistry::register(
+        &ctx.accounts.sender_registry,
+        ctx.accounts.sender.key,
+        ctx.bumps.sender_registry,
+        ctx.accounts.escrow_account.key(),
+        ctx.accounts.sender.to_account_info(),
+        ctx.accounts.system_program.to_account_info(),
+    )?;
+    Config::check_active_escrows(&ctx.accounts.config, active_escrows)?;
 
     anchor_lang::system_program::transfer(ctx.accounts.into_transfer_sol_context(), amount)?;
 
-    Ok(())
+    emit!(EscrowCreated {
+        escrow: ctx.accounts.escrow_account.key(),
+        sender: *ctx.accounts.sender.key,
+        mint: NATIVE_MINT_KEY,
+        amount,
+        expiration_time,
+        memo: options.memo,
+    });
+
+    Ok(ctx.accounts.escrow_account.key())
 }
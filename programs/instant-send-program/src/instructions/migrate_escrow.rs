@@ -0,0 +1,67 @@
+//file: src/instructions/migrate_escrow.rs
+use anchor_lang::prelude::*;
+
+use crate::error::CustomError;
+use crate::{
+    EscrowAccount, EscrowSOLAccount, ANCHOR_DISCRIMINATOR_SIZE, ESCROW_VERSION, SEED_ESCROW_SOL,
+    SEED_ESCROW_SPL,
+};
+
+// Migration is permissionless: it only grows the account to the current layout and bumps
+// `version`. Whoever calls it pays the rent difference and does not get it back: closing the
+// escrow sends all of its rent to the receiver its rent policy picks.
+#[derive(Accounts)]
+pub struct MigrateEscrowSPL<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [SEED_ESCROW_SPL, escrow_account.seed_key(), &escrow_account.seed_nonce()],
+        bump = escrow_account.bump,
+        realloc = ANCHOR_DISCRIMINATOR_SIZE + EscrowAccount::INIT_SPACE,
+        realloc::payer = payer,
+        realloc::zero = true,
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn migrate_escrow_spl(ctx: Context<MigrateEscrowSPL>) -> Result<()> {
+    let escrow_account = &mut ctx.accounts.escrow_account;
+
+    require!(
+        escrow_account.version < ESCROW_VERSION,
+        CustomError::AlreadyMigrated
+    );
+    escrow_account.version = ESCROW_VERSION;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct MigrateEscrowSOL<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [SEED_ESCROW_SOL, escrow_account.seed_key(), &escrow_account.seed_nonce()],
+        bump = escrow_account.bump,
+        realloc = ANCHOR_DISCRIMINATOR_SIZE + EscrowSOLAccount::INIT_SPACE,
+        realloc::payer = payer,
+        realloc::zero = true,
+    )]
+    pub escrow_account: Account<'info, EscrowSOLAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn migrate_escrow_sol(ctx: Context<MigrateEscrowSOL>) -> Result<()> {
+    let escrow_account = &mut ctx.accounts.escrow_account;
+
+    require!(
+        escrow_account.version < ESCROW_VERSION,
+        CustomError::AlreadyMigrated
+    );
+    escrow_account.version = ESCROW_VERSION;
+
+    Ok(())
+}
@@ -0,0 +1,172 @@
+//file: src/instructions/mint_policy.rs
+use anchor_lang::prelude::*;
+
+use crate::error::CustomError;
+use crate::{
+    AmountCaps, Config, MintLimits, MintLimitsChanged, MintListPage, MintListUpdated,
+    MintPolicyChanged, MintPolicyMode, MintRiskPolicyChanged, ANCHOR_DISCRIMINATOR_SIZE,
+    SEED_CONFIG, SEED_MINT_LIMITS, SEED_MINT_LIST,
+};
+
+#[derive(Accounts)]
+pub struct SetMintPolicy<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [SEED_CONFIG],
+        bump = config.bump,
+        has_one = admin @ CustomError::Unauthorized,
+        constraint = !config.paused.admin @ CustomError::ProgramPaused,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+pub fn set_mint_policy(ctx: Context<SetMintPolicy>, mode: MintPolicyMode) -> Result<()> {
+    ctx.accounts.config.mint_policy = mode;
+    emit!(MintPolicyChanged { mode });
+    Ok(())
+}
+
+// Shares the admin-only accounts of `set_mint_policy`.
+pub fn set_mint_risk_policy(
+    ctx: Context<SetMintPolicy>,
+    reject_mask: u16,
+    warn_mask: u16,
+) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.risk_reject_mask = reject_mask;
+    config.risk_warn_mask = warn_mask;
+    emit!(MintRiskPolicyChanged {
+        reject_mask,
+        warn_mask
+    });
+    Ok(())
+}
+
+// The same list backs both modes; switching modes does not clear it.
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct AddListedMint<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [SEED_CONFIG],
+        bump = config.bump,
+        has_one = admin @ CustomError::Unauthorized,
+        constraint = !config.paused.admin @ CustomError::ProgramPaused,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = MintListPage::space(0),
+        seeds = [SEED_MINT_LIST, &MintListPage::bucket(&mint)],
+        bump,
+    )]
+    pub mint_list_page: Account<'info, MintListPage>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn add_listed_mint(ctx: Context<AddListedMint>, mint: Pubkey) -> Result<()> {
+    let page = &mut ctx.accounts.mint_list_page;
+    require!(!page.mints.contains(&mint), CustomError::MintAlreadyListed);
+
+    page.bucket = MintListPage::bucket(&mint)[0];
+    page.bump = ctx.bumps.mint_list_page;
+    page.mints.push(mint);
+
+    // Grow the page by one slot, with rent paid by the admin.
+    let info = page.to_account_info();
+    let space = MintListPage::space(page.mints.len());
+    if info.data_len() < space {
+        let rent_due = Rent::get()?
+            .minimum_balance(space)
+            .saturating_sub(info.lamports());
+        if rent_due > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.admin.to_account_info(),
+                        to: info.clone(),
+                    },
+                ),
+                rent_due,
+            )?;
+        }
+        info.realloc(space, false)?;
+    }
+
+    emit!(MintListUpdated { mint, listed: true });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct RemoveListedMint<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [SEED_CONFIG],
+        bump = config.bump,
+        has_one = admin @ CustomError::Unauthorized,
+        constraint = !config.paused.admin @ CustomError::ProgramPaused,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [SEED_MINT_LIST, &MintListPage::bucket(&mint)],
+        bump = mint_list_page.bump,
+    )]
+    pub mint_list_page: Account<'info, MintListPage>,
+}
+
+pub fn remove_listed_mint(ctx: Context<RemoveListedMint>, mint: Pubkey) -> Result<()> {
+    let mints = &mut ctx.accounts.mint_list_page.mints;
+    let index = mints
+        .iter()
+        .position(|listed| *listed == mint)
+        .ok_or(CustomError::MintNotListed)?;
+    // The page keeps its size, so the freed slot is reused by the next add.
+    mints.swap_remove(index);
+
+    emit!(MintListUpdated {
+        mint,
+        listed: false
+    });
+    Ok(())
+}
+
+// SOL escrows are capped through `NATIVE_MINT_KEY`; all-zero caps lift every cap.
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct SetMintLimits<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [SEED_CONFIG],
+        bump = config.bump,
+        has_one = admin @ CustomError::Unauthorized,
+        constraint = !config.paused.admin @ CustomError::ProgramPaused,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = ANCHOR_DISCRIMINATOR_SIZE + MintLimits::INIT_SPACE,
+        seeds = [SEED_MINT_LIMITS, mint.as_ref()],
+        bump,
+    )]
+    pub mint_limits: Account<'info, MintLimits>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn set_mint_limits(ctx: Context<SetMintLimits>, mint: Pubkey, caps: AmountCaps) -> Result<()> {
+    caps.validate()?;
+    let mint_limits = &mut ctx.accounts.mint_limits;
+    mint_limits.mint = mint;
+    mint_limits.bump = ctx.bumps.mint_limits;
+    mint_limits.caps = caps;
+
+    emit!(MintLimitsChanged { mint, caps });
+    Ok(())
+}
@@ -6,3 +6,36 @@ pub use redeem_funds::*;
 
 pub mod refund_funds;
 pub use refund_funds::*;
+
+pub mod migrate_escrow;
+pub use migrate_escrow::*;
+
+pub mod sender_registry;
+pub use sender_registry::*;
+
+pub mod admin;
+pub use admin::*;
+
+pub mod redeem_and_invoke;
+pub use redeem_and_invoke::*;
+
+pub mod approve_release;
+pub use approve_release::*;
+
+pub mod stream;
+pub use stream::*;
+
+pub mod allowance;
+pub use allowance::*;
+
+pub mod mint_policy;
+pub use mint_policy::*;
+
+pub mod escrow_memo;
+pub use escrow_memo::*;
+
+pub mod sweep_expired;
+pub use sweep_expired::*;
+
+pub mod escrow_view;
+pub use escrow_view::*;
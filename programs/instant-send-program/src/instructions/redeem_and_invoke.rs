@@ -0,0 +1,91 @@
+//file: src/instructions/redeem_and_invoke.rs
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke;
+use anchor_spl::token_interface::TokenAccount;
+
+use crate::error::CustomError;
+use crate::instructions::redeem_funds::*;
+use crate::secret::PinProof;
+use crate::{WhitelistedProgram, SEED_WHITELISTED_PROGRAM};
+
+// Redeems into the recipient's token account, then lets the recipient hand the tokens to a
+// whitelisted program (e.g. a swap or savings vault) in the same instruction.
+#[derive(Accounts)]
+pub struct RedeemAndInvoke<'info> {
+    #[account(constraint = redeem.recipient.is_signer @ CustomError::RecipientMustSign)]
+    pub redeem: RedeemFundsSPL<'info>,
+    #[account(
+        seeds = [SEED_WHITELISTED_PROGRAM, invoke_program.key().as_ref()],
+        bump = whitelisted_program.bump,
+    )]
+    pub whitelisted_program: Account<'info, WhitelistedProgram>,
+    /// CHECK: Must be executable and whitelisted, which the seeds above enforce.
+    #[account(executable)]
+    pub invoke_program: UncheckedAccount<'info>,
+    #[account(mut, token::authority = redeem.recipient)]
+    pub output_token_account: InterfaceAccount<'info, TokenAccount>,
+}
+
+impl<'info> RedeemAndInvoke<'info> {
+    /// Calls the whitelisted program with the caller's data and `remaining_accounts`.
+    /// Only the signatures already present in the transaction are forwarded; the escrow
+    /// PDA never signs this call.
+    pub fn invoke_whitelisted_program(
+        &self,
+        data: Vec<u8>,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let instruction = Instruction {
+            program_id: self.invoke_program.key(),
+            accounts: remaining_accounts
+                .iter()
+                .map(|account| AccountMeta {
+                    pubkey: account.key(),
+                    is_signer: account.is_signer,
+                    is_writable: account.is_writable,
+                })
+                .collect(),
+            data,
+        };
+        let mut account_infos = remaining_accounts.to_vec();
+        account_infos.push(self.invoke_program.to_account_info());
+        invoke(&instruction, &account_infos)?;
+        Ok(())
+    }
+}
+
+pub fn redeem_and_invoke<'info>(
+    ctx: Context<'_, '_, '_, 'info, RedeemAndInvoke<'info>>,
+    secret: &[u8],
+    pin: Option<PinProof>,
+    invoke_data: Vec<u8>,
+    min_output: u64,
+    condition_accounts: u8,
+) -> Result<()> {
+    // The first `condition_accounts` remaining accounts go to the claim condition,
+    // the rest to the whitelisted program.
+    let split = (condition_accounts as usize).min(ctx.remaining_accounts.len());
+    let (condition_remaining, invoke_remaining) = ctx.remaining_accounts.split_at(split);
+
+    ctx.accounts
+        .redeem
+        .redeem(secret, pin.as_ref(), condition_remaining)?;
+
+    // Measured from after the redeem, so that redeemed tokens landing in the output account
+    // (when it is the recipient's account for the escrowed mint) can't count as output.
+    ctx.accounts.output_token_account.reload()?;
+    let output_before = ctx.accounts.output_token_account.amount;
+    ctx.accounts
+        .invoke_whitelisted_program(invoke_data, invoke_remaining)?;
+
+    ctx.accounts.output_token_account.reload()?;
+    let received = ctx
+        .accounts
+        .output_token_account
+        .amount
+        .saturating_sub(output_before);
+    require!(received >= min_output, CustomError::InsufficientOutput);
+
+    Ok(())
+}
@@ -1,29 +1,79 @@
 //file: src/instructions/redeem_funds.rs
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::memo::Memo;
+use anchor_spl::token_2022::Token2022;
 use anchor_spl::token_interface::{
     CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
 };
-use sha2::{Digest, Sha256};
 
 use crate::error::CustomError;
-use crate::{EscrowAccount, EscrowSOLAccount, SEED_ESCROW_SOL, SEED_ESCROW_SPL};
+use crate::instructions::stream::{WithdrawVestedSOL, WithdrawVestedSPL};
+use crate::receipt::Receipt;
+use crate::secret::{verify_pin, verify_secret, PinProof};
+use crate::{
+    Config, EscrowAccount, EscrowMemo, EscrowSOLAccount, SenderRegistry, SEED_CONFIG,
+    SEED_ESCROW_MEMO, SEED_ESCROW_SOL, SEED_ESCROW_SPL, SEED_RECEIPT_MINT, SEED_SENDER_REGISTRY,
+};
+
+// The claim checks shared by redeems and the first withdrawal from streams and allowances;
+// each accounts struct holds the escrow, its recipient and the condition accounts.
+macro_rules! claim_checks {
+    ($($accounts:ident),+) => {$(
+        impl<'info> $accounts<'info> {
+            pub fn verify_secret(&self, secret: &[u8]) -> Result<()> {
+                verify_secret(&self.escrow_account.hash_of_secret, secret)
+            }
+
+            pub fn verify_pin(&self, secret: &[u8], pin: Option<&PinProof>) -> Result<()> {
+                verify_pin(secret, &self.escrow_account.pin_hash, pin)
+            }
+
+            pub fn verify_condition(
+                &self,
+                remaining_accounts: &[AccountInfo<'info>],
+            ) -> Result<()> {
+                self.escrow_account.condition.check(
+                    self.condition_program.as_ref(),
+                    self.condition_config.as_ref(),
+                    self.escrow_account.to_account_info(),
+                    self.recipient.to_account_info(),
+                    remaining_accounts,
+                )
+            }
+        }
+    )+};
+}
+
+claim_checks!(
+    RedeemFundsSPL,
+    RedeemFundsSOL,
+    WithdrawVestedSPL,
+    WithdrawVestedSOL
+);
 
 #[derive(Accounts)]
 pub struct RedeemFundsSPL<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
-    /// CHECK: The recipient account is provided by the caller and verified in the program logic.
+    /// CHECK: Chosen by whoever holds the secret; `recipient_token_account` must be its
+    /// associated token account, owned by it.
     #[account(mut)]
     pub recipient: AccountInfo<'info>,
     #[account(
         mut,
-        seeds = [SEED_ESCROW_SPL, &escrow_account.hash_of_secret],
+        seeds = [SEED_ESCROW_SPL, escrow_account.seed_key(), &escrow_account.seed_nonce()],
         bump = escrow_account.bump,
-        close = recipient, //we return the rent-exempt balance to the reciever, such that have enough sol, to make an inital transaction with their spl tokens.
+        close = rent_receiver,
     )]
     pub escrow_account: Account<'info, EscrowAccount>,
-    #[account(mut)]
+    /// The vault: the escrow PDA's associated token account for `token_mint`.
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = escrow_account,
+        associated_token::token_program = token_program
+    )]
     pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
     #[account(
         init_if_needed,
@@ -38,6 +88,35 @@ pub struct RedeemFundsSPL<'info> {
     #[account(mut, address = escrow_account.sender)]
     /// CHECK: This is safe because we check the address
     pub sender: AccountInfo<'info>,
+    /// CHECK: Receives the reclaimed rent; checked against the escrow's rent policy.
+    #[account(
+        mut,
+        address = escrow_account.rent_policy.receiver(&escrow_account.sender, recipient.key, signer.key)
+            @ CustomError::InvalidRentReceiver
+    )]
+    pub rent_receiver: AccountInfo<'info>,
+    /// CHECK: The sender's registry PDA; may not exist for escrows created before registries.
+    #[account(mut, seeds = [SEED_SENDER_REGISTRY, escrow_account.sender.as_ref()], bump)]
+    pub sender_registry: UncheckedAccount<'info>,
+    /// CHECK: Checked against `escrow_account.condition`; only needed when it is set.
+    pub condition_program: Option<UncheckedAccount<'info>>,
+    /// CHECK: Checked against `escrow_account.condition`; only needed when it is set.
+    pub condition_config: Option<UncheckedAccount<'info>>,
+    /// Only needed when `escrow_account.has_memo` is set.
+    #[account(seeds = [SEED_ESCROW_MEMO, escrow_account.key().as_ref()], bump = escrow_memo.bump)]
+    pub escrow_memo: Option<Account<'info, EscrowMemo>>,
+    pub memo_program: Option<Program<'info, Memo>>,
+    /// Only needed when `escrow_account.has_receipt` is set.
+    #[account(mut, seeds = [SEED_RECEIPT_MINT, escrow_account.key().as_ref()], bump)]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+    /// The account holding the receipt, whoever owns it; the receipt is burned out of it. May
+    /// be left out, which leaves the receipt and its mint behind.
+    #[account(mut, token::mint = receipt_mint, token::token_program = receipt_token_program)]
+    pub receipt_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub receipt_token_program: Option<Program<'info, Token2022>>,
+    /// CHECK: Config PDA with the pause flags; nothing is paused when it does not exist.
+    #[account(seeds = [SEED_CONFIG], bump)]
+    pub config: UncheckedAccount<'info>,
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
@@ -45,19 +124,6 @@ pub struct RedeemFundsSPL<'info> {
 }
 
 impl<'info> RedeemFundsSPL<'info> {
-    pub fn verify_secret(&self, secret: &str) -> Result<()> {
-        let provided_hash = {
-            let mut hasher = Sha256::new();
-            hasher.update(secret.as_bytes());
-            hasher.finalize()
-        };
-        require!(
-            provided_hash[..] == self.escrow_account.hash_of_secret,
-            CustomError::InvalidSecret
-        );
-        Ok(())
-    }
-
     pub fn transfer_tokens_to_recipient(
         &self,
         amount: u64,
@@ -80,7 +146,7 @@ impl<'info> RedeemFundsSPL<'info> {
     pub fn close_escrow_token_account(&self, signer_seeds: &[&[&[u8]]]) -> Result<()> {
         let cpi_accounts = CloseAccount {
             account: self.escrow_token_account.to_account_info(),
-            destination: self.signer.to_account_info(),
+            destination: self.rent_receiver.to_account_info(),
             authority: self.escrow_account.to_account_info(),
         };
         let cpi_ctx = CpiContext::new_with_signer(
@@ -90,65 +156,136 @@ impl<'info> RedeemFundsSPL<'info> {
         );
         anchor_spl::token_interface::close_account(cpi_ctx)
     }
+
+    /// Checks the secret, PIN and claim condition, logs the memo, then moves the tokens to
+    /// the recipient and releases the vault. `condition_accounts` are forwarded to the
+    /// condition program. Shared by `redeem_funds_spl` and `redeem_and_invoke`.
+    pub fn redeem(
+        &mut self,
+        secret: &[u8],
+        pin: Option<&PinProof>,
+        condition_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(
+            !Config::paused(&self.config)?.redeem,
+            CustomError::ProgramPaused
+        );
+        require!(
+            !self.escrow_account.vesting.is_set(),
+            CustomError::StreamingEscrow
+        );
+        require!(
+            !self.escrow_account.allowance.is_set(),
+            CustomError::AllowanceEscrow
+        );
+        self.verify_secret(secret)?;
+        self.verify_pin(secret, pin)?;
+        self.verify_condition(condition_accounts)?;
+        require!(
+            self.escrow_account.approvers.is_met(),
+            CustomError::ApprovalThresholdNotMet
+        );
+        require!(
+            !self.escrow_account.is_redeemed,
+            CustomError::AlreadyRedeemed
+        );
+        self.escrow_account.is_redeemed = true;
+        EscrowMemo::log(
+            self.escrow_account.has_memo,
+            self.escrow_memo.as_ref(),
+            self.memo_program.as_ref(),
+        )?;
+
+        // Transfer tokens to recipient
+        let nonce = self.escrow_account.seed_nonce();
+        let seeds = &[
+            SEED_ESCROW_SPL,
+            self.escrow_account.seed_key(),
+            &nonce,
+            &[self.escrow_account.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        // Sweep the whole vault rather than `amount`: anyone can top it up, and a non-zero
+        // balance would make the close fail and lock the escrow.
+        self.transfer_tokens_to_recipient(self.escrow_token_account.amount, signer_seeds)?;
+        self.close_escrow_token_account(signer_seeds)?;
+        if let Some(receipt) = Receipt::of(
+            self.escrow_account.has_receipt,
+            self.receipt_mint.as_ref(),
+            self.receipt_account.as_ref(),
+            self.receipt_token_program.as_ref(),
+        )? {
+            receipt.burn(
+                self.escrow_account.to_account_info(),
+                signer_seeds,
+                self.rent_receiver.to_account_info(),
+            )?;
+        }
+
+        SenderRegistry::unregister(&self.sender_registry, &self.escrow_account.key())
+    }
 }
 
 // Redeem funds for SPL tokens
-pub fn redeem_funds_spl(ctx: Context<RedeemFundsSPL>, secret: String) -> Result<()> {
-    ctx.accounts.verify_secret(&secret)?;
-    require!(
-        !ctx.accounts.escrow_account.is_redeemed,
-        CustomError::AlreadyRedeemed
-    );
-    ctx.accounts.escrow_account.is_redeemed = true;
-
-    // Transfer tokens to recipient
-    let seeds = &[
-        SEED_ESCROW_SPL,
-        &ctx.accounts.escrow_account.hash_of_secret,
-        &[ctx.accounts.escrow_account.bump],
-    ];
-    let signer_seeds = &[&seeds[..]];
+pub fn redeem_funds_spl<'info>(
+    ctx: Context<'_, '_, '_, 'info, RedeemFundsSPL<'info>>,
+    secret: &[u8],
+    pin: Option<PinProof>,
+) -> Result<()> {
     ctx.accounts
-        .transfer_tokens_to_recipient(ctx.accounts.escrow_account.amount, signer_seeds)?;
-    ctx.accounts.close_escrow_token_account(signer_seeds)?;
-
-    Ok(())
+        .redeem(secret, pin.as_ref(), ctx.remaining_accounts)
 }
 
 #[derive(Accounts)]
 pub struct RedeemFundsSOL<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
-    /// CHECK: The recipient account is provided by the caller and verified in the program logic.
+    /// CHECK: Chosen by whoever holds the secret; only ever credited with lamports.
     #[account(mut)]
     pub recipient: AccountInfo<'info>,
     #[account(
         mut,
-        seeds = [SEED_ESCROW_SOL, &escrow_account.hash_of_secret],
+        seeds = [SEED_ESCROW_SOL, escrow_account.seed_key(), &escrow_account.seed_nonce()],
         bump = escrow_account.bump,
-        close = sender,
+        close = rent_receiver,
     )]
     pub escrow_account: Account<'info, EscrowSOLAccount>,
     /// CHECK: The sender account is provided by the caller and verified in the program logic.
     #[account(mut, address = escrow_account.sender)]
     pub sender: AccountInfo<'info>,
+    /// CHECK: Receives the reclaimed rent; checked against the escrow's rent policy.
+    #[account(
+        mut,
+        address = escrow_account.rent_policy.receiver(&escrow_account.sender, recipient.key, signer.key)
+            @ CustomError::InvalidRentReceiver
+    )]
+    pub rent_receiver: AccountInfo<'info>,
+    /// CHECK: The sender's registry PDA; may not exist for escrows created before registries.
+    #[account(mut, seeds = [SEED_SENDER_REGISTRY, escrow_account.sender.as_ref()], bump)]
+    pub sender_registry: UncheckedAccount<'info>,
+    /// CHECK: Checked against `escrow_account.condition`; only needed when it is set.
+    pub condition_program: Option<UncheckedAccount<'info>>,
+    /// CHECK: Checked against `escrow_account.condition`; only needed when it is set.
+    pub condition_config: Option<UncheckedAccount<'info>>,
+    /// Only needed when `escrow_account.has_memo` is set.
+    #[account(seeds = [SEED_ESCROW_MEMO, escrow_account.key().as_ref()], bump = escrow_memo.bump)]
+    pub escrow_memo: Option<Account<'info, EscrowMemo>>,
+    pub memo_program: Option<Program<'info, Memo>>,
+    /// Only needed when `escrow_account.has_receipt` is set.
+    #[account(mut, seeds = [SEED_RECEIPT_MINT, escrow_account.key().as_ref()], bump)]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+    /// The account holding the receipt, whoever owns it; the receipt is burned out of it. May
+    /// be left out, which leaves the receipt and its mint behind.
+    #[account(mut, token::mint = receipt_mint, token::token_program = receipt_token_program)]
+    pub receipt_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub receipt_token_program: Option<Program<'info, Token2022>>,
+    /// CHECK: Config PDA with the pause flags; nothing is paused when it does not exist.
+    #[account(seeds = [SEED_CONFIG], bump)]
+    pub config: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> RedeemFundsSOL<'info> {
-    pub fn verify_secret(&self, secret: &str) -> Result<()> {
-        let provided_hash = {
-            let mut hasher = Sha256::new();
-            hasher.update(secret.as_bytes());
-            hasher.finalize()
-        };
-        require!(
-            provided_hash[..] == self.escrow_account.hash_of_secret,
-            CustomError::InvalidSecret
-        );
-        Ok(())
-    }
-
     //Transfer: `from` must not carry data
     //occurs because the system program's transfer instruction requires that the from account must not carry data (i.e., it must be a system account with no data). In your case, the escrow_account has data associated with it (EscrowSOLAccount), so using the system program's transfer instruction is invalid.
     //In the context of transferring lamports from a program-owned account with data, you cannot use the system program's transfer instruction. Instead, you need to adjust the lamports balances directly within your program.
@@ -185,8 +322,30 @@ impl<'info> RedeemFundsSOL<'info> {
     // }
 }
 
-pub fn redeem_funds_sol(ctx: Context<RedeemFundsSOL>, secret: String) -> Result<()> {
-    ctx.accounts.verify_secret(&secret)?;
+pub fn redeem_funds_sol<'info>(
+    ctx: Context<'_, '_, '_, 'info, RedeemFundsSOL<'info>>,
+    secret: &[u8],
+    pin: Option<PinProof>,
+) -> Result<()> {
+    require!(
+        !Config::paused(&ctx.accounts.config)?.redeem,
+        CustomError::ProgramPaused
+    );
+    require!(
+        !ctx.accounts.escrow_account.vesting.is_set(),
+        CustomError::StreamingEscrow
+    );
+    require!(
+        !ctx.accounts.escrow_account.allowance.is_set(),
+        CustomError::AllowanceEscrow
+    );
+    ctx.accounts.verify_secret(secret)?;
+    ctx.accounts.verify_pin(secret, pin.as_ref())?;
+    ctx.accounts.verify_condition(ctx.remaining_accounts)?;
+    require!(
+        ctx.accounts.escrow_account.approvers.is_met(),
+        CustomError::ApprovalThresholdNotMet
+    );
 
     require!(
         !ctx.accounts.escrow_account.is_redeemed,
@@ -194,9 +353,39 @@ pub fn redeem_funds_sol(ctx: Context<RedeemFundsSOL>, secret: String) -> Result<
     );
 
     ctx.accounts.escrow_account.is_redeemed = true;
+    EscrowMemo::log(
+        ctx.accounts.escrow_account.has_memo,
+        ctx.accounts.escrow_memo.as_ref(),
+        ctx.accounts.memo_program.as_ref(),
+    )?;
     ctx.accounts
         .transfer_sol_to_recipient(ctx.accounts.escrow_account.amount)?;
     // ctx.accounts.refund_remaining_lamports_to_sender()?;
+    if let Some(receipt) = Receipt::of(
+        ctx.accounts.escrow_account.has_receipt,
+        ctx.accounts.receipt_mint.as_ref(),
+        ctx.accounts.receipt_account.as_ref(),
+        ctx.accounts.receipt_token_program.as_ref(),
+    )? {
+        let escrow_account = &ctx.accounts.escrow_account;
+        let nonce = escrow_account.seed_nonce();
+        let seeds = &[
+            SEED_ESCROW_SOL,
+            escrow_account.seed_key(),
+            &nonce,
+            &[escrow_account.bump],
+        ];
+        receipt.burn(
+            escrow_account.to_account_info(),
+            &[&seeds[..]],
+            ctx.accounts.rent_receiver.to_account_info(),
+        )?;
+    }
+
+    SenderRegistry::unregister(
+        &ctx.accounts.sender_registry,
+        &ctx.accounts.escrow_account.key(),
+    )?;
 
     Ok(())
 }
@@ -1,12 +1,17 @@
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_2022::Token2022;
 use anchor_spl::token_interface::{
     CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
 };
-use sha2::{Digest, Sha256};
 
 use crate::error::CustomError;
-use crate::{EscrowAccount, EscrowSOLAccount, SEED_ESCROW_SOL, SEED_ESCROW_SPL};
+use crate::receipt::{check_refund_authority, Receipt};
+use crate::secret::verify_secret;
+use crate::{
+    EscrowAccount, EscrowSOLAccount, SenderRegistry, SEED_ESCROW_SOL, SEED_ESCROW_SPL,
+    SEED_RECEIPT_MINT, SEED_SENDER_REGISTRY,
+};
 
 #[derive(Accounts)]
 pub struct RefundFundsSPL<'info> {
@@ -14,18 +19,35 @@ pub struct RefundFundsSPL<'info> {
     pub signer: Signer<'info>,
     #[account(
         mut,
-        seeds = [SEED_ESCROW_SPL, &escrow_account.hash_of_secret],
+        seeds = [SEED_ESCROW_SPL, escrow_account.seed_key(), &escrow_account.seed_nonce()],
         bump = escrow_account.bump,
         close = sender, // Refunds the rent-exempt balance to the sender
     )]
     pub escrow_account: Account<'info, EscrowAccount>,
-    #[account(mut)]
+    /// The vault: the escrow PDA's associated token account for `token_mint`.
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = escrow_account,
+        associated_token::token_program = token_program
+    )]
     pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
     #[account(mut, address = escrow_account.sender)]
     /// CHECK: This is safe because we verify the address
     pub sender: AccountInfo<'info>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = token_mint,
+        associated_token::authority = sender,
+        associated_token::token_program = token_program
+    )]
+    pub sender_token_account: InterfaceAccount<'info, TokenAccount>,
     #[account(address = escrow_account.token_mint, mint::token_program = token_program)]
     pub token_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: The sender's registry PDA; may not exist for escrows created before registries.
+    #[account(mut, seeds = [SEED_SENDER_REGISTRY, escrow_account.sender.as_ref()], bump)]
+    pub sender_registry: UncheckedAccount<'info>,
     pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
     pub associated_token_program: Program<'info, AssociatedToken>,
@@ -33,19 +55,6 @@ pub struct RefundFundsSPL<'info> {
 }
 
 impl<'info> RefundFundsSPL<'info> {
-    pub fn verify_secret(&self, secret: &str) -> Result<()> {
-        let provided_hash = {
-            let mut hasher = Sha256::new();
-            hasher.update(secret.as_bytes());
-            hasher.finalize()
-        };
-        require!(
-            provided_hash[..] == self.escrow_account.hash_of_secret,
-            CustomError::InvalidSecret
-        );
-        Ok(())
-    }
-
     pub fn transfer_tokens_back_to_sender(
         &self,
         amount: u64,
@@ -53,7 +62,7 @@ impl<'info> RefundFundsSPL<'info> {
     ) -> Result<()> {
         let cpi_accounts = TransferChecked {
             from: self.escrow_token_account.to_account_info(),
-            to: self.sender.to_account_info(),
+            to: self.sender_token_account.to_account_info(),
             authority: self.escrow_account.to_account_info(),
             mint: self.token_mint.to_account_info(),
         };
@@ -80,26 +89,38 @@ impl<'info> RefundFundsSPL<'info> {
     }
 }
 
-pub fn refund_funds_spl(ctx: Context<RefundFundsSPL>, secret: String) -> Result<()> {
-    ctx.accounts.verify_secret(&secret)?;
+pub fn refund_funds_spl(ctx: Context<RefundFundsSPL>, secret: &[u8]) -> Result<()> {
+    require!(
+        !ctx.accounts.escrow_account.has_receipt,
+        CustomError::RefundRequiresReceipt
+    );
+    verify_secret(&ctx.accounts.escrow_account.hash_of_secret, secret)?;
 
     require!(
         Clock::get()?.unix_timestamp > ctx.accounts.escrow_account.expiration_time,
         CustomError::NotExpired
     );
 
+    let nonce = ctx.accounts.escrow_account.seed_nonce();
     let seeds = &[
         SEED_ESCROW_SPL,
-        &ctx.accounts.escrow_account.hash_of_secret,
+        ctx.accounts.escrow_account.seed_key(),
+        &nonce,
         &[ctx.accounts.escrow_account.bump],
     ];
     let signer_seeds = &[&seeds[..]];
 
+    // The whole vault, including anything donated to it, so that the close can't be blocked.
     ctx.accounts
-        .transfer_tokens_back_to_sender(ctx.accounts.escrow_account.amount, signer_seeds)?;
+        .transfer_tokens_back_to_sender(ctx.accounts.escrow_token_account.amount, signer_seeds)?;
 
     ctx.accounts.close_escrow_token_account(signer_seeds)?;
 
+    SenderRegistry::unregister(
+        &ctx.accounts.sender_registry,
+        &ctx.accounts.escrow_account.key(),
+    )?;
+
     Ok(())
 }
 
@@ -109,7 +130,7 @@ pub struct RefundFundsSOL<'info> {
     pub signer: Signer<'info>,
     #[account(
         mut,
-        seeds = [SEED_ESCROW_SOL, &escrow_account.hash_of_secret],
+        seeds = [SEED_ESCROW_SOL, escrow_account.seed_key(), &escrow_account.seed_nonce()],
         bump = escrow_account.bump,
         close = sender, // Refunds the rent-exempt balance to the sender
     )]
@@ -117,23 +138,13 @@ pub struct RefundFundsSOL<'info> {
     #[account(mut, address = escrow_account.sender)]
     /// CHECK: This is safe because we verify the address
     pub sender: AccountInfo<'info>,
+    /// CHECK: The sender's registry PDA; may not exist for escrows created before registries.
+    #[account(mut, seeds = [SEED_SENDER_REGISTRY, escrow_account.sender.as_ref()], bump)]
+    pub sender_registry: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> RefundFundsSOL<'info> {
-    pub fn verify_secret(&self, secret: &str) -> Result<()> {
-        let provided_hash = {
-            let mut hasher = Sha256::new();
-            hasher.update(secret.as_bytes());
-            hasher.finalize()
-        };
-        require!(
-            provided_hash[..] == self.escrow_account.hash_of_secret,
-            CustomError::InvalidSecret
-        );
-        Ok(())
-    }
-
     pub fn transfer_sol_back_to_sender(&self, amount: u64) -> Result<()> {
         **self.sender.to_account_info().try_borrow_mut_lamports()? += amount;
         **self
@@ -144,15 +155,198 @@ impl<'info> RefundFundsSOL<'info> {
     }
 }
 
-pub fn refund_funds_sol(ctx: Context<RefundFundsSOL>, secret: String) -> Result<()> {
-    ctx.accounts.verify_secret(&secret)?;
+pub fn refund_funds_sol(ctx: Context<RefundFundsSOL>, secret: &[u8]) -> Result<()> {
+    require!(
+        !ctx.accounts.escrow_account.has_receipt,
+        CustomError::RefundRequiresReceipt
+    );
+    verify_secret(&ctx.accounts.escrow_account.hash_of_secret, secret)?;
 
     require!(
         Clock::get()?.unix_timestamp > ctx.accounts.escrow_account.expiration_time,
         CustomError::NotExpired
     );
     ctx.accounts
-        .transfer_sol_back_to_sender(ctx.accounts.escrow_account.amount)?;
+        .transfer_sol_back_to_sender(ctx.accounts.escrow_account.remaining())?;
+
+    SenderRegistry::unregister(
+        &ctx.accounts.sender_registry,
+        &ctx.accounts.escrow_account.key(),
+    )?;
 
     Ok(())
 }
+
+// Refunds of escrows with a receipt. The holder signs instead of presenting the secret, and
+// gets the funds and the rent; the receipt is burned. Once the holder burned the receipt
+// themselves, the sender signs here instead.
+#[derive(Accounts)]
+pub struct RefundWithReceiptSPL<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [SEED_ESCROW_SPL, escrow_account.seed_key(), &escrow_account.seed_nonce()],
+        bump = escrow_account.bump,
+        close = holder,
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = escrow_account,
+        associated_token::token_program = token_program
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = holder,
+        associated_token::mint = token_mint,
+        associated_token::authority = holder,
+        associated_token::token_program = token_program
+    )]
+    pub holder_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = escrow_account.token_mint, mint::token_program = token_program)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [SEED_RECEIPT_MINT, escrow_account.key().as_ref()], bump)]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+    /// The holder's account with the receipt; left out once the receipt was burned.
+    #[account(
+        mut,
+        token::mint = receipt_mint,
+        token::authority = holder,
+        token::token_program = receipt_token_program
+    )]
+    pub receipt_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK: The sender's registry PDA.
+    #[account(mut, seeds = [SEED_SENDER_REGISTRY, escrow_account.sender.as_ref()], bump)]
+    pub sender_registry: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub receipt_token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn refund_with_receipt_spl(ctx: Context<RefundWithReceiptSPL>) -> Result<()> {
+    let accounts = &ctx.accounts;
+    let receipt = Receipt::of(
+        accounts.escrow_account.has_receipt,
+        Some(&accounts.receipt_mint),
+        accounts.receipt_account.as_ref(),
+        Some(&accounts.receipt_token_program),
+    )?
+    .ok_or(CustomError::NoReceipt)?;
+    check_refund_authority(
+        Some(&receipt),
+        &accounts.escrow_account.sender,
+        accounts.holder.key,
+    )?;
+    require!(
+        Clock::get()?.unix_timestamp > accounts.escrow_account.expiration_time,
+        CustomError::NotExpired
+    );
+
+    let nonce = accounts.escrow_account.seed_nonce();
+    let seeds = &[
+        SEED_ESCROW_SPL,
+        accounts.escrow_account.seed_key(),
+        &nonce,
+        &[accounts.escrow_account.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    // The whole vault, as in `refund_funds_spl`.
+    anchor_spl::token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: accounts.escrow_token_account.to_account_info(),
+                to: accounts.holder_token_account.to_account_info(),
+                authority: accounts.escrow_account.to_account_info(),
+                mint: accounts.token_mint.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        accounts.escrow_token_account.amount,
+        accounts.token_mint.decimals,
+    )?;
+    anchor_spl::token_interface::close_account(CpiContext::new_with_signer(
+        accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: accounts.escrow_token_account.to_account_info(),
+            destination: accounts.holder.to_account_info(),
+            authority: accounts.escrow_account.to_account_info(),
+        },
+        signer_seeds,
+    ))?;
+    receipt.burn(
+        accounts.escrow_account.to_account_info(),
+        signer_seeds,
+        accounts.holder.to_account_info(),
+    )?;
+
+    SenderRegistry::unregister(&accounts.sender_registry, &accounts.escrow_account.key())
+}
+
+#[derive(Accounts)]
+pub struct RefundWithReceiptSOL<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [SEED_ESCROW_SOL, escrow_account.seed_key(), &escrow_account.seed_nonce()],
+        bump = escrow_account.bump,
+        close = holder,
+    )]
+    pub escrow_account: Account<'info, EscrowSOLAccount>,
+    #[account(mut, seeds = [SEED_RECEIPT_MINT, escrow_account.key().as_ref()], bump)]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+    /// The holder's account with the receipt; left out once the receipt was burned.
+    #[account(
+        mut,
+        token::mint = receipt_mint,
+        token::authority = holder,
+        token::token_program = receipt_token_program
+    )]
+    pub receipt_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK: The sender's registry PDA.
+    #[account(mut, seeds = [SEED_SENDER_REGISTRY, escrow_account.sender.as_ref()], bump)]
+    pub sender_registry: UncheckedAccount<'info>,
+    pub receipt_token_program: Program<'info, Token2022>,
+}
+
+pub fn refund_with_receipt_sol(ctx: Context<RefundWithReceiptSOL>) -> Result<()> {
+    let accounts = &ctx.accounts;
+    let receipt = Receipt::of(
+        accounts.escrow_account.has_receipt,
+        Some(&accounts.receipt_mint),
+        accounts.receipt_account.as_ref(),
+        Some(&accounts.receipt_token_program),
+    )?
+    .ok_or(CustomError::NoReceipt)?;
+    check_refund_authority(
+        Some(&receipt),
+        &accounts.escrow_account.sender,
+        accounts.holder.key,
+    )?;
+    require!(
+        Clock::get()?.unix_timestamp > accounts.escrow_account.expiration_time,
+        CustomError::NotExpired
+    );
+
+    let escrow = accounts.escrow_account.to_account_info();
+    let remaining = accounts.escrow_account.remaining();
+    **escrow.try_borrow_mut_lamports()? -= remaining;
+    **accounts.holder.try_borrow_mut_lamports()? += remaining;
+
+    let nonce = accounts.escrow_account.seed_nonce();
+    let seeds = &[
+        SEED_ESCROW_SOL,
+        accounts.escrow_account.seed_key(),
+        &nonce,
+        &[accounts.escrow_account.bump],
+    ];
+    receipt.burn(escrow, &[&seeds[..]], accounts.holder.to_account_info())?;
+
+    SenderRegistry::unregister(&accounts.sender_registry, &accounts.escrow_account.key())
+}
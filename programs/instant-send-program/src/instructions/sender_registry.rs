@@ -0,0 +1,48 @@
+//file: src/instructions/sender_registry.rs
+use anchor_lang::prelude::*;
+
+use crate::error::CustomError;
+use crate::{SenderRegistry, REGISTRY_PAGE_SIZE, SEED_SENDER_REGISTRY};
+
+#[derive(Accounts)]
+pub struct CloseSenderRegistry<'info> {
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [SEED_SENDER_REGISTRY, sender.key().as_ref()],
+        bump = sender_registry.bump,
+        close = sender, // Refunds the registry rent to the sender
+    )]
+    pub sender_registry: Account<'info, SenderRegistry>,
+}
+
+pub fn close_sender_registry(ctx: Context<CloseSenderRegistry>) -> Result<()> {
+    // Open escrows would otherwise be dropped from listings once the registry is recreated.
+    require!(
+        ctx.accounts.sender_registry.escrows.is_empty(),
+        CustomError::RegistryNotEmpty
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ListSenderEscrows<'info> {
+    /// CHECK: Any sender's registry; read in place by `SenderRegistry::page`, which checks
+    /// the discriminator, so that registries of any size can be paged.
+    #[account(owner = crate::ID)]
+    pub sender_registry: UncheckedAccount<'info>,
+}
+
+// View instruction: meant to be simulated, the page is returned through return data.
+pub fn list_sender_escrows(
+    ctx: Context<ListSenderEscrows>,
+    offset: u32,
+    limit: u32,
+) -> Result<Vec<Pubkey>> {
+    SenderRegistry::page(
+        &ctx.accounts.sender_registry,
+        offset as usize,
+        limit.min(REGISTRY_PAGE_SIZE) as usize,
+    )
+}
@@ -0,0 +1,598 @@
+//file: src/instructions/stream.rs
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{
+    CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+use crate::error::CustomError;
+use crate::receipt::{check_refund_authority, Receipt};
+use crate::secret::PinProof;
+use crate::{
+    Config, EscrowAccount, EscrowSOLAccount, SenderRegistry, SEED_CONFIG, SEED_ESCROW_SOL,
+    SEED_ESCROW_SPL, SEED_RECEIPT_MINT, SEED_SENDER_REGISTRY,
+};
+
+// Streaming escrows release `amount` linearly between `start_time` and `end_time`.
+// The first withdrawal is a full claim (secret, PIN, condition, approvals) and binds the
+// recipient as beneficiary; later withdrawals can be sent by anyone but only pay the
+// beneficiary. The escrow closes once everything has been withdrawn.
+// The withdraw accounts are shared with `withdraw_allowance_*`.
+#[derive(Accounts)]
+pub struct WithdrawVestedSPL<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    /// CHECK: Bound as the beneficiary on the first withdrawal and checked against it after.
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [SEED_ESCROW_SPL, escrow_account.seed_key(), &escrow_account.seed_nonce()],
+        bump = escrow_account.bump,
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = escrow_account,
+        associated_token::token_program = token_program
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = token_mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program
+    )]
+    pub recipient_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = escrow_account.token_mint, mint::token_program = token_program)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: Receives the reclaimed rent after the last withdrawal; checked against the
+    /// escrow's rent policy.
+    #[account(
+        mut,
+        address = escrow_account.rent_policy.receiver(&escrow_account.sender, recipient.key, signer.key)
+            @ CustomError::InvalidRentReceiver
+    )]
+    pub rent_receiver: AccountInfo<'info>,
+    /// CHECK: The sender's registry PDA.
+    #[account(mut, seeds = [SEED_SENDER_REGISTRY, escrow_account.sender.as_ref()], bump)]
+    pub sender_registry: UncheckedAccount<'info>,
+    /// CHECK: Checked against `escrow_account.condition`; only needed when it is set.
+    pub condition_program: Option<UncheckedAccount<'info>>,
+    /// CHECK: Checked against `escrow_account.condition`; only needed when it is set.
+    pub condition_config: Option<UncheckedAccount<'info>>,
+    /// Only needed when `escrow_account.has_receipt` is set.
+    #[account(mut, seeds = [SEED_RECEIPT_MINT, escrow_account.key().as_ref()], bump)]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+    /// The account holding the receipt, whoever owns it; the receipt is burned out of it. May
+    /// be left out, which leaves the receipt and its mint behind.
+    #[account(mut, token::mint = receipt_mint, token::token_program = receipt_token_program)]
+    pub receipt_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub receipt_token_program: Option<Program<'info, Token2022>>,
+    /// CHECK: Config PDA with the pause flags; nothing is paused when it does not exist.
+    #[account(seeds = [SEED_CONFIG], bump)]
+    pub config: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> WithdrawVestedSPL<'info> {
+    /// Runs the redeem checks on the first withdrawal and binds the beneficiary;
+    /// afterwards only checks the recipient against it.
+    pub fn verify_claim(
+        &mut self,
+        secret: Option<&[u8]>,
+        pin: Option<&PinProof>,
+        condition_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        if self.escrow_account.beneficiary != Pubkey::default() {
+            require_keys_eq!(
+                self.recipient.key(),
+                self.escrow_account.beneficiary,
+                CustomError::InvalidBeneficiary
+            );
+            return Ok(());
+        }
+        let secret = secret.ok_or(CustomError::InvalidSecret)?;
+        self.verify_secret(secret)?;
+        self.verify_pin(secret, pin)?;
+        self.verify_condition(condition_accounts)?;
+        require!(
+            self.escrow_account.approvers.is_met(),
+            CustomError::ApprovalThresholdNotMet
+        );
+        self.escrow_account.beneficiary = self.recipient.key();
+        Ok(())
+    }
+
+    pub fn transfer_tokens_to_recipient(
+        &self,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        let cpi_accounts = TransferChecked {
+            from: self.escrow_token_account.to_account_info(),
+            to: self.recipient_token_account.to_account_info(),
+            authority: self.escrow_account.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        anchor_spl::token_interface::transfer_checked(cpi_ctx, amount, self.token_mint.decimals)
+    }
+
+    pub fn close_escrow_token_account(&self, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        let cpi_accounts = CloseAccount {
+            account: self.escrow_token_account.to_account_info(),
+            destination: self.rent_receiver.to_account_info(),
+            authority: self.escrow_account.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        anchor_spl::token_interface::close_account(cpi_ctx)
+    }
+}
+
+pub fn withdraw_vested_spl<'info>(
+    ctx: Context<'_, '_, '_, 'info, WithdrawVestedSPL<'info>>,
+    secret: Option<&[u8]>,
+    pin: Option<PinProof>,
+) -> Result<()> {
+    require!(
+        !Config::paused(&ctx.accounts.config)?.redeem,
+        CustomError::ProgramPaused
+    );
+    require!(
+        ctx.accounts.escrow_account.vesting.is_set(),
+        CustomError::NotStreamingEscrow
+    );
+    ctx.accounts
+        .verify_claim(secret, pin.as_ref(), ctx.remaining_accounts)?;
+
+    let escrow_account = &mut ctx.accounts.escrow_account;
+    let vested = escrow_account
+        .vesting
+        .vested(escrow_account.amount, Clock::get()?.unix_timestamp);
+    let payout = vested.saturating_sub(escrow_account.withdrawn);
+    require!(payout > 0, CustomError::NothingToWithdraw);
+    escrow_account.withdrawn += payout;
+
+    let nonce = ctx.accounts.escrow_account.seed_nonce();
+    let seeds = &[
+        SEED_ESCROW_SPL,
+        ctx.accounts.escrow_account.seed_key(),
+        &nonce,
+        &[ctx.accounts.escrow_account.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+    // The last withdrawal sweeps the vault, so tokens donated to it can't block the close.
+    let finished = ctx.accounts.escrow_account.remaining() == 0;
+    let payout = if finished {
+        ctx.accounts.escrow_token_account.amount
+    } else {
+        payout
+    };
+    ctx.accounts
+        .transfer_tokens_to_recipient(payout, signer_seeds)?;
+
+    if finished {
+        ctx.accounts.close_escrow_token_account(signer_seeds)?;
+        if let Some(receipt) = Receipt::of(
+            ctx.accounts.escrow_account.has_receipt,
+            ctx.accounts.receipt_mint.as_ref(),
+            ctx.accounts.receipt_account.as_ref(),
+            ctx.accounts.receipt_token_program.as_ref(),
+        )? {
+            receipt.burn(
+                ctx.accounts.escrow_account.to_account_info(),
+                signer_seeds,
+                ctx.accounts.rent_receiver.to_account_info(),
+            )?;
+        }
+        ctx.accounts
+            .escrow_account
+            .close(ctx.accounts.rent_receiver.to_account_info())?;
+        SenderRegistry::unregister(
+            &ctx.accounts.sender_registry,
+            &ctx.accounts.escrow_account.key(),
+        )?;
+    }
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct WithdrawVestedSOL<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    /// CHECK: Bound as the beneficiary on the first withdrawal and checked against it after.
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [SEED_ESCROW_SOL, escrow_account.seed_key(), &escrow_account.seed_nonce()],
+        bump = escrow_account.bump,
+    )]
+    pub escrow_account: Account<'info, EscrowSOLAccount>,
+    /// CHECK: Receives the reclaimed rent after the last withdrawal; checked against the
+    /// escrow's rent policy.
+    #[account(
+        mut,
+        address = escrow_account.rent_policy.receiver(&escrow_account.sender, recipient.key, signer.key)
+            @ CustomError::InvalidRentReceiver
+    )]
+    pub rent_receiver: AccountInfo<'info>,
+    /// CHECK: The sender's registry PDA.
+    #[account(mut, seeds = [SEED_SENDER_REGISTRY, escrow_account.sender.as_ref()], bump)]
+    pub sender_registry: UncheckedAccount<'info>,
+    /// CHECK: Checked against `escrow_account.condition`; only needed when it is set.
+    pub condition_program: Option<UncheckedAccount<'info>>,
+    /// CHECK: Checked against `escrow_account.condition`; only needed when it is set.
+    pub condition_config: Option<UncheckedAccount<'info>>,
+    /// Only needed when `escrow_account.has_receipt` is set.
+    #[account(mut, seeds = [SEED_RECEIPT_MINT, escrow_account.key().as_ref()], bump)]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+    /// The account holding the receipt, whoever owns it; the receipt is burned out of it. May
+    /// be left out, which leaves the receipt and its mint behind.
+    #[account(mut, token::mint = receipt_mint, token::token_program = receipt_token_program)]
+    pub receipt_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub receipt_token_program: Option<Program<'info, Token2022>>,
+    /// CHECK: Config PDA with the pause flags; nothing is paused when it does not exist.
+    #[account(seeds = [SEED_CONFIG], bump)]
+    pub config: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> WithdrawVestedSOL<'info> {
+    /// Runs the redeem checks on the first withdrawal and binds the beneficiary;
+    /// afterwards only checks the recipient against it.
+    pub fn verify_claim(
+        &mut self,
+        secret: Option<&[u8]>,
+        pin: Option<&PinProof>,
+        condition_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        if self.escrow_account.beneficiary != Pubkey::default() {
+            require_keys_eq!(
+                self.recipient.key(),
+                self.escrow_account.beneficiary,
+                CustomError::InvalidBeneficiary
+            );
+            return Ok(());
+        }
+        let secret = secret.ok_or(CustomError::InvalidSecret)?;
+        self.verify_secret(secret)?;
+        self.verify_pin(secret, pin)?;
+        self.verify_condition(condition_accounts)?;
+        require!(
+            self.escrow_account.approvers.is_met(),
+            CustomError::ApprovalThresholdNotMet
+        );
+        self.escrow_account.beneficiary = self.recipient.key();
+        Ok(())
+    }
+
+    pub fn transfer_sol_to_recipient(&self, amount: u64) -> Result<()> {
+        **self.recipient.to_account_info().try_borrow_mut_lamports()? += amount;
+        **self
+            .escrow_account
+            .to_account_info()
+            .try_borrow_mut_lamports()? -= amount;
+        Ok(())
+    }
+}
+
+pub fn withdraw_vested_sol<'info>(
+    ctx: Context<'_, '_, '_, 'info, WithdrawVestedSOL<'info>>,
+    secret: Option<&[u8]>,
+    pin: Option<PinProof>,
+) -> Result<()> {
+    require!(
+        !Config::paused(&ctx.accounts.config)?.redeem,
+        CustomError::ProgramPaused
+    );
+    require!(
+        ctx.accounts.escrow_account.vesting.is_set(),
+        CustomError::NotStreamingEscrow
+    );
+    ctx.accounts
+        .verify_claim(secret, pin.as_ref(), ctx.remaining_accounts)?;
+
+    let escrow_account = &mut ctx.accounts.escrow_account;
+    let vested = escrow_account
+        .vesting
+        .vested(escrow_account.amount, Clock::get()?.unix_timestamp);
+    let payout = vested.saturating_sub(escrow_account.withdrawn);
+    require!(payout > 0, CustomError::NothingToWithdraw);
+    escrow_account.withdrawn += payout;
+
+    ctx.accounts.transfer_sol_to_recipient(payout)?;
+
+    if ctx.accounts.escrow_account.remaining() == 0 {
+        if let Some(receipt) = Receipt::of(
+            ctx.accounts.escrow_account.has_receipt,
+            ctx.accounts.receipt_mint.as_ref(),
+            ctx.accounts.receipt_account.as_ref(),
+            ctx.accounts.receipt_token_program.as_ref(),
+        )? {
+            let escrow_account = &ctx.accounts.escrow_account;
+            let nonce = escrow_account.seed_nonce();
+            let seeds = &[
+                SEED_ESCROW_SOL,
+                escrow_account.seed_key(),
+                &nonce,
+                &[escrow_account.bump],
+            ];
+            receipt.burn(
+                escrow_account.to_account_info(),
+                &[&seeds[..]],
+                ctx.accounts.rent_receiver.to_account_info(),
+            )?;
+        }
+        ctx.accounts
+            .escrow_account
+            .close(ctx.accounts.rent_receiver.to_account_info())?;
+        SenderRegistry::unregister(
+            &ctx.accounts.sender_registry,
+            &ctx.accounts.escrow_account.key(),
+        )?;
+    }
+
+    Ok(())
+}
+
+// The sender, or the receipt holder, stops a stream: the unvested part goes back to them and
+// the vested part stays withdrawable. Rent is returned to them if nothing is left to withdraw.
+#[derive(Accounts)]
+pub struct CancelStreamSPL<'info> {
+    /// The sender, or the receipt holder for escrows with a receipt.
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [SEED_ESCROW_SPL, escrow_account.seed_key(), &escrow_account.seed_nonce()],
+        bump = escrow_account.bump,
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = escrow_account,
+        associated_token::token_program = token_program
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = sender,
+        associated_token::mint = token_mint,
+        associated_token::authority = sender,
+        associated_token::token_program = token_program
+    )]
+    pub sender_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = escrow_account.token_mint, mint::token_program = token_program)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: The sender's registry PDA.
+    #[account(mut, seeds = [SEED_SENDER_REGISTRY, escrow_account.sender.as_ref()], bump)]
+    pub sender_registry: UncheckedAccount<'info>,
+    /// Only needed when `escrow_account.has_receipt` is set.
+    #[account(mut, seeds = [SEED_RECEIPT_MINT, escrow_account.key().as_ref()], bump)]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+    /// The signer's account holding the receipt.
+    #[account(mut, token::mint = receipt_mint, token::token_program = receipt_token_program)]
+    pub receipt_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub receipt_token_program: Option<Program<'info, Token2022>>,
+    /// CHECK: Config PDA with the pause flags; nothing is paused when it does not exist.
+    #[account(seeds = [SEED_CONFIG], bump)]
+    pub config: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CancelStreamSPL<'info> {
+    pub fn transfer_tokens_back_to_sender(
+        &self,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        let cpi_accounts = TransferChecked {
+            from: self.escrow_token_account.to_account_info(),
+            to: self.sender_token_account.to_account_info(),
+            authority: self.escrow_account.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        anchor_spl::token_interface::transfer_checked(cpi_ctx, amount, self.token_mint.decimals)
+    }
+
+    pub fn close_escrow_token_account(&self, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        let cpi_accounts = CloseAccount {
+            account: self.escrow_token_account.to_account_info(),
+            destination: self.sender.to_account_info(),
+            authority: self.escrow_account.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        anchor_spl::token_interface::close_account(cpi_ctx)
+    }
+}
+
+pub fn cancel_stream_spl(ctx: Context<CancelStreamSPL>) -> Result<()> {
+    require!(
+        !Config::paused(&ctx.accounts.config)?.refund,
+        CustomError::ProgramPaused
+    );
+    let receipt = Receipt::of(
+        ctx.accounts.escrow_account.has_receipt,
+        ctx.accounts.receipt_mint.as_ref(),
+        ctx.accounts.receipt_account.as_ref(),
+        ctx.accounts.receipt_token_program.as_ref(),
+    )?;
+    check_refund_authority(
+        receipt.as_ref(),
+        &ctx.accounts.escrow_account.sender,
+        ctx.accounts.sender.key,
+    )?;
+    let escrow_account = &mut ctx.accounts.escrow_account;
+    require!(
+        escrow_account.vesting.is_set(),
+        CustomError::NotStreamingEscrow
+    );
+    let amount = escrow_account.amount;
+    escrow_account.amount = escrow_account
+        .vesting
+        .cancel(amount, Clock::get()?.unix_timestamp);
+    let unvested = amount - escrow_account.amount;
+
+    let nonce = ctx.accounts.escrow_account.seed_nonce();
+    let seeds = &[
+        SEED_ESCROW_SPL,
+        ctx.accounts.escrow_account.seed_key(),
+        &nonce,
+        &[ctx.accounts.escrow_account.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+    // When nothing is left for the recipient the vault is swept and closed, donations included.
+    let finished = ctx.accounts.escrow_account.remaining() == 0;
+    let refund = if finished {
+        ctx.accounts.escrow_token_account.amount
+    } else {
+        unvested
+    };
+    if refund > 0 {
+        ctx.accounts
+            .transfer_tokens_back_to_sender(refund, signer_seeds)?;
+    }
+
+    if finished {
+        ctx.accounts.close_escrow_token_account(signer_seeds)?;
+        if let Some(receipt) = &receipt {
+            receipt.burn(
+                ctx.accounts.escrow_account.to_account_info(),
+                signer_seeds,
+                ctx.accounts.sender.to_account_info(),
+            )?;
+        }
+        ctx.accounts
+            .escrow_account
+            .close(ctx.accounts.sender.to_account_info())?;
+        SenderRegistry::unregister(
+            &ctx.accounts.sender_registry,
+            &ctx.accounts.escrow_account.key(),
+        )?;
+    }
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CancelStreamSOL<'info> {
+    /// The sender, or the receipt holder for escrows with a receipt.
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [SEED_ESCROW_SOL, escrow_account.seed_key(), &escrow_account.seed_nonce()],
+        bump = escrow_account.bump,
+    )]
+    pub escrow_account: Account<'info, EscrowSOLAccount>,
+    /// CHECK: The sender's registry PDA.
+    #[account(mut, seeds = [SEED_SENDER_REGISTRY, escrow_account.sender.as_ref()], bump)]
+    pub sender_registry: UncheckedAccount<'info>,
+    /// Only needed when `escrow_account.has_receipt` is set.
+    #[account(mut, seeds = [SEED_RECEIPT_MINT, escrow_account.key().as_ref()], bump)]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+    /// The signer's account holding the receipt.
+    #[account(mut, token::mint = receipt_mint, token::token_program = receipt_token_program)]
+    pub receipt_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub receipt_token_program: Option<Program<'info, Token2022>>,
+    /// CHECK: Config PDA with the pause flags; nothing is paused when it does not exist.
+    #[account(seeds = [SEED_CONFIG], bump)]
+    pub config: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CancelStreamSOL<'info> {
+    pub fn transfer_sol_back_to_sender(&self, amount: u64) -> Result<()> {
+        **self.sender.to_account_info().try_borrow_mut_lamports()? += amount;
+        **self
+            .escrow_account
+            .to_account_info()
+            .try_borrow_mut_lamports()? -= amount;
+        Ok(())
+    }
+}
+
+pub fn cancel_stream_sol(ctx: Context<CancelStreamSOL>) -> Result<()> {
+    require!(
+        !Config::paused(&ctx.accounts.config)?.refund,
+        CustomError::ProgramPaused
+    );
+    let receipt = Receipt::of(
+        ctx.accounts.escrow_account.has_receipt,
+        ctx.accounts.receipt_mint.as_ref(),
+        ctx.accounts.receipt_account.as_ref(),
+        ctx.accounts.receipt_token_program.as_ref(),
+    )?;
+    check_refund_authority(
+        receipt.as_ref(),
+        &ctx.accounts.escrow_account.sender,
+        ctx.accounts.sender.key,
+    )?;
+    let escrow_account = &mut ctx.accounts.escrow_account;
+    require!(
+        escrow_account.vesting.is_set(),
+        CustomError::NotStreamingEscrow
+    );
+    let amount = escrow_account.amount;
+    escrow_account.amount = escrow_account
+        .vesting
+        .cancel(amount, Clock::get()?.unix_timestamp);
+    let unvested = amount - escrow_account.amount;
+
+    ctx.accounts.transfer_sol_back_to_sender(unvested)?;
+
+    if ctx.accounts.escrow_account.remaining() == 0 {
+        if let Some(receipt) = &receipt {
+            let escrow_account = &ctx.accounts.escrow_account;
+            let nonce = escrow_account.seed_nonce();
+            let seeds = &[
+                SEED_ESCROW_SOL,
+                escrow_account.seed_key(),
+                &nonce,
+                &[escrow_account.bump],
+            ];
+            receipt.burn(
+                escrow_account.to_account_info(),
+                &[&seeds[..]],
+                ctx.accounts.sender.to_account_info(),
+            )?;
+        }
+        ctx.accounts
+            .escrow_account
+            .close(ctx.accounts.sender.to_account_info())?;
+        SenderRegistry::unregister(
+            &ctx.accounts.sender_registry,
+            &ctx.accounts.escrow_account.key(),
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,411 @@
+//file: src/instructions/sweep_expired.rs
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{
+    CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+use crate::error::CustomError;
+use crate::receipt::{check_refund_authority, Receipt};
+use crate::{
+    Config, EscrowAccount, EscrowSOLAccount, EscrowSwept, SenderRegistry, SEED_CONFIG,
+    SEED_ESCROW_SOL, SEED_ESCROW_SPL, SEED_RECEIPT_MINT, SEED_SENDER_REGISTRY,
+};
+
+// Anyone can crank these once `config.sweep` allows it. Everything goes back to the sender
+// exactly as a refund would, except the bounty taken from the escrow account's rent.
+// Escrows with a receipt go to its holder instead, through `sweep_expired_with_receipt_*`.
+
+#[derive(Accounts)]
+pub struct SweepExpiredSPL<'info> {
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [SEED_ESCROW_SPL, escrow_account.seed_key(), &escrow_account.seed_nonce()],
+        bump = escrow_account.bump,
+        close = sender,
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = escrow_account,
+        associated_token::token_program = token_program
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = escrow_account.sender)]
+    /// CHECK: This is safe because we verify the address
+    pub sender: AccountInfo<'info>,
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        associated_token::mint = token_mint,
+        associated_token::authority = sender,
+        associated_token::token_program = token_program
+    )]
+    pub sender_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = escrow_account.token_mint, mint::token_program = token_program)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: The sender's registry PDA; may not exist for escrows created before registries.
+    #[account(mut, seeds = [SEED_SENDER_REGISTRY, escrow_account.sender.as_ref()], bump)]
+    pub sender_registry: UncheckedAccount<'info>,
+    #[account(seeds = [SEED_CONFIG], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn sweep_expired_spl(ctx: Context<SweepExpiredSPL>) -> Result<()> {
+    // The refund belongs to the receipt holder, who may not be the sender.
+    require!(
+        !ctx.accounts.escrow_account.has_receipt,
+        CustomError::SweepRequiresReceipt
+    );
+    ctx.accounts.config.sweep.check(
+        ctx.accounts.escrow_account.expiration_time,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    let nonce = ctx.accounts.escrow_account.seed_nonce();
+    let seeds = &[
+        SEED_ESCROW_SPL,
+        ctx.accounts.escrow_account.seed_key(),
+        &nonce,
+        &[ctx.accounts.escrow_account.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    // The whole vault, as in `refund_funds_spl`, so that a donation can't block the close.
+    anchor_spl::token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.escrow_token_account.to_account_info(),
+                to: ctx.accounts.sender_token_account.to_account_info(),
+                authority: ctx.accounts.escrow_account.to_account_info(),
+                mint: ctx.accounts.token_mint.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        ctx.accounts.escrow_token_account.amount,
+        ctx.accounts.token_mint.decimals,
+    )?;
+    anchor_spl::token_interface::close_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.escrow_token_account.to_account_info(),
+            destination: ctx.accounts.sender.to_account_info(),
+            authority: ctx.accounts.escrow_account.to_account_info(),
+        },
+        signer_seeds,
+    ))?;
+
+    let bounty = pay_bounty(
+        &ctx.accounts.escrow_account.to_account_info(),
+        &ctx.accounts.cranker.to_account_info(),
+        ctx.accounts.config.sweep.bounty,
+    )?;
+
+    SenderRegistry::unregister(
+        &ctx.accounts.sender_registry,
+        &ctx.accounts.escrow_account.key(),
+    )?;
+
+    emit!(EscrowSwept {
+        escrow: ctx.accounts.escrow_account.key(),
+        sender: ctx.accounts.sender.key(),
+        cranker: ctx.accounts.cranker.key(),
+        bounty,
+        receipt_holder: None,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SweepExpiredSOL<'info> {
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [SEED_ESCROW_SOL, escrow_account.seed_key(), &escrow_account.seed_nonce()],
+        bump = escrow_account.bump,
+        close = sender,
+    )]
+    pub escrow_account: Account<'info, EscrowSOLAccount>,
+    #[account(mut, address = escrow_account.sender)]
+    /// CHECK: This is safe because we verify the address
+    pub sender: AccountInfo<'info>,
+    /// CHECK: The sender's registry PDA; may not exist for escrows created before registries.
+    #[account(mut, seeds = [SEED_SENDER_REGISTRY, escrow_account.sender.as_ref()], bump)]
+    pub sender_registry: UncheckedAccount<'info>,
+    #[account(seeds = [SEED_CONFIG], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn sweep_expired_sol(ctx: Context<SweepExpiredSOL>) -> Result<()> {
+    require!(
+        !ctx.accounts.escrow_account.has_receipt,
+        CustomError::SweepRequiresReceipt
+    );
+    ctx.accounts.config.sweep.check(
+        ctx.accounts.escrow_account.expiration_time,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    let escrow = ctx.accounts.escrow_account.to_account_info();
+    let remaining = ctx.accounts.escrow_account.remaining();
+    **escrow.try_borrow_mut_lamports()? -= remaining;
+    **ctx.accounts.sender.try_borrow_mut_lamports()? += remaining;
+
+    // What is left is the rent, which `close` hands to the sender after the bounty.
+    let bounty = pay_bounty(
+        &escrow,
+        &ctx.accounts.cranker.to_account_info(),
+        ctx.accounts.config.sweep.bounty,
+    )?;
+
+    SenderRegistry::unregister(
+        &ctx.accounts.sender_registry,
+        &ctx.accounts.escrow_account.key(),
+    )?;
+
+    emit!(EscrowSwept {
+        escrow: escrow.key(),
+        sender: ctx.accounts.sender.key(),
+        cranker: ctx.accounts.cranker.key(),
+        bounty,
+        receipt_holder: None,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SweepExpiredWithReceiptSPL<'info> {
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [SEED_ESCROW_SPL, escrow_account.seed_key(), &escrow_account.seed_nonce()],
+        bump = escrow_account.bump,
+        close = holder,
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = escrow_account,
+        associated_token::token_program = token_program
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: The owner of `receipt_account`, or the sender once the receipt was burned;
+    /// checked in the handler.
+    #[account(mut)]
+    pub holder: AccountInfo<'info>,
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        associated_token::mint = token_mint,
+        associated_token::authority = holder,
+        associated_token::token_program = token_program
+    )]
+    pub holder_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = escrow_account.token_mint, mint::token_program = token_program)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [SEED_RECEIPT_MINT, escrow_account.key().as_ref()], bump)]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+    /// The holder's account with the receipt; left out once the receipt was burned.
+    #[account(
+        mut,
+        token::mint = receipt_mint,
+        token::authority = holder,
+        token::token_program = receipt_token_program
+    )]
+    pub receipt_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK: The sender's registry PDA.
+    #[account(mut, seeds = [SEED_SENDER_REGISTRY, escrow_account.sender.as_ref()], bump)]
+    pub sender_registry: UncheckedAccount<'info>,
+    #[account(seeds = [SEED_CONFIG], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub receipt_token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn sweep_expired_with_receipt_spl(ctx: Context<SweepExpiredWithReceiptSPL>) -> Result<()> {
+    let accounts = &ctx.accounts;
+    let receipt = Receipt::of(
+        accounts.escrow_account.has_receipt,
+        Some(&accounts.receipt_mint),
+        accounts.receipt_account.as_ref(),
+        Some(&accounts.receipt_token_program),
+    )?
+    .ok_or(CustomError::NoReceipt)?;
+    check_refund_authority(
+        Some(&receipt),
+        &accounts.escrow_account.sender,
+        accounts.holder.key,
+    )?;
+    accounts.config.sweep.check(
+        accounts.escrow_account.expiration_time,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    let nonce = accounts.escrow_account.seed_nonce();
+    let seeds = &[
+        SEED_ESCROW_SPL,
+        accounts.escrow_account.seed_key(),
+        &nonce,
+        &[accounts.escrow_account.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    // The whole vault, as in `refund_with_receipt_spl`.
+    anchor_spl::token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: accounts.escrow_token_account.to_account_info(),
+                to: accounts.holder_token_account.to_account_info(),
+                authority: accounts.escrow_account.to_account_info(),
+                mint: accounts.token_mint.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        accounts.escrow_token_account.amount,
+        accounts.token_mint.decimals,
+    )?;
+    anchor_spl::token_interface::close_account(CpiContext::new_with_signer(
+        accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: accounts.escrow_token_account.to_account_info(),
+            destination: accounts.holder.to_account_info(),
+            authority: accounts.escrow_account.to_account_info(),
+        },
+        signer_seeds,
+    ))?;
+    receipt.burn(
+        accounts.escrow_account.to_account_info(),
+        signer_seeds,
+        accounts.holder.to_account_info(),
+    )?;
+
+    let bounty = pay_bounty(
+        &accounts.escrow_account.to_account_info(),
+        &accounts.cranker.to_account_info(),
+        accounts.config.sweep.bounty,
+    )?;
+
+    SenderRegistry::unregister(&accounts.sender_registry, &accounts.escrow_account.key())?;
+
+    emit!(EscrowSwept {
+        escrow: accounts.escrow_account.key(),
+        sender: accounts.escrow_account.sender,
+        cranker: accounts.cranker.key(),
+        bounty,
+        receipt_holder: Some(accounts.holder.key()),
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SweepExpiredWithReceiptSOL<'info> {
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [SEED_ESCROW_SOL, escrow_account.seed_key(), &escrow_account.seed_nonce()],
+        bump = escrow_account.bump,
+        close = holder,
+    )]
+    pub escrow_account: Account<'info, EscrowSOLAccount>,
+    /// CHECK: The owner of `receipt_account`, or the sender once the receipt was burned;
+    /// checked in the handler.
+    #[account(mut)]
+    pub holder: AccountInfo<'info>,
+    #[account(mut, seeds = [SEED_RECEIPT_MINT, escrow_account.key().as_ref()], bump)]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+    /// The holder's account with the receipt; left out once the receipt was burned.
+    #[account(
+        mut,
+        token::mint = receipt_mint,
+        token::authority = holder,
+        token::token_program = receipt_token_program
+    )]
+    pub receipt_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK: The sender's registry PDA.
+    #[account(mut, seeds = [SEED_SENDER_REGISTRY, escrow_account.sender.as_ref()], bump)]
+    pub sender_registry: UncheckedAccount<'info>,
+    #[account(seeds = [SEED_CONFIG], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    pub receipt_token_program: Program<'info, Token2022>,
+}
+
+pub fn sweep_expired_with_receipt_sol(ctx: Context<SweepExpiredWithReceiptSOL>) -> Result<()> {
+    let accounts = &ctx.accounts;
+    let receipt = Receipt::of(
+        accounts.escrow_account.has_receipt,
+        Some(&accounts.receipt_mint),
+        accounts.receipt_account.as_ref(),
+        Some(&accounts.receipt_token_program),
+    )?
+    .ok_or(CustomError::NoReceipt)?;
+    check_refund_authority(
+        Some(&receipt),
+        &accounts.escrow_account.sender,
+        accounts.holder.key,
+    )?;
+    accounts.config.sweep.check(
+        accounts.escrow_account.expiration_time,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    let nonce = accounts.escrow_account.seed_nonce();
+    let seeds = &[
+        SEED_ESCROW_SOL,
+        accounts.escrow_account.seed_key(),
+        &nonce,
+        &[accounts.escrow_account.bump],
+    ];
+    receipt.burn(
+        accounts.escrow_account.to_account_info(),
+        &[&seeds[..]],
+        accounts.holder.to_account_info(),
+    )?;
+
+    let escrow = accounts.escrow_account.to_account_info();
+    let remaining = accounts.escrow_account.remaining();
+    **escrow.try_borrow_mut_lamports()? -= remaining;
+    **accounts.holder.try_borrow_mut_lamports()? += remaining;
+
+    // What is left is the rent, which `close` hands to the holder after the bounty.
+    let bounty = pay_bounty(
+        &escrow,
+        &accounts.cranker.to_account_info(),
+        accounts.config.sweep.bounty,
+    )?;
+
+    SenderRegistry::unregister(&accounts.sender_registry, &escrow.key())?;
+
+    emit!(EscrowSwept {
+        escrow: escrow.key(),
+        sender: accounts.escrow_account.sender,
+        cranker: accounts.cranker.key(),
+        bounty,
+        receipt_holder: Some(accounts.holder.key()),
+    });
+    Ok(())
+}
+
+/// Moves up to `bounty` lamports from the escrow account to the cranker, never more than
+/// the account holds. Returns what was paid.
+fn pay_bounty(escrow: &AccountInfo, cranker: &AccountInfo, bounty: u64) -> Result<u64> {
+    let bounty = bounty.min(escrow.lamports());
+    **escrow.try_borrow_mut_lamports()? -= bounty;
+    **cranker.try_borrow_mut_lamports()? += bounty;
+    Ok(bounty)
+}
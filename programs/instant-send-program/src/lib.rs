@@ -1,11 +1,16 @@
 use anchor_lang::prelude::*;
 pub mod constants;
 pub mod error;
+pub mod events;
 pub mod instructions;
+pub mod receipt;
+pub mod secret;
 pub mod state;
 
 pub use constants::*;
+pub use events::*;
 use instructions::*;
+use secret::PinProof;
 pub use state::*;
 //declare_id!("4khKXMz3ttSaoxuwJ6nB93SB2PSjvj3FZP4E1gCPGHKW");
 declare_id!("BCLTR5fuCWrMUWc75yKnG35mtrvXt6t2eLuPwCXA93oY");
@@ -15,43 +20,396 @@ pub mod instant_send_program {
     use super::*;
 
     // SPL Token functions
+    /// Returns the escrow's address, so programs creating escrows through CPI can read it
+    /// from the return data.
     pub fn initialize_transfer_spl(
         ctx: Context<InitializeTransferSPL>,
         amount: u64,
         expiration_time: i64,
         hash_of_secret: [u8; 32],
-    ) -> Result<()> {
+        options: EscrowOptions,
+    ) -> Result<Pubkey> {
         instructions::initialize_transfer::initialize_transfer_spl(
             ctx,
             amount,
             expiration_time,
             hash_of_secret,
+            options,
         )
     }
 
-    pub fn redeem_funds_spl(ctx: Context<RedeemFundsSPL>, secret: String) -> Result<()> {
-        instructions::redeem_funds::redeem_funds_spl(ctx, secret)
+    /// Legacy form of `redeem_funds_spl_v2`; hashes the string's UTF-8 bytes. Takes no PIN,
+    /// so PIN-protected escrows fail here with `InvalidPin`.
+    pub fn redeem_funds_spl<'info>(
+        ctx: Context<'_, '_, '_, 'info, RedeemFundsSPL<'info>>,
+        secret: String,
+    ) -> Result<()> {
+        instructions::redeem_funds::redeem_funds_spl(ctx, secret.as_bytes(), None)
+    }
+
+    pub fn redeem_funds_spl_v2<'info>(
+        ctx: Context<'_, '_, '_, 'info, RedeemFundsSPL<'info>>,
+        secret: Vec<u8>,
+        pin: Option<PinProof>,
+    ) -> Result<()> {
+        crate::secret::check_secret_len(&secret)?;
+        instructions::redeem_funds::redeem_funds_spl(ctx, &secret, pin)
     }
 
+    /// Returns the escrow's address, like `initialize_transfer_spl`.
     pub fn initialize_transfer_sol(
         ctx: Context<InitializeTransferSOL>,
         amount: u64,
         expiration_time: i64,
         hash_of_secret: [u8; 32],
-    ) -> Result<()> {
+        options: EscrowOptions,
+    ) -> Result<Pubkey> {
         instructions::initialize_transfer::initialize_transfer_sol(
             ctx,
             amount,
             expiration_time,
             hash_of_secret,
+            options,
+        )
+    }
+
+    /// Legacy form of `redeem_funds_sol_v2`; hashes the string's UTF-8 bytes. Takes no PIN,
+    /// so PIN-protected escrows fail here with `InvalidPin`.
+    pub fn redeem_funds_sol<'info>(
+        ctx: Context<'_, '_, '_, 'info, RedeemFundsSOL<'info>>,
+        secret: String,
+    ) -> Result<()> {
+        instructions::redeem_funds::redeem_funds_sol(ctx, secret.as_bytes(), None)
+    }
+
+    pub fn redeem_funds_sol_v2<'info>(
+        ctx: Context<'_, '_, '_, 'info, RedeemFundsSOL<'info>>,
+        secret: Vec<u8>,
+        pin: Option<PinProof>,
+    ) -> Result<()> {
+        crate::secret::check_secret_len(&secret)?;
+        instructions::redeem_funds::redeem_funds_sol(ctx, &secret, pin)
+    }
+
+    /// Legacy form of `refund_funds_spl_v2`; hashes the string's UTF-8 bytes.
+    pub fn refund_funds_spl(ctx: Context<RefundFundsSPL>, secret: String) -> Result<()> {
+        instructions::refund_funds::refund_funds_spl(ctx, secret.as_bytes())
+    }
+
+    pub fn refund_funds_spl_v2(ctx: Context<RefundFundsSPL>, secret: Vec<u8>) -> Result<()> {
+        crate::secret::check_secret_len(&secret)?;
+        instructions::refund_funds::refund_funds_spl(ctx, &secret)
+    }
+
+    /// Legacy form of `refund_funds_sol_v2`; hashes the string's UTF-8 bytes.
+    pub fn refund_funds_sol(ctx: Context<RefundFundsSOL>, secret: String) -> Result<()> {
+        instructions::refund_funds::refund_funds_sol(ctx, secret.as_bytes())
+    }
+
+    pub fn refund_funds_sol_v2(ctx: Context<RefundFundsSOL>, secret: Vec<u8>) -> Result<()> {
+        crate::secret::check_secret_len(&secret)?;
+        instructions::refund_funds::refund_funds_sol(ctx, &secret)
+    }
+
+    pub fn refund_with_receipt_spl(ctx: Context<RefundWithReceiptSPL>) -> Result<()> {
+        instructions::refund_funds::refund_with_receipt_spl(ctx)
+    }
+
+    pub fn refund_with_receipt_sol(ctx: Context<RefundWithReceiptSOL>) -> Result<()> {
+        instructions::refund_funds::refund_with_receipt_sol(ctx)
+    }
+
+    // Permissionless refunds of long-expired escrows
+    pub fn sweep_expired_spl(ctx: Context<SweepExpiredSPL>) -> Result<()> {
+        instructions::sweep_expired::sweep_expired_spl(ctx)
+    }
+
+    pub fn sweep_expired_sol(ctx: Context<SweepExpiredSOL>) -> Result<()> {
+        instructions::sweep_expired::sweep_expired_sol(ctx)
+    }
+
+    pub fn sweep_expired_with_receipt_spl(ctx: Context<SweepExpiredWithReceiptSPL>) -> Result<()> {
+        instructions::sweep_expired::sweep_expired_with_receipt_spl(ctx)
+    }
+
+    pub fn sweep_expired_with_receipt_sol(ctx: Context<SweepExpiredWithReceiptSOL>) -> Result<()> {
+        instructions::sweep_expired::sweep_expired_with_receipt_sol(ctx)
+    }
+
+    // Escrow layout migration
+    pub fn migrate_escrow_spl(ctx: Context<MigrateEscrowSPL>) -> Result<()> {
+        instructions::migrate_escrow::migrate_escrow_spl(ctx)
+    }
+
+    pub fn migrate_escrow_sol(ctx: Context<MigrateEscrowSOL>) -> Result<()> {
+        instructions::migrate_escrow::migrate_escrow_sol(ctx)
+    }
+
+    // Sender registry
+    pub fn close_sender_registry(ctx: Context<CloseSenderRegistry>) -> Result<()> {
+        instructions::sender_registry::close_sender_registry(ctx)
+    }
+
+    pub fn list_sender_escrows(
+        ctx: Context<ListSenderEscrows>,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<Pubkey>> {
+        instructions::sender_registry::list_sender_escrows(ctx, offset, limit)
+    }
+
+    pub fn get_escrow_status(ctx: Context<ViewEscrow>) -> Result<EscrowStatus> {
+        instructions::escrow_view::get_escrow_status(ctx)
+    }
+
+    pub fn check_secret(ctx: Context<ViewEscrow>, secret: Vec<u8>) -> Result<bool> {
+        instructions::escrow_view::check_secret(ctx, &secret)
+    }
+
+    /// Legacy form of `redeem_and_invoke_v2`; hashes the string's UTF-8 bytes.
+    pub fn redeem_and_invoke<'info>(
+        ctx: Context<'_, '_, '_, 'info, RedeemAndInvoke<'info>>,
+        secret: String,
+        pin: Option<PinProof>,
+        invoke_data: Vec<u8>,
+        min_output: u64,
+        condition_accounts: u8,
+    ) -> Result<()> {
+        instructions::redeem_and_invoke::redeem_and_invoke(
+            ctx,
+            secret.as_bytes(),
+            pin,
+            invoke_data,
+            min_output,
+            condition_accounts,
+        )
+    }
+
+    pub fn redeem_and_invoke_v2<'info>(
+        ctx: Context<'_, '_, '_, 'info, RedeemAndInvoke<'info>>,
+        secret: Vec<u8>,
+        pin: Option<PinProof>,
+        invoke_data: Vec<u8>,
+        min_output: u64,
+        condition_accounts: u8,
+    ) -> Result<()> {
+        crate::secret::check_secret_len(&secret)?;
+        instructions::redeem_and_invoke::redeem_and_invoke(
+            ctx,
+            &secret,
+            pin,
+            invoke_data,
+            min_output,
+            condition_accounts,
         )
     }
 
-    pub fn redeem_funds_sol(ctx: Context<RedeemFundsSOL>, secret: String) -> Result<()> {
-        instructions::redeem_funds::redeem_funds_sol(ctx, secret)
+    // Escrow memos
+    pub fn close_escrow_memo(ctx: Context<CloseEscrowMemo>) -> Result<()> {
+        instructions::escrow_memo::close_escrow_memo(ctx)
+    }
+
+    // M-of-N release approvals
+    pub fn approve_release_spl(ctx: Context<ApproveReleaseSPL>) -> Result<()> {
+        instructions::approve_release::approve_release_spl(ctx)
+    }
+
+    pub fn revoke_approval_spl(ctx: Context<ApproveReleaseSPL>) -> Result<()> {
+        instructions::approve_release::revoke_approval_spl(ctx)
+    }
+
+    pub fn approve_release_sol(ctx: Context<ApproveReleaseSOL>) -> Result<()> {
+        instructions::approve_release::approve_release_sol(ctx)
+    }
+
+    pub fn revoke_approval_sol(ctx: Context<ApproveReleaseSOL>) -> Result<()> {
+        instructions::approve_release::revoke_approval_sol(ctx)
+    }
+
+    // Streaming escrows
+    /// Legacy form of `withdraw_vested_spl_v2`; hashes the string's UTF-8 bytes.
+    pub fn withdraw_vested_spl<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawVestedSPL<'info>>,
+        secret: Option<String>,
+        pin: Option<PinProof>,
+    ) -> Result<()> {
+        instructions::stream::withdraw_vested_spl(ctx, secret.as_deref().map(str::as_bytes), pin)
+    }
+
+    pub fn withdraw_vested_spl_v2<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawVestedSPL<'info>>,
+        secret: Option<Vec<u8>>,
+        pin: Option<PinProof>,
+    ) -> Result<()> {
+        secret
+            .as_deref()
+            .map(secret::check_secret_len)
+            .transpose()?;
+        instructions::stream::withdraw_vested_spl(ctx, secret.as_deref(), pin)
     }
 
-    // pub fn refund_funds_sol(ctx: Context<RefundFundsSOL>) -> Result<()> {
-    //     instructions::refund_funds::refund_funds_sol(ctx)
-    // }
+    /// Legacy form of `withdraw_vested_sol_v2`; hashes the string's UTF-8 bytes.
+    pub fn withdraw_vested_sol<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawVestedSOL<'info>>,
+        secret: Option<String>,
+        pin: Option<PinProof>,
+    ) -> Result<()> {
+        instructions::stream::withdraw_vested_sol(ctx, secret.as_deref().map(str::as_bytes), pin)
+    }
+
+    pub fn withdraw_vested_sol_v2<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawVestedSOL<'info>>,
+        secret: Option<Vec<u8>>,
+        pin: Option<PinProof>,
+    ) -> Result<()> {
+        secret
+            .as_deref()
+            .map(secret::check_secret_len)
+            .transpose()?;
+        instructions::stream::withdraw_vested_sol(ctx, secret.as_deref(), pin)
+    }
+
+    pub fn cancel_stream_spl(ctx: Context<CancelStreamSPL>) -> Result<()> {
+        instructions::stream::cancel_stream_spl(ctx)
+    }
+
+    pub fn cancel_stream_sol(ctx: Context<CancelStreamSOL>) -> Result<()> {
+        instructions::stream::cancel_stream_sol(ctx)
+    }
+
+    // Allowance escrows
+    /// Legacy form of `withdraw_allowance_spl_v2`; hashes the string's UTF-8 bytes.
+    pub fn withdraw_allowance_spl<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawVestedSPL<'info>>,
+        secret: Option<String>,
+        pin: Option<PinProof>,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::allowance::withdraw_allowance_spl(
+            ctx,
+            secret.as_deref().map(str::as_bytes),
+            pin,
+            amount,
+        )
+    }
+
+    pub fn withdraw_allowance_spl_v2<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawVestedSPL<'info>>,
+        secret: Option<Vec<u8>>,
+        pin: Option<PinProof>,
+        amount: u64,
+    ) -> Result<()> {
+        secret
+            .as_deref()
+            .map(secret::check_secret_len)
+            .transpose()?;
+        instructions::allowance::withdraw_allowance_spl(ctx, secret.as_deref(), pin, amount)
+    }
+
+    /// Legacy form of `withdraw_allowance_sol_v2`; hashes the string's UTF-8 bytes.
+    pub fn withdraw_allowance_sol<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawVestedSOL<'info>>,
+        secret: Option<String>,
+        pin: Option<PinProof>,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::allowance::withdraw_allowance_sol(
+            ctx,
+            secret.as_deref().map(str::as_bytes),
+            pin,
+            amount,
+        )
+    }
+
+    pub fn withdraw_allowance_sol_v2<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawVestedSOL<'info>>,
+        secret: Option<Vec<u8>>,
+        pin: Option<PinProof>,
+        amount: u64,
+    ) -> Result<()> {
+        secret
+            .as_deref()
+            .map(secret::check_secret_len)
+            .transpose()?;
+        instructions::allowance::withdraw_allowance_sol(ctx, secret.as_deref(), pin, amount)
+    }
+
+    pub fn top_up_spl(ctx: Context<TopUpSPL>, amount: u64) -> Result<()> {
+        instructions::allowance::top_up_spl(ctx, amount)
+    }
+
+    pub fn top_up_sol(ctx: Context<TopUpSOL>, amount: u64) -> Result<()> {
+        instructions::allowance::top_up_sol(ctx, amount)
+    }
+
+    pub fn close_allowance_spl(ctx: Context<CloseAllowanceSPL>) -> Result<()> {
+        instructions::allowance::close_allowance_spl(ctx)
+    }
+
+    pub fn close_allowance_sol(ctx: Context<CloseAllowanceSOL>) -> Result<()> {
+        instructions::allowance::close_allowance_sol(ctx)
+    }
+
+    // Admin functions
+    pub fn initialize_config(ctx: Context<InitializeConfig>, admin: Pubkey) -> Result<()> {
+        instructions::admin::initialize_config(ctx, admin)
+    }
+
+    pub fn set_admin(ctx: Context<SetAdmin>, new_admin: Pubkey) -> Result<()> {
+        instructions::admin::set_admin(ctx, new_admin)
+    }
+
+    pub fn set_guardian(ctx: Context<SetAdmin>, guardian: Pubkey) -> Result<()> {
+        instructions::admin::set_guardian(ctx, guardian)
+    }
+
+    pub fn set_max_active_escrows(ctx: Context<SetAdmin>, max_active_escrows: u32) -> Result<()> {
+        instructions::admin::set_max_active_escrows(ctx, max_active_escrows)
+    }
+
+    pub fn set_sweep_settings(ctx: Context<SetAdmin>, settings: SweepSettings) -> Result<()> {
+        instructions::admin::set_sweep_settings(ctx, settings)
+    }
+
+    pub fn set_pause(ctx: Context<SetPause>, paused: PauseFlags) -> Result<()> {
+        instructions::admin::set_pause(ctx, paused)
+    }
+
+    pub fn add_whitelisted_program(ctx: Context<AddWhitelistedProgram>) -> Result<()> {
+        instructions::admin::add_whitelisted_program(ctx)
+    }
+
+    pub fn remove_whitelisted_program(ctx: Context<RemoveWhitelistedProgram>) -> Result<()> {
+        instructions::admin::remove_whitelisted_program(ctx)
+    }
+
+    // Mint policy
+    pub fn set_mint_policy(ctx: Context<SetMintPolicy>, mode: MintPolicyMode) -> Result<()> {
+        instructions::mint_policy::set_mint_policy(ctx, mode)
+    }
+
+    pub fn set_mint_risk_policy(
+        ctx: Context<SetMintPolicy>,
+        reject_mask: u16,
+        warn_mask: u16,
+    ) -> Result<()> {
+        instructions::mint_policy::set_mint_risk_policy(ctx, reject_mask, warn_mask)
+    }
+
+    pub fn add_listed_mint(ctx: Context<AddListedMint>, mint: Pubkey) -> Result<()> {
+        instructions::mint_policy::add_listed_mint(ctx, mint)
+    }
+
+    pub fn remove_listed_mint(ctx: Context<RemoveListedMint>, mint: Pubkey) -> Result<()> {
+        instructions::mint_policy::remove_listed_mint(ctx, mint)
+    }
+
+    // Creation limits
+    pub fn set_mint_limits(
+        ctx: Context<SetMintLimits>,
+        mint: Pubkey,
+        caps: AmountCaps,
+    ) -> Result<()> {
+        instructions::mint_policy::set_mint_limits(ctx, mint, caps)
+    }
 }
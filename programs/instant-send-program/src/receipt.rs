@@ -0,0 +1,165 @@
+//! Refund receipts.
+//!
+//! An escrow created with `EscrowOptions::receipt` mints a 1-of-1 Token-2022 token to the
+//! sender. Whoever holds it, rather than `escrow_account.sender`, refunds or cancels the escrow
+//! and receives what the sender would have, so the right to unclaimed funds moves with the
+//! token, e.g. to another treasury. The escrow PDA is the receipt mint's permanent delegate and
+//! close authority: when the escrow closes the receipt is burned out of whichever account
+//! holds it and the mint is closed, without the holder's signature.
+//!
+//! The holder can also burn the receipt themselves. That gives up the refund, which falls
+//! back to the sender, but never blocks redemption: closing the escrow then only closes the
+//! mint.
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::instruction::AuthorityType;
+use anchor_spl::token_2022::{Burn, CloseAccount, MintTo, SetAuthority, Token2022};
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+use crate::error::CustomError;
+
+/// The receipt accounts of an escrow that has one.
+pub struct Receipt<'a, 'info> {
+    pub mint: &'a InterfaceAccount<'info, Mint>,
+    /// The token account holding the receipt, whoever owns it. Redeemers may leave it out.
+    pub account: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: &'a Program<'info, Token2022>,
+}
+
+impl<'a, 'info> Receipt<'a, 'info> {
+    /// The receipt accounts passed to an instruction; the mint and token program must be there
+    /// when the escrow `has_receipt`. `None` for escrows without one.
+    pub fn of(
+        has_receipt: bool,
+        mint: Option<&'a InterfaceAccount<'info, Mint>>,
+        account: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+        token_program: Option<&'a Program<'info, Token2022>>,
+    ) -> Result<Option<Self>> {
+        if !has_receipt {
+            return Ok(None);
+        }
+        match (mint, token_program) {
+            (Some(mint), Some(token_program)) => Ok(Some(Self {
+                mint,
+                account,
+                token_program,
+            })),
+            _ => err!(CustomError::ReceiptAccountsMissing),
+        }
+    }
+
+    /// Mints the receipt into the sender's account created with the escrow, then drops the
+    /// mint authority so it stays 1-of-1. Returns whether the escrow has a receipt; the
+    /// receipt accounts must be passed exactly when `requested`.
+    pub fn issue(
+        requested: bool,
+        mint: Option<&'a InterfaceAccount<'info, Mint>>,
+        account: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+        token_program: Option<&'a Program<'info, Token2022>>,
+        escrow: AccountInfo<'info>,
+        escrow_seeds: &[&[&[u8]]],
+    ) -> Result<bool> {
+        let (mint, account, token_program) = match (requested, mint, account, token_program) {
+            (false, None, None, _) => return Ok(false),
+            (true, Some(mint), Some(account), Some(token_program)) => {
+                (mint, account, token_program)
+            }
+            _ => return err!(CustomError::InvalidReceiptAccounts),
+        };
+        anchor_spl::token_2022::mint_to(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                MintTo {
+                    mint: mint.to_account_info(),
+                    to: account.to_account_info(),
+                    authority: escrow.clone(),
+                },
+                escrow_seeds,
+            ),
+            1,
+        )?;
+        anchor_spl::token_2022::set_authority(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                SetAuthority {
+                    current_authority: escrow,
+                    account_or_mint: mint.to_account_info(),
+                },
+                escrow_seeds,
+            ),
+            AuthorityType::MintTokens,
+            None,
+        )?;
+        Ok(true)
+    }
+
+    /// Whether the holder burned the receipt, giving up the refund.
+    pub fn is_burned(&self) -> bool {
+        self.mint.supply == 0
+    }
+
+    /// Checks that `authority` holds the receipt.
+    pub fn check_holder(&self, authority: &Pubkey) -> Result<()> {
+        require!(
+            self.account
+                .is_some_and(|account| account.owner == *authority && account.amount == 1),
+            CustomError::ReceiptNotHeld
+        );
+        Ok(())
+    }
+
+    /// Burns the receipt, unless its holder already did, and closes its mint, sending the
+    /// mint's rent to `rent_receiver`. Called wherever an escrow with a receipt closes.
+    ///
+    /// Without the holder's account an unburned receipt outlives the escrow as a worthless
+    /// token, and its mint stays open.
+    pub fn burn(
+        &self,
+        escrow: AccountInfo<'info>,
+        escrow_seeds: &[&[&[u8]]],
+        rent_receiver: AccountInfo<'info>,
+    ) -> Result<()> {
+        if !self.is_burned() {
+            let Some(account) = self.account else {
+                return Ok(());
+            };
+            require!(account.amount == 1, CustomError::ReceiptNotHeld);
+            anchor_spl::token_2022::burn(
+                CpiContext::new_with_signer(
+                    self.token_program.to_account_info(),
+                    Burn {
+                        mint: self.mint.to_account_info(),
+                        from: account.to_account_info(),
+                        authority: escrow.clone(),
+                    },
+                    escrow_seeds,
+                ),
+                1,
+            )?;
+        }
+        anchor_spl::token_2022::close_account(CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.mint.to_account_info(),
+                destination: rent_receiver,
+                authority: escrow,
+            },
+            escrow_seeds,
+        ))
+    }
+}
+
+/// Checks that `authority` may refund or cancel an escrow: its sender, or the holder of its
+/// receipt when it has one that was not burned.
+pub fn check_refund_authority(
+    receipt: Option<&Receipt>,
+    sender: &Pubkey,
+    authority: &Pubkey,
+) -> Result<()> {
+    match receipt {
+        Some(receipt) if !receipt.is_burned() => receipt.check_holder(authority),
+        _ => {
+            require_keys_eq!(*authority, *sender, CustomError::Unauthorized);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! Claim secrets.
+//!
+//! Escrows store `sha256(secret)`. The `_v2` instructions take the secret as raw bytes,
+//! `MIN_SECRET_LEN..=MAX_SECRET_LEN` long; the original instructions take a `String` and hash
+//! its UTF-8 bytes, so both forms open the same escrows.
+use anchor_lang::prelude::*;
+use sha2::{Digest, Sha256};
+
+use crate::error::CustomError;
+use crate::{MAX_SECRET_LEN, MIN_SECRET_LEN};
+
+pub fn hash_secret(secret: &[u8]) -> [u8; 32] {
+    Sha256::digest(secret).into()
+}
+
+/// Fails with `InvalidSecret` unless `secret` opens an escrow storing `hash_of_secret`.
+pub fn verify_secret(hash_of_secret: &[u8; 32], secret: &[u8]) -> Result<()> {
+    require!(
+        hash_secret(secret) == *hash_of_secret,
+        CustomError::InvalidSecret
+    );
+    Ok(())
+}
+
+/// A PIN with the salt committed alongside it. The salt is never stored on chain: it travels
+/// with the PIN, outside the link, so the link alone is not enough to brute-force a short PIN
+/// offline from `pin_hash`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PinProof {
+    pub salt: [u8; 16],
+    pub pin: String,
+}
+
+/// Checks `pin` against the escrow's PIN commitment, `sha256(secret || salt || pin)`. Escrows
+/// without a PIN store an all-zero `pin_hash` and accept any.
+pub fn verify_pin(secret: &[u8], pin_hash: &[u8; 32], pin: Option<&PinProof>) -> Result<()> {
+    if *pin_hash == [0u8; 32] {
+        return Ok(());
+    }
+    let pin = pin.ok_or(CustomError::InvalidPin)?;
+    let provided_hash: [u8; 32] = Sha256::new()
+        .chain_update(secret)
+        .chain_update(pin.salt)
+        .chain_update(pin.pin.as_bytes())
+        .finalize()
+        .into();
+    require!(provided_hash == *pin_hash, CustomError::InvalidPin);
+    Ok(())
+}
+
+/// Length bounds of the byte-array secret taken by the `_v2` instructions.
+pub fn check_secret_len(secret: &[u8]) -> Result<()> {
+    require!(
+        (MIN_SECRET_LEN as usize..=MAX_SECRET_LEN as usize).contains(&secret.len()),
+        CustomError::InvalidSecretLength
+    );
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+
+use crate::error::CustomError;
+
+/// Rate limit for allowance escrows: the beneficiary may withdraw up to `per_period_cap`
+/// in each `period_seconds` window. All zeroes for other escrows.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, InitSpace)]
+pub struct AllowanceSchedule {
+    pub period_seconds: i64,
+    pub per_period_cap: u64,
+    pub current_period_start: i64,
+    pub withdrawn_this_period: u64,
+}
+
+impl AllowanceSchedule {
+    pub fn new(period_seconds: i64, per_period_cap: u64, now: i64) -> Result<Self> {
+        require!(
+            period_seconds > 0 && per_period_cap > 0,
+            CustomError::InvalidAllowance
+        );
+        Ok(Self {
+            period_seconds,
+            per_period_cap,
+            current_period_start: now,
+            withdrawn_this_period: 0,
+        })
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.period_seconds != 0
+    }
+
+    /// Records a withdrawal of `amount` at `now`, rolling over to the current period first.
+    /// Periods stay aligned to the creation time, so unused allowance does not carry over.
+    pub fn withdraw(&mut self, amount: u64, now: i64) -> Result<()> {
+        let elapsed = now.saturating_sub(self.current_period_start);
+        if elapsed >= self.period_seconds {
+            self.current_period_start += elapsed - elapsed % self.period_seconds;
+            self.withdrawn_this_period = 0;
+        }
+        let withdrawn = self
+            .withdrawn_this_period
+            .checked_add(amount)
+            .filter(|withdrawn| *withdrawn <= self.per_period_cap)
+            .ok_or(CustomError::AllowanceExceeded)?;
+        self.withdrawn_this_period = withdrawn;
+        Ok(())
+    }
+}
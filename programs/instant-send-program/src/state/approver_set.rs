@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+
+use crate::error::CustomError;
+use crate::MAX_APPROVERS;
+
+/// M-of-N approvers who must sign off before an escrow can be redeemed, on top of the secret.
+/// Empty (`threshold == 0`) when the escrow needs no approvals.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, InitSpace)]
+pub struct ApproverSet {
+    /// The first `count` entries are used.
+    pub approvers: [Pubkey; MAX_APPROVERS],
+    pub count: u8,
+    pub threshold: u8,
+    /// Bit `i` is set once `approvers[i]` has approved.
+    pub approved: u8,
+}
+
+impl ApproverSet {
+    pub fn new(approvers: &[Pubkey], threshold: u8) -> Result<Self> {
+        require!(
+            approvers.len() <= MAX_APPROVERS
+                && threshold > 0
+                && threshold as usize <= approvers.len(),
+            CustomError::InvalidApprovers
+        );
+        let mut set = Self {
+            count: approvers.len() as u8,
+            threshold,
+            ..Self::default()
+        };
+        for (i, approver) in approvers.iter().enumerate() {
+            require!(
+                !approvers[..i].contains(approver),
+                CustomError::InvalidApprovers
+            );
+            set.approvers[i] = *approver;
+        }
+        Ok(set)
+    }
+
+    fn index_of(&self, approver: &Pubkey) -> Result<usize> {
+        self.approvers[..self.count as usize]
+            .iter()
+            .position(|key| key == approver)
+            .ok_or_else(|| error!(CustomError::NotApprover))
+    }
+
+    pub fn approve(&mut self, approver: &Pubkey) -> Result<()> {
+        let bit = 1u8 << self.index_of(approver)?;
+        require!(self.approved & bit == 0, CustomError::AlreadyApproved);
+        self.approved |= bit;
+        Ok(())
+    }
+
+    pub fn revoke(&mut self, approver: &Pubkey) -> Result<()> {
+        let bit = 1u8 << self.index_of(approver)?;
+        require!(self.approved & bit != 0, CustomError::ApprovalNotFound);
+        self.approved &= !bit;
+        Ok(())
+    }
+
+    /// Always true for escrows without approvers.
+    pub fn is_met(&self) -> bool {
+        self.approved.count_ones() >= self.threshold as u32
+    }
+}
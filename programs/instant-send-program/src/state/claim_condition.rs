@@ -0,0 +1,78 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke;
+use sha2::{Digest, Sha256};
+
+use crate::error::CustomError;
+
+/// sha256("global:check")[..8], the Anchor discriminator of a condition program's `check`.
+pub const CONDITION_CHECK_DISCRIMINATOR: [u8; 8] = [238, 251, 184, 43, 83, 233, 244, 65];
+
+/// Extra gate checked at redemption by calling another program's `check` instruction,
+/// e.g. an oracle price gate or a KYC attestation. All zeroes when the escrow has none.
+///
+/// `check` receives `[config, escrow, recipient, ..remaining_accounts]`, all read-only,
+/// with no instruction arguments. Returning an error blocks the redemption.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, InitSpace)]
+pub struct ClaimCondition {
+    pub program_id: Pubkey,
+    /// `sha256(program_id || config_address)`, so the config cannot be swapped for another
+    /// after the escrow was created. Its data is not committed: conditions such as oracle
+    /// gates update their config, and `check` is expected to read it as it is at redemption.
+    pub config_hash: [u8; 32],
+}
+
+impl ClaimCondition {
+    pub fn is_set(&self) -> bool {
+        self.program_id != Pubkey::default()
+    }
+
+    pub fn hash_config(program_id: &Pubkey, config: &Pubkey) -> [u8; 32] {
+        Sha256::new()
+            .chain_update(program_id)
+            .chain_update(config)
+            .finalize()
+            .into()
+    }
+
+    /// Verifies the condition accounts against the escrow and calls `check`.
+    /// Does nothing for escrows without a condition.
+    pub fn check<'info>(
+        &self,
+        condition_program: Option<&UncheckedAccount<'info>>,
+        condition_config: Option<&UncheckedAccount<'info>>,
+        escrow: AccountInfo<'info>,
+        recipient: AccountInfo<'info>,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        if !self.is_set() {
+            return Ok(());
+        }
+        let (Some(program), Some(config)) = (condition_program, condition_config) else {
+            return err!(CustomError::ConditionAccountsMissing);
+        };
+        require_keys_eq!(
+            program.key(),
+            self.program_id,
+            CustomError::InvalidConditionProgram
+        );
+        require!(
+            Self::hash_config(&self.program_id, config.key) == self.config_hash,
+            CustomError::InvalidConditionConfig
+        );
+
+        let mut account_infos = vec![config.to_account_info(), escrow, recipient];
+        account_infos.extend_from_slice(remaining_accounts);
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts: account_infos
+                .iter()
+                .map(|account| AccountMeta::new_readonly(account.key(), account.is_signer))
+                .collect(),
+            data: CONDITION_CHECK_DISCRIMINATOR.to_vec(),
+        };
+        account_infos.push(program.to_account_info());
+        invoke(&instruction, &account_infos)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+//file: src/state/config.rs
+use anchor_lang::prelude::*;
+
+use crate::error::CustomError;
+use crate::CONFIG_RESERVED_SPACE;
+
+/// Program-wide settings, a single PDA owned by the admin.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,
+    pub bump: u8,
+    pub version: u8,
+    /// Can set the pause flags alongside the admin, e.g. a hot key for incident response.
+    pub guardian: Pubkey,
+    pub paused: PauseFlags,
+    pub mint_policy: MintPolicyMode,
+    /// `MINT_RISK_*` flags that make SPL creates fail.
+    pub risk_reject_mask: u16,
+    /// `MINT_RISK_*` flags reported with a `MintRiskDetected` event; anything else is allowed.
+    pub risk_warn_mask: u16,
+    /// Most open escrows a sender can have, counted in their registry; 0 means no cap.
+    pub max_active_escrows: u32,
+    pub sweep: SweepSettings,
+    pub reserved: [u8; CONFIG_RESERVED_SPACE],
+}
+
+/// Circuit breakers for each instruction family. Refunds of expired escrows are never
+/// paused, so funds can always go back to their sender.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, InitSpace)]
+pub struct PauseFlags {
+    /// Creating escrows and topping up allowances.
+    pub create: bool,
+    /// Redeeming and withdrawing from escrows.
+    pub redeem: bool,
+    /// Early refunds by the sender: cancelling streams and closing allowances.
+    pub refund: bool,
+    /// Admin instructions other than `set_pause`.
+    pub admin: bool,
+}
+
+/// Lets anyone return long-expired escrows to their sender, see `sweep_expired_spl`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, InitSpace)]
+pub struct SweepSettings {
+    pub enabled: bool,
+    /// Seconds after `expiration_time` before an escrow can be swept, leaving the sender
+    /// time to refund it themselves.
+    pub delay: u32,
+    /// Lamports paid to whoever sweeps, out of the escrow account's rent and capped at it.
+    pub bounty: u64,
+}
+
+/// Which SPL mints can be escrowed, based on the `MintListPage` accounts.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, InitSpace)]
+pub enum MintPolicyMode {
+    /// Any mint; the lists are ignored.
+    #[default]
+    Disabled,
+    /// Only listed mints.
+    AllowList,
+    /// Any mint except listed ones.
+    DenyList,
+}
+
+impl Config {
+    /// The config PDA in `info`, or `None` on deployments without one.
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        if info.owner != &crate::ID || info.data_is_empty() {
+            return Ok(None);
+        }
+        Self::try_deserialize(&mut &info.try_borrow_data()?[..]).map(Some)
+    }
+
+    /// Pause flags from the config PDA in `info`. Deployments without a config have
+    /// nothing paused.
+    pub fn paused(info: &AccountInfo) -> Result<PauseFlags> {
+        Ok(Self::load(info)?
+            .map(|config| config.paused)
+            .unwrap_or_default())
+    }
+
+    /// Fails with `TooManyActiveEscrows` if a sender with `active` open escrows is over
+    /// the cap in the config PDA in `info`.
+    pub fn check_active_escrows(info: &AccountInfo, active: usize) -> Result<()> {
+        let max = Self::load(info)?
+            .map(|config| config.max_active_escrows)
+            .unwrap_or_default();
+        require!(
+            max == 0 || active <= max as usize,
+            CustomError::TooManyActiveEscrows
+        );
+        Ok(())
+    }
+}
+
+impl SweepSettings {
+    /// Fails unless sweeping is enabled and the delay after `expiration_time` has passed.
+    pub fn check(&self, expiration_time: i64, now: i64) -> Result<()> {
+        require!(self.enabled, CustomError::SweepDisabled);
+        require!(
+            now > expiration_time.saturating_add(self.delay as i64),
+            CustomError::NotSweepable
+        );
+        Ok(())
+    }
+}
+
+// sha256("account:Config")[..8]
+versioned_account!(Config, [155, 12, 170, 224, 30, 250, 204, 130]);
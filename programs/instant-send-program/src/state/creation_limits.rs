@@ -0,0 +1,118 @@
+//file: src/state/creation_limits.rs
+use anchor_lang::prelude::*;
+
+use crate::error::CustomError;
+
+/// Caps on what senders can escrow of one mint. Zero disables a cap.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, InitSpace)]
+pub struct AmountCaps {
+    /// Largest amount a single escrow can hold, including allowance top-ups.
+    pub max_escrow_amount: u64,
+    /// Length of the window `max_window_volume` applies to.
+    pub window_seconds: i64,
+    /// Most a single sender can escrow within any `window_seconds`, not just between fixed
+    /// window boundaries.
+    pub max_window_volume: u64,
+}
+
+impl AmountCaps {
+    pub fn validate(&self) -> Result<()> {
+        require!(self.window_seconds >= 0, CustomError::InvalidLimits);
+        require!(
+            self.max_window_volume == 0 || self.window_seconds > 0,
+            CustomError::InvalidLimits
+        );
+        Ok(())
+    }
+
+    pub fn check_escrow_amount(&self, escrow_amount: u64) -> Result<()> {
+        require!(
+            self.max_escrow_amount == 0 || escrow_amount <= self.max_escrow_amount,
+            CustomError::EscrowAmountCapExceeded
+        );
+        Ok(())
+    }
+}
+
+/// Admin-set caps for one mint; SOL escrows use `NATIVE_MINT_KEY`.
+#[account]
+#[derive(InitSpace)]
+pub struct MintLimits {
+    pub mint: Pubkey,
+    pub bump: u8,
+    pub caps: AmountCaps,
+}
+
+impl MintLimits {
+    /// Caps from the mint limits PDA in `info`. Mints without one are uncapped.
+    pub fn caps(info: &AccountInfo) -> Result<AmountCaps> {
+        if info.owner != &crate::ID || info.data_is_empty() {
+            return Ok(AmountCaps::default());
+        }
+        let limits = Self::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        Ok(limits.caps)
+    }
+}
+
+/// How much one sender escrowed of one mint, in `window_seconds` buckets aligned to the unix
+/// epoch: the current bucket and the one before it.
+#[account]
+#[derive(InitSpace)]
+pub struct SenderUsage {
+    pub sender: Pubkey,
+    pub mint: Pubkey,
+    pub bump: u8,
+    pub window_start: i64,
+    pub window_volume: u64,
+    /// Volume of the bucket ending at `window_start`; zero if the sender escrowed nothing then.
+    pub previous_window_volume: u64,
+}
+
+impl SenderUsage {
+    /// Adds `amount` to the sender's volume at `now` and enforces the window cap.
+    ///
+    /// The cap covers the current bucket and the previous one together. Any `window_seconds`
+    /// span lies within two adjacent buckets, so no span ever holds more than
+    /// `max_window_volume`; a sender who used the whole cap waits until a bucket without
+    /// deposits has passed. Also fills in the account on first use, since it is created with
+    /// `init_if_needed`.
+    pub fn record(
+        &mut self,
+        sender: Pubkey,
+        mint: Pubkey,
+        bump: u8,
+        caps: &AmountCaps,
+        amount: u64,
+        now: i64,
+    ) -> Result<()> {
+        self.sender = sender;
+        self.mint = mint;
+        self.bump = bump;
+
+        // Without a window there is nothing to track.
+        if caps.window_seconds == 0 {
+            return Ok(());
+        }
+        let window_start = now - now.rem_euclid(caps.window_seconds);
+        if window_start != self.window_start {
+            // The old bucket still counts while any part of it is within a window of `now`.
+            let elapsed = now.saturating_sub(self.window_start);
+            self.previous_window_volume = match elapsed < caps.window_seconds.saturating_mul(2) {
+                true => self.window_volume,
+                false => 0,
+            };
+            self.window_start = window_start;
+            self.window_volume = 0;
+        }
+        self.window_volume = self.window_volume.saturating_add(amount);
+        require!(
+            caps.max_window_volume == 0
+                || self
+                    .window_volume
+                    .saturating_add(self.previous_window_volume)
+                    <= caps.max_window_volume,
+            CustomError::WindowVolumeCapExceeded
+        );
+        Ok(())
+    }
+}
@@ -1,8 +1,11 @@
 //file: src/state/escrow_account.rs
 use anchor_lang::prelude::*;
 
-#[account]
-#[derive(InitSpace)]
+use crate::{
+    AllowanceSchedule, ApproverSet, ClaimCondition, VestingSchedule, ESCROW_RESERVED_SPACE,
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
 pub struct EscrowAccount {
     pub sender: Pubkey,
     pub amount: u64,
@@ -11,4 +14,86 @@ pub struct EscrowAccount {
     pub token_mint: Pubkey,
     pub hash_of_secret: [u8; 32],
     pub bump: u8,
+    // Fields below were added after the first release; legacy accounts decode them as zero.
+    pub version: u8,
+    pub seed_scheme: EscrowSeedScheme,
+    pub nonce: u64,
+    pub rent_policy: RentPolicy,
+    /// `sha256(secret || salt || pin)`, all zeroes when no PIN is required.
+    pub pin_hash: [u8; 32],
+    pub condition: ClaimCondition,
+    pub approvers: ApproverSet,
+    pub vesting: VestingSchedule,
+    /// Amount already paid out by `withdraw_vested_*` or `withdraw_allowance_*`.
+    pub withdrawn: u64,
+    /// Recipient bound by the first withdrawal; later withdrawals pay only them.
+    pub beneficiary: Pubkey,
+    pub allowance: AllowanceSchedule,
+    /// `MINT_RISK_*` flags found on `token_mint` at creation, for UIs.
+    pub risk_flags: u16,
+    /// Whether an `EscrowMemo` was created with the escrow; redeeming it then logs the memo.
+    pub has_memo: bool,
+    /// Whether a receipt token was minted with the escrow; its holder refunds and cancels
+    /// instead of `sender`, see `crate::receipt`.
+    pub has_receipt: bool,
+    pub reserved: [u8; ESCROW_RESERVED_SPACE],
+}
+
+/// How an escrow PDA was derived. Legacy escrows were seeded by the secret hash,
+/// which let anyone watching the mempool squat the address.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum EscrowSeedScheme {
+    /// `[seed, hash_of_secret]`
+    Hash,
+    /// `[seed, sender, nonce]`
+    SenderNonce,
+}
+
+/// Who receives the rent reclaimed when an escrow is redeemed. Refunds always
+/// return the rent to the sender, since there is no recipient or redeemer.
+/// Legacy escrows decode as `Sender`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum RentPolicy {
+    Sender,
+    Recipient,
+    /// Whoever signs the redeem transaction, e.g. a relayer paying the fees.
+    Redeemer,
+}
+
+impl RentPolicy {
+    pub fn receiver(&self, sender: &Pubkey, recipient: &Pubkey, redeemer: &Pubkey) -> Pubkey {
+        match self {
+            RentPolicy::Sender => *sender,
+            RentPolicy::Recipient => *recipient,
+            RentPolicy::Redeemer => *redeemer,
+        }
+    }
+}
+
+impl EscrowAccount {
+    /// Amount still held by the escrow.
+    pub fn remaining(&self) -> u64 {
+        self.amount.saturating_sub(self.withdrawn)
+    }
+
+    /// Second PDA seed: the secret hash for legacy escrows, the sender otherwise.
+    pub fn seed_key(&self) -> &[u8] {
+        match self.seed_scheme {
+            EscrowSeedScheme::Hash => &self.hash_of_secret,
+            EscrowSeedScheme::SenderNonce => self.sender.as_ref(),
+        }
+    }
+
+    /// Third PDA seed: the little-endian nonce, or empty for legacy escrows.
+    /// An empty seed does not change the derived address, so both schemes
+    /// share the same seed list.
+    pub fn seed_nonce(&self) -> Vec<u8> {
+        match self.seed_scheme {
+            EscrowSeedScheme::Hash => Vec::new(),
+            EscrowSeedScheme::SenderNonce => self.nonce.to_le_bytes().to_vec(),
+        }
+    }
 }
+
+// sha256("account:EscrowAccount")[..8]
+versioned_account!(EscrowAccount, [36, 69, 48, 18, 128, 225, 125, 135]);
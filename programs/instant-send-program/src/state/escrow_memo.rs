@@ -0,0 +1,68 @@
+//file: src/state/escrow_memo.rs
+use anchor_lang::prelude::*;
+use anchor_spl::memo::{BuildMemo, Memo};
+
+use crate::error::CustomError;
+use crate::{ANCHOR_DISCRIMINATOR_SIZE, MAX_MEMO_LEN};
+
+/// Note attached to an escrow at creation, e.g. a greeting or an invoice reference.
+/// Kept in its own account, sized to the memo, so escrows without one stay small.
+/// The sender closes it with `close_escrow_memo` once the escrow is gone.
+#[account]
+pub struct EscrowMemo {
+    pub escrow: Pubkey,
+    pub sender: Pubkey,
+    pub bump: u8,
+    pub memo: String,
+}
+
+impl EscrowMemo {
+    /// Account size needed to hold a memo of `len` bytes.
+    pub fn space(len: usize) -> usize {
+        ANCHOR_DISCRIMINATOR_SIZE + 32 + 32 + 1 + 4 + len
+    }
+
+    /// Fills in the memo account created alongside an escrow. Returns whether the escrow
+    /// has a memo; the account must be passed exactly when `memo` is set.
+    pub fn write(
+        account: Option<&mut Account<EscrowMemo>>,
+        bump: Option<u8>,
+        escrow: Pubkey,
+        sender: Pubkey,
+        memo: Option<&String>,
+    ) -> Result<bool> {
+        match (account, memo) {
+            (None, None) => Ok(false),
+            (Some(account), Some(memo)) => {
+                require!(
+                    memo.len() <= MAX_MEMO_LEN as usize,
+                    CustomError::MemoTooLong
+                );
+                account.escrow = escrow;
+                account.sender = sender;
+                account.bump = bump.ok_or(CustomError::InvalidMemoAccount)?;
+                account.memo = memo.clone();
+                Ok(true)
+            }
+            _ => err!(CustomError::InvalidMemoAccount),
+        }
+    }
+    /// Logs the memo of an escrow with `has_memo` through the SPL Memo program, so it
+    /// shows up in the recipient's wallet history.
+    pub fn log(
+        has_memo: bool,
+        account: Option<&Account<EscrowMemo>>,
+        memo_program: Option<&Program<Memo>>,
+    ) -> Result<()> {
+        if !has_memo {
+            return Ok(());
+        }
+        let (Some(account), Some(memo_program)) = (account, memo_program) else {
+            return err!(CustomError::MemoAccountsMissing);
+        };
+        anchor_spl::memo::build_memo(
+            CpiContext::new(memo_program.to_account_info(), BuildMemo {}),
+            account.memo.as_bytes(),
+        )
+    }
+}
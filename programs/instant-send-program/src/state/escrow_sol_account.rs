@@ -1,7 +1,11 @@
 use anchor_lang::prelude::*;
 
-#[account]
-#[derive(InitSpace)]
+use crate::{
+    AllowanceSchedule, ApproverSet, ClaimCondition, EscrowSeedScheme, RentPolicy, VestingSchedule,
+    ESCROW_SOL_RESERVED_SPACE,
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
 pub struct EscrowSOLAccount {
     pub sender: Pubkey,
     pub amount: u64,
@@ -9,4 +13,51 @@ pub struct EscrowSOLAccount {
     pub is_redeemed: bool,
     pub hash_of_secret: [u8; 32],
     pub bump: u8,
+    // Fields below were added after the first release; legacy accounts decode them as zero.
+    pub version: u8,
+    pub seed_scheme: EscrowSeedScheme,
+    pub nonce: u64,
+    pub rent_policy: RentPolicy,
+    /// `sha256(secret || salt || pin)`, all zeroes when no PIN is required.
+    pub pin_hash: [u8; 32],
+    pub condition: ClaimCondition,
+    pub approvers: ApproverSet,
+    pub vesting: VestingSchedule,
+    /// Amount already paid out by `withdraw_vested_*` or `withdraw_allowance_*`.
+    pub withdrawn: u64,
+    /// Recipient bound by the first withdrawal; later withdrawals pay only them.
+    pub beneficiary: Pubkey,
+    pub allowance: AllowanceSchedule,
+    /// Whether an `EscrowMemo` was created with the escrow; redeeming it then logs the memo.
+    pub has_memo: bool,
+    /// Whether a receipt token was minted with the escrow; its holder refunds and cancels
+    /// instead of `sender`, see `crate::receipt`.
+    pub has_receipt: bool,
+    pub reserved: [u8; ESCROW_SOL_RESERVED_SPACE],
+}
+
+impl EscrowSOLAccount {
+    /// Amount still held by the escrow.
+    pub fn remaining(&self) -> u64 {
+        self.amount.saturating_sub(self.withdrawn)
+    }
+
+    /// Second PDA seed: the secret hash for legacy escrows, the sender otherwise.
+    pub fn seed_key(&self) -> &[u8] {
+        match self.seed_scheme {
+            EscrowSeedScheme::Hash => &self.hash_of_secret,
+            EscrowSeedScheme::SenderNonce => self.sender.as_ref(),
+        }
+    }
+
+    /// Third PDA seed: the little-endian nonce, or empty for legacy escrows.
+    pub fn seed_nonce(&self) -> Vec<u8> {
+        match self.seed_scheme {
+            EscrowSeedScheme::Hash => Vec::new(),
+            EscrowSeedScheme::SenderNonce => self.nonce.to_le_bytes().to_vec(),
+        }
+    }
 }
+
+// sha256("account:EscrowSOLAccount")[..8]
+versioned_account!(EscrowSOLAccount, [254, 87, 94, 62, 155, 182, 159, 157]);
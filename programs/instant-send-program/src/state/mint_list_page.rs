@@ -0,0 +1,50 @@
+//file: src/state/mint_list_page.rs
+use anchor_lang::prelude::*;
+
+use crate::error::CustomError;
+use crate::{Config, MintPolicyMode, ANCHOR_DISCRIMINATOR_SIZE};
+
+/// One page of the admin's mint list, holding the mints whose first byte is `bucket`.
+/// Splitting the list this way keeps each page small, and a create only needs the one
+/// page its mint falls into to prove membership or absence.
+#[account]
+pub struct MintListPage {
+    pub bucket: u8,
+    pub bump: u8,
+    pub mints: Vec<Pubkey>,
+}
+
+impl MintListPage {
+    /// Account size needed to hold `len` mints.
+    pub fn space(len: usize) -> usize {
+        ANCHOR_DISCRIMINATOR_SIZE + 1 + 1 + 4 + len * 32
+    }
+
+    /// Page seed for `mint`.
+    pub fn bucket(mint: &Pubkey) -> [u8; 1] {
+        [mint.to_bytes()[0]]
+    }
+
+    fn contains(info: &AccountInfo, mint: &Pubkey) -> Result<bool> {
+        if info.owner != &crate::ID || info.data_is_empty() {
+            return Ok(false);
+        }
+        let page = Self::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        Ok(page.mints.contains(mint))
+    }
+
+    /// Fails with `MintNotAllowed` if the config's mint policy rejects `mint`.
+    /// `page` is the (possibly missing) page for the mint's bucket.
+    pub fn check_mint(config: &AccountInfo, page: &AccountInfo, mint: &Pubkey) -> Result<()> {
+        let mode = Config::load(config)?
+            .map(|config| config.mint_policy)
+            .unwrap_or_default();
+        let allowed = match mode {
+            MintPolicyMode::Disabled => true,
+            MintPolicyMode::AllowList => Self::contains(page, mint)?,
+            MintPolicyMode::DenyList => !Self::contains(page, mint)?,
+        };
+        require!(allowed, CustomError::MintNotAllowed);
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+//file: src/state/mint_risk.rs
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    default_account_state::DefaultAccountState, non_transferable::NonTransferable,
+    permanent_delegate::PermanentDelegate, transfer_fee::TransferFeeConfig,
+    transfer_hook::TransferHook, BaseStateWithExtensions, StateWithExtensions,
+};
+use anchor_spl::token_2022::spl_token_2022::state::{AccountState, Mint as MintState};
+use anchor_spl::token_interface::Mint;
+
+use crate::{
+    MINT_RISK_DEFAULT_FROZEN, MINT_RISK_FREEZE_AUTHORITY, MINT_RISK_NON_TRANSFERABLE,
+    MINT_RISK_PERMANENT_DELEGATE, MINT_RISK_TRANSFER_FEE, MINT_RISK_TRANSFER_HOOK,
+};
+
+/// Bitmask of `MINT_RISK_*` flags for `mint`: a freeze authority, or Token-2022 extensions
+/// that let someone else move the vault's tokens or that break delivery to the recipient.
+pub fn mint_risk_flags(mint: &InterfaceAccount<Mint>) -> Result<u16> {
+    let mut flags = 0;
+    if mint.freeze_authority.is_some() {
+        flags |= MINT_RISK_FREEZE_AUTHORITY;
+    }
+
+    let info = mint.to_account_info();
+    if info.owner != &anchor_spl::token_2022::ID {
+        return Ok(flags);
+    }
+    let data = info.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+
+    if let Ok(extension) = state.get_extension::<PermanentDelegate>() {
+        if Option::<Pubkey>::from(extension.delegate).is_some() {
+            flags |= MINT_RISK_PERMANENT_DELEGATE;
+        }
+    }
+    if state.get_extension::<NonTransferable>().is_ok() {
+        flags |= MINT_RISK_NON_TRANSFERABLE;
+    }
+    if let Ok(extension) = state.get_extension::<DefaultAccountState>() {
+        if extension.state == AccountState::Frozen as u8 {
+            flags |= MINT_RISK_DEFAULT_FROZEN;
+        }
+    }
+    if state.get_extension::<TransferFeeConfig>().is_ok() {
+        flags |= MINT_RISK_TRANSFER_FEE;
+    }
+    if let Ok(extension) = state.get_extension::<TransferHook>() {
+        if Option::<Pubkey>::from(extension.program_id).is_some() {
+            flags |= MINT_RISK_TRANSFER_HOOK;
+        }
+    }
+    Ok(flags)
+}
@@ -1,6 +1,45 @@
 //file: src/state/mod.rs
+#[macro_use]
+mod versioned;
+
+pub mod approver_set;
+pub use approver_set::*;
+
+pub mod claim_condition;
+pub use claim_condition::*;
+
+pub mod allowance;
+pub use allowance::*;
+
+pub mod vesting;
+pub use vesting::*;
+
 pub mod escrow_account;
 pub use escrow_account::*;
 
 pub mod escrow_sol_account;
 pub use escrow_sol_account::*;
+
+pub mod sender_nonce;
+pub use sender_nonce::*;
+
+pub mod sender_registry;
+pub use sender_registry::*;
+
+pub mod config;
+pub use config::*;
+
+pub mod whitelisted_program;
+pub use whitelisted_program::*;
+
+pub mod mint_list_page;
+pub use mint_list_page::*;
+
+pub mod mint_risk;
+pub use mint_risk::*;
+
+pub mod creation_limits;
+pub use creation_limits::*;
+
+pub mod escrow_memo;
+pub use escrow_memo::*;
@@ -0,0 +1,26 @@
+//file: src/state/sender_nonce.rs
+use anchor_lang::prelude::*;
+
+/// Per-sender counter used to derive sender-scoped escrow addresses.
+/// Shared by SPL and SOL escrows; every create consumes one nonce.
+#[account]
+#[derive(InitSpace)]
+pub struct SenderNonce {
+    pub sender: Pubkey,
+    pub next_nonce: u64,
+    pub bump: u8,
+}
+
+impl SenderNonce {
+    /// Advances the counter after an escrow was derived from `next_nonce`.
+    /// Also fills in the account on first use, since it is created with `init_if_needed`.
+    pub fn consume(&mut self, sender: Pubkey, bump: u8) -> Result<()> {
+        self.sender = sender;
+        self.bump = bump;
+        self.next_nonce = self
+            .next_nonce
+            .checked_add(1)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
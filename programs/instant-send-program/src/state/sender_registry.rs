@@ -0,0 +1,192 @@
+//file: src/state/sender_registry.rs
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{Allocate, Assign, Transfer};
+use anchor_lang::Discriminator;
+
+use crate::{ANCHOR_DISCRIMINATOR_SIZE, SEED_SENDER_REGISTRY};
+
+/// Open escrows (SPL and SOL) created by one sender, so wallets can list them
+/// without `getProgramAccounts`. Grows by one slot per create and keeps its
+/// size when entries are removed, so freed slots are reused.
+///
+/// The program never deserializes `escrows`, which has no upper bound: the helpers
+/// below edit the account bytes in place, so no instruction's heap use depends on
+/// how many escrows a sender has open. Removal swaps the last entry into the freed
+/// slot, so the order of the list is not stable.
+#[account]
+pub struct SenderRegistry {
+    pub sender: Pubkey,
+    pub bump: u8,
+    pub escrows: Vec<Pubkey>,
+}
+
+// Byte offsets of the Borsh layout above.
+const SENDER_OFFSET: usize = ANCHOR_DISCRIMINATOR_SIZE;
+const BUMP_OFFSET: usize = SENDER_OFFSET + 32;
+const LEN_OFFSET: usize = BUMP_OFFSET + 1;
+const ESCROWS_OFFSET: usize = LEN_OFFSET + 4;
+
+impl SenderRegistry {
+    /// Account size needed to hold `len` escrow addresses.
+    pub fn space(len: usize) -> usize {
+        ESCROWS_OFFSET + len * 32
+    }
+
+    /// Appends `escrow` to the registry PDA of `sender` in `info`, creating it on first
+    /// use and growing it when it is full, with rent paid by `payer`. Returns how many
+    /// escrows the sender now has open.
+    pub fn register<'info>(
+        info: &AccountInfo<'info>,
+        sender: &Pubkey,
+        bump: u8,
+        escrow: Pubkey,
+        payer: AccountInfo<'info>,
+        system_program: AccountInfo<'info>,
+    ) -> Result<usize> {
+        if info.owner == &crate::ID {
+            Self::check_discriminator(info)?;
+        } else {
+            Self::create(info, sender, bump, &payer, &system_program)?;
+        }
+
+        let len = Self::len(info)?;
+        let space = Self::space(len + 1);
+        if info.data_len() < space {
+            Self::fund(info, space, &payer, &system_program)?;
+            info.realloc(space, false)?;
+        }
+        let mut data = info.try_borrow_mut_data()?;
+        let slot = ESCROWS_OFFSET + len * 32;
+        data[slot..slot + 32].copy_from_slice(escrow.as_ref());
+        write_len(&mut data, len + 1);
+        Ok(len + 1)
+    }
+
+    /// Removes `escrow` from the registry held in `info`. Escrows created before the
+    /// registry existed were never added, so a missing registry is not an error.
+    pub fn unregister(info: &AccountInfo, escrow: &Pubkey) -> Result<()> {
+        if info.owner != &crate::ID || info.data_is_empty() {
+            return Ok(());
+        }
+        Self::check_discriminator(info)?;
+
+        let mut data = info.try_borrow_mut_data()?;
+        let len = read_len(&data);
+        let escrows = &mut data[ESCROWS_OFFSET..ESCROWS_OFFSET + len * 32];
+        let Some(index) = escrows
+            .chunks_exact(32)
+            .position(|key| key == escrow.as_ref())
+        else {
+            return Ok(());
+        };
+        let last = (len - 1) * 32;
+        escrows.copy_within(last..last + 32, index * 32);
+        escrows[last..].fill(0);
+        write_len(&mut data, len - 1);
+        Ok(())
+    }
+
+    /// Number of escrows listed in the registry held in `info`.
+    pub fn len(info: &AccountInfo) -> Result<usize> {
+        Self::check_discriminator(info)?;
+        Ok(read_len(&info.try_borrow_data()?))
+    }
+
+    /// Up to `limit` escrows listed from position `offset` on.
+    pub fn page(info: &AccountInfo, offset: usize, limit: usize) -> Result<Vec<Pubkey>> {
+        let len = Self::len(info)?;
+        let start = offset.min(len);
+        let end = start.saturating_add(limit).min(len);
+        let data = info.try_borrow_data()?;
+        Ok(data[ESCROWS_OFFSET + start * 32..ESCROWS_OFFSET + end * 32]
+            .chunks_exact(32)
+            .map(|key| Pubkey::try_from(key).unwrap())
+            .collect())
+    }
+
+    fn check_discriminator(info: &AccountInfo) -> Result<()> {
+        let data = info.try_borrow_data()?;
+        require!(
+            data.len() >= ESCROWS_OFFSET,
+            ErrorCode::AccountDiscriminatorNotFound
+        );
+        require!(
+            data[..ANCHOR_DISCRIMINATOR_SIZE] == Self::DISCRIMINATOR,
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        Ok(())
+    }
+
+    /// Creates the empty registry of `sender` at its PDA `info`. Done by hand rather than
+    /// with `init_if_needed`, which would deserialize existing registries in full.
+    fn create<'info>(
+        info: &AccountInfo<'info>,
+        sender: &Pubkey,
+        bump: u8,
+        payer: &AccountInfo<'info>,
+        system_program: &AccountInfo<'info>,
+    ) -> Result<()> {
+        let space = Self::space(0);
+        let seeds: &[&[u8]] = &[SEED_SENDER_REGISTRY, sender.as_ref(), &[bump]];
+        Self::fund(info, space, payer, system_program)?;
+        anchor_lang::system_program::allocate(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                Allocate {
+                    account_to_allocate: info.clone(),
+                },
+                &[seeds],
+            ),
+            space as u64,
+        )?;
+        anchor_lang::system_program::assign(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                Assign {
+                    account_to_assign: info.clone(),
+                },
+                &[seeds],
+            ),
+            &crate::ID,
+        )?;
+
+        let mut data = info.try_borrow_mut_data()?;
+        data[..ANCHOR_DISCRIMINATOR_SIZE].copy_from_slice(&Self::DISCRIMINATOR);
+        data[SENDER_OFFSET..BUMP_OFFSET].copy_from_slice(sender.as_ref());
+        data[BUMP_OFFSET] = bump;
+        Ok(())
+    }
+
+    /// Tops `info` up to the rent-exempt minimum for `space` bytes.
+    fn fund<'info>(
+        info: &AccountInfo<'info>,
+        space: usize,
+        payer: &AccountInfo<'info>,
+        system_program: &AccountInfo<'info>,
+    ) -> Result<()> {
+        let rent_due = Rent::get()?
+            .minimum_balance(space)
+            .saturating_sub(info.lamports());
+        if rent_due == 0 {
+            return Ok(());
+        }
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                Transfer {
+                    from: payer.clone(),
+                    to: info.clone(),
+                },
+            ),
+            rent_due,
+        )
+    }
+}
+
+fn read_len(data: &[u8]) -> usize {
+    u32::from_le_bytes(data[LEN_OFFSET..ESCROWS_OFFSET].try_into().unwrap()) as usize
+}
+
+fn write_len(data: &mut [u8], len: usize) {
+    data[LEN_OFFSET..ESCROWS_OFFSET].copy_from_slice(&(len as u32).to_le_bytes());
+}
@@ -0,0 +1,61 @@
+//file: src/state/versioned.rs
+
+/// Implements the Anchor account traits for an account whose layout grows over time.
+///
+/// Accounts written by an older program version are shorter than the current layout.
+/// They are zero-extended before decoding, so every field appended since then reads as
+/// its zero value (`version == 0` for accounts created before versioning existed).
+///
+/// The discriminator is passed explicitly and must stay `sha256("account:<Name>")[..8]`
+/// so existing accounts keep matching.
+macro_rules! versioned_account {
+    ($name:ident, $discriminator:expr) => {
+        impl anchor_lang::Discriminator for $name {
+            const DISCRIMINATOR: [u8; 8] = $discriminator;
+        }
+
+        impl anchor_lang::Owner for $name {
+            fn owner() -> Pubkey {
+                crate::ID
+            }
+        }
+
+        impl anchor_lang::AccountSerialize for $name {
+            fn try_serialize<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+                if writer
+                    .write_all(&<Self as anchor_lang::Discriminator>::DISCRIMINATOR)
+                    .is_err()
+                {
+                    return Err(ErrorCode::AccountDidNotSerialize.into());
+                }
+                if AnchorSerialize::serialize(self, writer).is_err() {
+                    return Err(ErrorCode::AccountDidNotSerialize.into());
+                }
+                Ok(())
+            }
+        }
+
+        impl anchor_lang::AccountDeserialize for $name {
+            fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+                let discriminator = <Self as anchor_lang::Discriminator>::DISCRIMINATOR;
+                if buf.len() < discriminator.len() {
+                    return Err(ErrorCode::AccountDiscriminatorNotFound.into());
+                }
+                if buf[..8] != discriminator {
+                    return Err(error!(ErrorCode::AccountDiscriminatorMismatch)
+                        .with_account_name(stringify!($name)));
+                }
+                Self::try_deserialize_unchecked(buf)
+            }
+
+            fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+                let mut data = buf[8..].to_vec();
+                if data.len() < <Self as anchor_lang::Space>::INIT_SPACE {
+                    data.resize(<Self as anchor_lang::Space>::INIT_SPACE, 0);
+                }
+                AnchorDeserialize::deserialize(&mut data.as_slice())
+                    .map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+            }
+        }
+    };
+}
@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+
+use crate::error::CustomError;
+
+/// Linear release schedule for streaming escrows. All zeroes for regular escrows,
+/// which release the whole amount at once.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, InitSpace)]
+pub struct VestingSchedule {
+    pub start_time: i64,
+    /// Nothing vests before this time; equal to `start_time` when there is no cliff.
+    pub cliff_time: i64,
+    pub end_time: i64,
+}
+
+impl VestingSchedule {
+    pub fn new(
+        start_time: i64,
+        cliff_time: Option<i64>,
+        end_time: i64,
+        expiration_time: i64,
+    ) -> Result<Self> {
+        let cliff_time = cliff_time.unwrap_or(start_time);
+        // The sender can refund everything left once the escrow expires, so the stream
+        // has to finish first.
+        require!(
+            start_time < end_time
+                && (start_time..=end_time).contains(&cliff_time)
+                && end_time <= expiration_time,
+            CustomError::InvalidVestingSchedule
+        );
+        Ok(Self {
+            start_time,
+            cliff_time,
+            end_time,
+        })
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.end_time != 0
+    }
+
+    /// Portion of `amount` released by `now`.
+    pub fn vested(&self, amount: u64, now: i64) -> u64 {
+        if now < self.cliff_time {
+            return 0;
+        }
+        if now >= self.end_time {
+            return amount;
+        }
+        let elapsed = (now - self.start_time) as u128;
+        let duration = (self.end_time - self.start_time) as u128;
+        (amount as u128 * elapsed / duration) as u64
+    }
+
+    /// Stops the stream at `now` and returns what has vested so far, which becomes the
+    /// escrow's new total. Ending the schedule at `now` keeps that total withdrawable.
+    pub fn cancel(&mut self, amount: u64, now: i64) -> u64 {
+        let vested = self.vested(amount, now);
+        if vested > 0 {
+            // Something vested, so `cliff_time <= now` and `start_time < now`.
+            self.end_time = now;
+        }
+        vested
+    }
+}
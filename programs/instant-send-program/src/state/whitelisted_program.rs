@@ -0,0 +1,10 @@
+//file: src/state/whitelisted_program.rs
+use anchor_lang::prelude::*;
+
+/// Marks a program that `redeem_and_invoke` may call. Removing it closes the PDA.
+#[account]
+#[derive(InitSpace)]
+pub struct WhitelistedProgram {
+    pub program_id: Pubkey,
+    pub bump: u8,
+}
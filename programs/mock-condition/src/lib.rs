@@ -0,0 +1,62 @@
+use anchor_lang::prelude::*;
+
+declare_id!("GyeTfGLqW561LC8YEuKS3vVk8RXfF3Eci9F6nCfzpA3Q");
+
+// Minimal claim condition: `check` passes or fails depending on a flag, which anyone can flip.
+// Real conditions would read an oracle, an attestation or a game account instead.
+#[program]
+pub mod mock_condition {
+    use super::*;
+
+    pub fn initialize(ctx: Context<Initialize>, pass: bool) -> Result<()> {
+        ctx.accounts.condition_config.pass = pass;
+        Ok(())
+    }
+
+    pub fn set_pass(ctx: Context<SetPass>, pass: bool) -> Result<()> {
+        ctx.accounts.condition_config.pass = pass;
+        Ok(())
+    }
+
+    pub fn check(ctx: Context<Check>) -> Result<()> {
+        require!(ctx.accounts.condition_config.pass, ConditionError::Rejected);
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ConditionConfig {
+    pub pass: bool,
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(init, payer = payer, space = 8 + ConditionConfig::INIT_SPACE)]
+    pub condition_config: Account<'info, ConditionConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPass<'info> {
+    #[account(mut)]
+    pub condition_config: Account<'info, ConditionConfig>,
+}
+
+// Account order expected by the escrow program: config, escrow, recipient.
+#[derive(Accounts)]
+pub struct Check<'info> {
+    pub condition_config: Account<'info, ConditionConfig>,
+    /// CHECK: Not read by this condition.
+    pub escrow: UncheckedAccount<'info>,
+    /// CHECK: Not read by this condition.
+    pub recipient: UncheckedAccount<'info>,
+}
+
+#[error_code]
+pub enum ConditionError {
+    #[msg("The claim condition is not met.")]
+    Rejected,
+}
@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    Mint, MintTo, TokenAccount, TokenInterface, TransferChecked,
+};
+
+declare_id!("6s5cTpuf6Uehk8WPm61VhFk6dPuHmJpojcdaRSEaq2He");
+
+pub const SEED_MINT_AUTHORITY: &[u8] = b"mint_authority";
+
+// Takes `amount_in` of any token into a vault and mints `amount_out` of an output mint
+// whose authority is this program's PDA. Only used by tests.
+#[program]
+pub mod mock_swap {
+    use super::*;
+
+    pub fn swap(ctx: Context<Swap>, amount_in: u64, amount_out: u64) -> Result<()> {
+        anchor_spl::token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.user_source.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                    mint: ctx.accounts.source_mint.to_account_info(),
+                },
+            ),
+            amount_in,
+            ctx.accounts.source_mint.decimals,
+        )?;
+
+        let seeds = &[SEED_MINT_AUTHORITY, &[ctx.bumps.mint_authority]];
+        anchor_spl::token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.output_mint.to_account_info(),
+                    to: ctx.accounts.user_destination.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            amount_out,
+        )
+    }
+}
+
+#[derive(Accounts)]
+pub struct Swap<'info> {
+    pub user: Signer<'info>,
+    #[account(mut, token::mint = source_mint, token::authority = user)]
+    pub user_source: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = source_mint)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub source_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, mint::authority = mint_authority)]
+    pub output_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, token::mint = output_mint)]
+    pub user_destination: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: PDA that signs as the output mint authority.
+    #[account(seeds = [SEED_MINT_AUTHORITY], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
@@ -0,0 +1,169 @@
+use anchor_lang::prelude::*;
+use instant_send_program::cpi::accounts::{InitializeTransferSOL, InitializeTransferSPL};
+use instant_send_program::instructions::initialize_transfer::EscrowOptions;
+use instant_send_program::program::InstantSendProgram;
+use instant_send_program::RentPolicy;
+
+declare_id!("62Bd98Lhv8XVZighpaCxyfLv37S6bSUhoDG9wDvdAY4r");
+
+pub const SEED_TREASURY: &[u8] = b"treasury";
+
+// Pays out of a PDA treasury by creating instant-send links through CPI, the way a payroll
+// or rewards program would. The treasury is a system account holding lamports, so it can
+// pay the escrow rent too. Only used by tests.
+#[program]
+pub mod mock_treasury {
+    use super::*;
+
+    pub fn send_sol(
+        ctx: Context<SendSol>,
+        amount: u64,
+        expiration_time: i64,
+        hash_of_secret: [u8; 32],
+    ) -> Result<Pubkey> {
+        let seeds = &[SEED_TREASURY, &[ctx.bumps.treasury]];
+        let accounts = &ctx.accounts.instant_send;
+        let escrow = instant_send_program::cpi::initialize_transfer_sol(
+            CpiContext::new_with_signer(
+                accounts.instant_send_program.to_account_info(),
+                InitializeTransferSOL {
+                    sender: ctx.accounts.treasury.to_account_info(),
+                    sender_nonce: accounts.sender_nonce.to_account_info(),
+                    sender_registry: accounts.sender_registry.to_account_info(),
+                    escrow_account: accounts.escrow_account.to_account_info(),
+                    escrow_memo: None,
+                    config: accounts.config.to_account_info(),
+                    mint_limits: accounts.mint_limits.to_account_info(),
+                    sender_usage: accounts.sender_usage.to_account_info(),
+                    system_program: accounts.system_program.to_account_info(),
+                    associated_token_program: None,
+                    rent: accounts.rent.to_account_info(),
+                    receipt_mint: None,
+                    receipt_account: None,
+                    receipt_token_program: None,
+                },
+                &[&seeds[..]],
+            ),
+            amount,
+            expiration_time,
+            hash_of_secret,
+            options(),
+        )?
+        .get();
+        require_keys_eq!(escrow, accounts.escrow_account.key());
+        Ok(escrow)
+    }
+
+    pub fn send_spl(
+        ctx: Context<SendSpl>,
+        amount: u64,
+        expiration_time: i64,
+        hash_of_secret: [u8; 32],
+    ) -> Result<Pubkey> {
+        let seeds = &[SEED_TREASURY, &[ctx.bumps.treasury]];
+        let accounts = &ctx.accounts.instant_send;
+        let escrow = instant_send_program::cpi::initialize_transfer_spl(
+            CpiContext::new_with_signer(
+                accounts.instant_send_program.to_account_info(),
+                InitializeTransferSPL {
+                    sender: ctx.accounts.treasury.to_account_info(),
+                    sender_nonce: accounts.sender_nonce.to_account_info(),
+                    sender_registry: accounts.sender_registry.to_account_info(),
+                    escrow_account: accounts.escrow_account.to_account_info(),
+                    escrow_memo: None,
+                    escrow_token_account: ctx.accounts.escrow_token_account.to_account_info(),
+                    sender_token_account: ctx.accounts.treasury_token_account.to_account_info(),
+                    token_mint: ctx.accounts.token_mint.to_account_info(),
+                    config: accounts.config.to_account_info(),
+                    mint_list_page: ctx.accounts.mint_list_page.to_account_info(),
+                    mint_limits: accounts.mint_limits.to_account_info(),
+                    sender_usage: accounts.sender_usage.to_account_info(),
+                    system_program: accounts.system_program.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    associated_token_program: ctx
+                        .accounts
+                        .associated_token_program
+                        .to_account_info(),
+                    rent: accounts.rent.to_account_info(),
+                    receipt_mint: None,
+                    receipt_account: None,
+                    receipt_token_program: None,
+                },
+                &[&seeds[..]],
+            ),
+            amount,
+            expiration_time,
+            hash_of_secret,
+            options(),
+        )?
+        .get();
+        require_keys_eq!(escrow, accounts.escrow_account.key());
+        Ok(escrow)
+    }
+}
+
+fn options() -> EscrowOptions {
+    EscrowOptions {
+        rent_policy: RentPolicy::Sender,
+        pin_hash: None,
+        condition: None,
+        approval: None,
+        vesting: None,
+        allowance: None,
+        memo: None,
+        receipt: false,
+    }
+}
+
+// The escrow program checks everything it is handed, so its accounts are passed through
+// unchecked.
+#[derive(Accounts)]
+pub struct InstantSendAccounts<'info> {
+    /// CHECK: Checked by the escrow program.
+    #[account(mut)]
+    pub sender_nonce: UncheckedAccount<'info>,
+    /// CHECK: Checked by the escrow program.
+    #[account(mut)]
+    pub sender_registry: UncheckedAccount<'info>,
+    /// CHECK: Checked by the escrow program.
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// CHECK: Checked by the escrow program.
+    pub config: UncheckedAccount<'info>,
+    /// CHECK: Checked by the escrow program.
+    pub mint_limits: UncheckedAccount<'info>,
+    /// CHECK: Checked by the escrow program.
+    #[account(mut)]
+    pub sender_usage: UncheckedAccount<'info>,
+    pub instant_send_program: Program<'info, InstantSendProgram>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct SendSol<'info> {
+    #[account(mut, seeds = [SEED_TREASURY], bump)]
+    pub treasury: SystemAccount<'info>,
+    pub instant_send: InstantSendAccounts<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SendSpl<'info> {
+    #[account(mut, seeds = [SEED_TREASURY], bump)]
+    pub treasury: SystemAccount<'info>,
+    pub instant_send: InstantSendAccounts<'info>,
+    /// CHECK: Created by the escrow program.
+    #[account(mut)]
+    pub escrow_token_account: UncheckedAccount<'info>,
+    /// CHECK: Any token account the treasury owns; checked by the escrow program.
+    #[account(mut)]
+    pub treasury_token_account: UncheckedAccount<'info>,
+    /// CHECK: Checked by the escrow program.
+    pub token_mint: UncheckedAccount<'info>,
+    /// CHECK: Checked by the escrow program.
+    pub mint_list_page: UncheckedAccount<'info>,
+    /// CHECK: Checked by the escrow program.
+    pub token_program: UncheckedAccount<'info>,
+    /// CHECK: Checked by the escrow program.
+    pub associated_token_program: UncheckedAccount<'info>,
+}
@@ -0,0 +1,377 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SYSVAR_CLOCK_PUBKEY, SYSVAR_RENT_PUBKEY, SystemProgram } from "@solana/web3.js";
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  createAccount,
+  createMint,
+  getAssociatedTokenAddressSync,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import { InstantSendProgram } from "../target/types/instant_send_program";
+import {
+  SEED_ESCROW_SOL,
+  SEED_ESCROW_SPL,
+  deriveConfig,
+  deriveEscrowAddress,
+  deriveSenderRegistry,
+  escrowOptions,
+  fundedKeypair,
+  generateSecret,
+  hashSecret,
+} from "./utils";
+
+const IDL = require("../target/idl/instant_send_program");
+
+const MEMO_PROGRAM_ID = new PublicKey("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+type Substitution = { account: string; with: () => object; error: string };
+
+// Every account of the redeem and refund contexts is swapped, one at a time, for a plausible
+// wrong one. Anchor deserializes accounts first, then runs `init_if_needed`, then checks
+// constraints in field order, so each substitution below can only trip the error it names.
+// `signer` is free by design: anyone holding the secret may submit the transaction.
+describe("Account substitution", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = new Program<InstantSendProgram>(IDL, provider);
+
+  let sender: Keypair;
+  let recipient: Keypair;
+  let stranger: Keypair;
+  let mint: PublicKey;
+  let otherMint: PublicKey;
+  let senderTokenAccount: PublicKey;
+  let recipientTokenAccount: PublicKey;
+  let strangerTokenAccount: PublicKey;
+  // Escrows of the other kind, and a memo account belonging to someone else's escrow.
+  let solEscrow: PublicKey;
+  let splEscrow: PublicKey;
+  let foreignMemo: PublicKey;
+
+  const expiresIn = (seconds: number) => new anchor.BN(Math.floor(Date.now() / 1000) + seconds);
+  const senderRegistry = () => deriveSenderRegistry(program.programId, sender.publicKey);
+
+  async function createSol(secret: Buffer, seconds = 3600): Promise<PublicKey> {
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SOL, sender.publicKey);
+    await program.methods
+      .initializeTransferSol(new anchor.BN(1_000_000), expiresIn(seconds), hashSecret(secret), escrowOptions())
+      .accounts({ sender: sender.publicKey, escrowAccount: escrow } as any)
+      .signers([sender])
+      .rpc();
+    return escrow;
+  }
+
+  async function createSpl(secret: Buffer, seconds = 3600): Promise<[PublicKey, PublicKey]> {
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SPL, sender.publicKey);
+    const vault = getAssociatedTokenAddressSync(mint, escrow, true);
+    await program.methods
+      .initializeTransferSpl(new anchor.BN(10), expiresIn(seconds), hashSecret(secret), escrowOptions())
+      .accounts({
+        sender: sender.publicKey,
+        escrowAccount: escrow,
+        escrowTokenAccount: vault,
+        senderTokenAccount,
+        tokenMint: mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      } as any)
+      .signers([sender])
+      .rpc();
+    return [escrow, vault];
+  }
+
+  async function expectError(promise: Promise<unknown>, code: string) {
+    try {
+      await promise;
+      expect.fail(`expected ${code}`);
+    } catch (err) {
+      expect(err.error?.errorCode?.code, String(err)).to.equal(code);
+    }
+  }
+
+  before(async () => {
+    sender = await fundedKeypair(provider, 5);
+    recipient = await fundedKeypair(provider, 1);
+    stranger = await fundedKeypair(provider, 1);
+
+    mint = await createMint(provider.connection, sender, sender.publicKey, null, 0);
+    otherMint = await createMint(provider.connection, sender, sender.publicKey, null, 0);
+    const tokenAccount = async (owner: PublicKey) =>
+      (await getOrCreateAssociatedTokenAccount(provider.connection, sender, mint, owner)).address;
+    senderTokenAccount = await tokenAccount(sender.publicKey);
+    // Existing accounts make `init_if_needed` compare them instead of creating new ones.
+    recipientTokenAccount = await tokenAccount(recipient.publicKey);
+    strangerTokenAccount = await tokenAccount(stranger.publicKey);
+    await mintTo(provider.connection, sender, mint, senderTokenAccount, sender, 1_000);
+
+    solEscrow = await createSol(generateSecret());
+    [splEscrow] = await createSpl(generateSecret());
+
+    const memoSender = await fundedKeypair(provider);
+    const memoEscrow = await deriveEscrowAddress(program, SEED_ESCROW_SOL, memoSender.publicKey);
+    foreignMemo = PublicKey.findProgramAddressSync(
+      [Buffer.from("escrow_memo"), memoEscrow.toBuffer()],
+      program.programId
+    )[0];
+    await program.methods
+      .initializeTransferSol(new anchor.BN(1_000_000), expiresIn(3600), hashSecret(generateSecret()), escrowOptions({ memo: "not yours" }))
+      .accounts({ sender: memoSender.publicKey, escrowAccount: memoEscrow, escrowMemo: foreignMemo } as any)
+      .signers([memoSender])
+      .rpc();
+  });
+
+  // An account the escrow PDA owns that is not its associated token account for the mint.
+  async function strayVault(escrow: PublicKey, tokenMint: PublicKey): Promise<PublicKey> {
+    return createAccount(provider.connection, sender, tokenMint, escrow, Keypair.generate());
+  }
+
+  describe("redeem_funds_spl_v2", () => {
+    const secret = generateSecret();
+    let escrow: PublicKey;
+    let vault: PublicKey;
+
+    before(async () => {
+      [escrow, vault] = await createSpl(secret);
+    });
+
+    const accounts = () => ({
+      signer: recipient.publicKey,
+      recipient: recipient.publicKey,
+      escrowAccount: escrow,
+      escrowTokenAccount: vault,
+      recipientTokenAccount,
+      tokenMint: mint,
+      sender: sender.publicKey,
+      rentReceiver: sender.publicKey,
+      senderRegistry: senderRegistry(),
+      conditionProgram: null,
+      conditionConfig: null,
+      escrowMemo: null,
+      memoProgram: null,
+      receiptMint: null,
+      receiptAccount: null,
+      receiptTokenProgram: null,
+      config: deriveConfig(program.programId),
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+      rent: SYSVAR_RENT_PUBKEY,
+    });
+
+    const redeem = (overrides: object) =>
+      program.methods
+        .redeemFundsSplV2(secret, null)
+        .accountsStrict({ ...accounts(), ...overrides } as any)
+        .signers([recipient])
+        .rpc();
+
+    const substitutions: Substitution[] = [
+      // The token account follows the recipient, so a new recipient fails on it.
+      { account: "recipient", with: () => ({ recipient: stranger.publicKey }), error: "ConstraintTokenOwner" },
+      { account: "escrowAccount", with: () => ({ escrowAccount: solEscrow }), error: "AccountDiscriminatorMismatch" },
+      { account: "escrowTokenAccount (sender's)", with: () => ({ escrowTokenAccount: senderTokenAccount }), error: "ConstraintTokenOwner" },
+      { account: "recipientTokenAccount", with: () => ({ recipientTokenAccount: strangerTokenAccount }), error: "ConstraintTokenOwner" },
+      { account: "tokenMint", with: () => ({ tokenMint: otherMint }), error: "ConstraintTokenMint" },
+      { account: "sender", with: () => ({ sender: stranger.publicKey }), error: "ConstraintAddress" },
+      { account: "rentReceiver", with: () => ({ rentReceiver: stranger.publicKey }), error: "InvalidRentReceiver" },
+      { account: "senderRegistry", with: () => ({ senderRegistry: deriveSenderRegistry(program.programId, stranger.publicKey) }), error: "ConstraintSeeds" },
+      { account: "escrowMemo", with: () => ({ escrowMemo: foreignMemo }), error: "ConstraintSeeds" },
+      { account: "memoProgram", with: () => ({ memoProgram: SystemProgram.programId }), error: "InvalidProgramId" },
+      { account: "config", with: () => ({ config: senderRegistry() }), error: "ConstraintSeeds" },
+      { account: "tokenProgram", with: () => ({ tokenProgram: TOKEN_2022_PROGRAM_ID }), error: "ConstraintAssociatedTokenTokenProgram" },
+      { account: "associatedTokenProgram", with: () => ({ associatedTokenProgram: TOKEN_PROGRAM_ID }), error: "InvalidProgramId" },
+      { account: "systemProgram", with: () => ({ systemProgram: TOKEN_PROGRAM_ID }), error: "InvalidProgramId" },
+      { account: "rent", with: () => ({ rent: SYSVAR_CLOCK_PUBKEY }), error: "AccountSysvarMismatch" },
+    ];
+
+    for (const { account, with: substitute, error } of substitutions) {
+      it(`rejects a substituted ${account}`, async () => {
+        await expectError(redeem(substitute()), error);
+      });
+    }
+
+    it("rejects an escrow-owned token account that is not its associated one", async () => {
+      await expectError(redeem({ escrowTokenAccount: await strayVault(escrow, mint) }), "ConstraintAssociated");
+    });
+
+    it("rejects the escrow's associated token account for another mint", async () => {
+      const otherVault = (
+        await getOrCreateAssociatedTokenAccount(provider.connection, sender, otherMint, escrow, true)
+      ).address;
+      await expectError(redeem({ escrowTokenAccount: otherVault }), "ConstraintAssociated");
+    });
+
+    it("redeems with the real accounts", async () => {
+      await redeem({});
+      expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+    });
+  });
+
+  describe("refund_funds_spl_v2", () => {
+    const secret = generateSecret();
+    let escrow: PublicKey;
+    let vault: PublicKey;
+
+    before(async () => {
+      [escrow, vault] = await createSpl(secret, 2);
+    });
+
+    const accounts = () => ({
+      signer: sender.publicKey,
+      escrowAccount: escrow,
+      escrowTokenAccount: vault,
+      sender: sender.publicKey,
+      senderTokenAccount,
+      tokenMint: mint,
+      senderRegistry: senderRegistry(),
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      rent: SYSVAR_RENT_PUBKEY,
+    });
+
+    const refund = (overrides: object) =>
+      program.methods
+        .refundFundsSplV2(secret)
+        .accountsStrict({ ...accounts(), ...overrides } as any)
+        .signers([sender])
+        .rpc();
+
+    const substitutions: Substitution[] = [
+      { account: "escrowAccount", with: () => ({ escrowAccount: solEscrow }), error: "AccountDiscriminatorMismatch" },
+      { account: "escrowTokenAccount (sender's)", with: () => ({ escrowTokenAccount: senderTokenAccount }), error: "ConstraintTokenOwner" },
+      // Swapped together with its token account so that only the address check can fail.
+      {
+        account: "sender",
+        with: () => ({ sender: stranger.publicKey, senderTokenAccount: strangerTokenAccount }),
+        error: "ConstraintAddress",
+      },
+      { account: "senderTokenAccount", with: () => ({ senderTokenAccount: recipientTokenAccount }), error: "ConstraintTokenOwner" },
+      { account: "tokenMint", with: () => ({ tokenMint: otherMint }), error: "ConstraintTokenMint" },
+      { account: "senderRegistry", with: () => ({ senderRegistry: deriveSenderRegistry(program.programId, stranger.publicKey) }), error: "ConstraintSeeds" },
+      { account: "tokenProgram", with: () => ({ tokenProgram: TOKEN_2022_PROGRAM_ID }), error: "ConstraintAssociatedTokenTokenProgram" },
+      { account: "systemProgram", with: () => ({ systemProgram: TOKEN_PROGRAM_ID }), error: "InvalidProgramId" },
+      { account: "associatedTokenProgram", with: () => ({ associatedTokenProgram: TOKEN_PROGRAM_ID }), error: "InvalidProgramId" },
+      { account: "rent", with: () => ({ rent: SYSVAR_CLOCK_PUBKEY }), error: "AccountSysvarMismatch" },
+    ];
+
+    for (const { account, with: substitute, error } of substitutions) {
+      it(`rejects a substituted ${account}`, async () => {
+        await expectError(refund(substitute()), error);
+      });
+    }
+
+    it("rejects an escrow-owned token account that is not its associated one", async () => {
+      await expectError(refund({ escrowTokenAccount: await strayVault(escrow, mint) }), "ConstraintAssociated");
+    });
+
+    it("refunds with the real accounts", async () => {
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+      await refund({});
+      expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+    });
+  });
+
+  describe("redeem_funds_sol_v2", () => {
+    const secret = generateSecret();
+    let escrow: PublicKey;
+
+    before(async () => {
+      escrow = await createSol(secret);
+    });
+
+    const accounts = () => ({
+      signer: recipient.publicKey,
+      recipient: recipient.publicKey,
+      escrowAccount: escrow,
+      sender: sender.publicKey,
+      rentReceiver: sender.publicKey,
+      senderRegistry: senderRegistry(),
+      conditionProgram: null,
+      conditionConfig: null,
+      escrowMemo: null,
+      memoProgram: null,
+      receiptMint: null,
+      receiptAccount: null,
+      receiptTokenProgram: null,
+      config: deriveConfig(program.programId),
+      systemProgram: SystemProgram.programId,
+    });
+
+    const redeem = (overrides: object) =>
+      program.methods
+        .redeemFundsSolV2(secret, null)
+        .accountsStrict({ ...accounts(), ...overrides } as any)
+        .signers([recipient])
+        .rpc();
+
+    const substitutions: Substitution[] = [
+      { account: "escrowAccount", with: () => ({ escrowAccount: splEscrow }), error: "AccountDiscriminatorMismatch" },
+      { account: "sender", with: () => ({ sender: stranger.publicKey }), error: "ConstraintAddress" },
+      { account: "rentReceiver", with: () => ({ rentReceiver: stranger.publicKey }), error: "InvalidRentReceiver" },
+      { account: "senderRegistry", with: () => ({ senderRegistry: deriveSenderRegistry(program.programId, stranger.publicKey) }), error: "ConstraintSeeds" },
+      { account: "escrowMemo", with: () => ({ escrowMemo: foreignMemo }), error: "ConstraintSeeds" },
+      { account: "memoProgram", with: () => ({ memoProgram: SystemProgram.programId }), error: "InvalidProgramId" },
+      { account: "config", with: () => ({ config: senderRegistry() }), error: "ConstraintSeeds" },
+      { account: "systemProgram", with: () => ({ systemProgram: MEMO_PROGRAM_ID }), error: "InvalidProgramId" },
+    ];
+
+    for (const { account, with: substitute, error } of substitutions) {
+      it(`rejects a substituted ${account}`, async () => {
+        await expectError(redeem(substitute()), error);
+      });
+    }
+
+    it("redeems with the real accounts", async () => {
+      await redeem({});
+      expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+    });
+  });
+
+  describe("refund_funds_sol_v2", () => {
+    const secret = generateSecret();
+    let escrow: PublicKey;
+
+    before(async () => {
+      escrow = await createSol(secret, 2);
+    });
+
+    const accounts = () => ({
+      signer: sender.publicKey,
+      escrowAccount: escrow,
+      sender: sender.publicKey,
+      senderRegistry: senderRegistry(),
+      systemProgram: SystemProgram.programId,
+    });
+
+    const refund = (overrides: object) =>
+      program.methods
+        .refundFundsSolV2(secret)
+        .accountsStrict({ ...accounts(), ...overrides } as any)
+        .signers([sender])
+        .rpc();
+
+    const substitutions: Substitution[] = [
+      { account: "escrowAccount", with: () => ({ escrowAccount: splEscrow }), error: "AccountDiscriminatorMismatch" },
+      { account: "sender", with: () => ({ sender: stranger.publicKey }), error: "ConstraintAddress" },
+      { account: "senderRegistry", with: () => ({ senderRegistry: deriveSenderRegistry(program.programId, stranger.publicKey) }), error: "ConstraintSeeds" },
+      { account: "systemProgram", with: () => ({ systemProgram: TOKEN_PROGRAM_ID }), error: "InvalidProgramId" },
+    ];
+
+    for (const { account, with: substitute, error } of substitutions) {
+      it(`rejects a substituted ${account}`, async () => {
+        await expectError(refund(substitute()), error);
+      });
+    }
+
+    it("refunds with the real accounts", async () => {
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+      await refund({});
+      expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+    });
+  });
+});
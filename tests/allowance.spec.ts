@@ -0,0 +1,134 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { InstantSendProgram } from "../target/types/instant_send_program";
+import {
+  SEED_ESCROW_SOL,
+  balance,
+  deriveEscrowAddress,
+  deriveSenderRegistry,
+  escrowOptions,
+  fundedKeypair,
+  hashSecret,
+} from "./utils";
+
+const IDL = require("../target/idl/instant_send_program");
+
+const CAP = 10_000_000;
+
+describe("Allowance escrows", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = new Program<InstantSendProgram>(IDL, provider);
+
+  let sender: Keypair;
+  let recipient: Keypair;
+
+  before(async () => {
+    sender = await fundedKeypair(provider);
+    recipient = await fundedKeypair(provider, 1);
+  });
+
+  async function createAllowance(secret: string, amount: number): Promise<PublicKey> {
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SOL, sender.publicKey);
+    await program.methods
+      .initializeTransferSol(
+        new anchor.BN(amount),
+        new anchor.BN(Math.floor(Date.now() / 1000) + 3600),
+        hashSecret(secret),
+        escrowOptions({
+          allowance: { periodSeconds: new anchor.BN(7 * 24 * 3600), perPeriodCap: new anchor.BN(CAP) },
+        })
+      )
+      .accounts({ sender: sender.publicKey } as any)
+      .signers([sender])
+      .rpc();
+    return escrow;
+  }
+
+  const withdraw = (escrow: PublicKey, secret: string | null, amount: number) =>
+    program.methods
+      .withdrawAllowanceSol(secret, null, new anchor.BN(amount))
+      .accounts({
+        signer: recipient.publicKey,
+        recipient: recipient.publicKey,
+        escrowAccount: escrow,
+        rentReceiver: sender.publicKey,
+        senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+      } as any)
+      .signers([recipient])
+      .rpc();
+
+  async function expectError(promise: Promise<unknown>, code: string) {
+    try {
+      await promise;
+      expect.fail(`expected ${code}`);
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal(code);
+    }
+  }
+
+  it("allows withdrawals up to the per-period cap", async () => {
+    const secret = "allowance-cap";
+    const escrow = await createAllowance(secret, 5 * CAP);
+
+    const before = await balance(provider, recipient.publicKey);
+    await withdraw(escrow, secret, CAP / 2);
+    // Once bound, the beneficiary no longer needs the secret.
+    await withdraw(escrow, null, CAP / 2);
+    expect(await balance(provider, recipient.publicKey)).to.equal(before + CAP);
+
+    await expectError(withdraw(escrow, null, 1), "AllowanceExceeded");
+  });
+
+  it("lets the sender top up and close", async () => {
+    const secret = "allowance-top-up";
+    const escrow = await createAllowance(secret, CAP);
+    await withdraw(escrow, secret, CAP);
+
+    await program.methods
+      .topUpSol(new anchor.BN(3 * CAP))
+      .accounts({ sender: sender.publicKey, escrowAccount: escrow } as any)
+      .signers([sender])
+      .rpc();
+    const account = await program.account.escrowSolAccount.fetch(escrow);
+    expect(account.amount.toNumber()).to.equal(4 * CAP);
+    expect(account.withdrawn.toNumber()).to.equal(CAP);
+
+    const escrowBalance = await balance(provider, escrow);
+    const senderBefore = await balance(provider, sender.publicKey);
+    await program.methods
+      .closeAllowanceSol()
+      .accounts({
+        sender: sender.publicKey,
+        escrowAccount: escrow,
+        senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+      } as any)
+      .signers([sender])
+      .rpc();
+    expect(await balance(provider, sender.publicKey)).to.equal(senderBefore + escrowBalance);
+    expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+  });
+
+  it("keeps allowance escrows out of redeem_funds", async () => {
+    const secret = "allowance-redeem";
+    const escrow = await createAllowance(secret, CAP);
+
+    await expectError(
+      program.methods
+        .redeemFundsSol(secret)
+        .accounts({
+          signer: recipient.publicKey,
+          recipient: recipient.publicKey,
+          escrowAccount: escrow,
+          sender: sender.publicKey,
+          rentReceiver: sender.publicKey,
+          senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+        } as any)
+        .signers([recipient])
+        .rpc(),
+      "AllowanceEscrow"
+    );
+  });
+});
@@ -0,0 +1,138 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { InstantSendProgram } from "../target/types/instant_send_program";
+import {
+  SEED_ESCROW_SOL,
+  deriveEscrowAddress,
+  deriveSenderRegistry,
+  escrowOptions,
+  fundedKeypair,
+  hashSecret,
+} from "./utils";
+
+const IDL = require("../target/idl/instant_send_program");
+
+describe("M-of-N approvals", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = new Program<InstantSendProgram>(IDL, provider);
+
+  let sender: Keypair;
+  let recipient: Keypair;
+  const finance = Keypair.generate();
+  const manager = Keypair.generate();
+  const legal = Keypair.generate();
+
+  before(async () => {
+    sender = await fundedKeypair(provider);
+    recipient = await fundedKeypair(provider, 1);
+  });
+
+  async function createEscrow(secret: string): Promise<PublicKey> {
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SOL, sender.publicKey);
+    const approval = {
+      approvers: [finance.publicKey, manager.publicKey, legal.publicKey],
+      threshold: 2,
+    };
+    await program.methods
+      .initializeTransferSol(
+        new anchor.BN(1_000_000),
+        new anchor.BN(Math.floor(Date.now() / 1000) + 3600),
+        hashSecret(secret),
+        escrowOptions({ approval })
+      )
+      .accounts({ sender: sender.publicKey } as any)
+      .signers([sender])
+      .rpc();
+    return escrow;
+  }
+
+  const approve = (escrow: PublicKey, approver: Keypair) =>
+    program.methods
+      .approveReleaseSol()
+      .accounts({ approver: approver.publicKey, escrowAccount: escrow } as any)
+      .signers([approver])
+      .rpc();
+
+  const revoke = (escrow: PublicKey, approver: Keypair) =>
+    program.methods
+      .revokeApprovalSol()
+      .accounts({ approver: approver.publicKey, escrowAccount: escrow } as any)
+      .signers([approver])
+      .rpc();
+
+  const redeem = (secret: string, escrow: PublicKey) =>
+    program.methods
+      .redeemFundsSol(secret)
+      .accounts({
+        signer: recipient.publicKey,
+        recipient: recipient.publicKey,
+        escrowAccount: escrow,
+        sender: sender.publicKey,
+        rentReceiver: sender.publicKey,
+        senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+      } as any)
+      .signers([recipient])
+      .rpc();
+
+  async function expectError(promise: Promise<unknown>, code: string) {
+    try {
+      await promise;
+      expect.fail(`expected ${code}`);
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal(code);
+    }
+  }
+
+  it("blocks redemption until the threshold is met", async () => {
+    const secret = "approvals-threshold";
+    const escrow = await createEscrow(secret);
+
+    await approve(escrow, finance);
+    await expectError(redeem(secret, escrow), "ApprovalThresholdNotMet");
+
+    await approve(escrow, manager);
+    await redeem(secret, escrow);
+    expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+  });
+
+  it("rejects duplicate approvals and outsiders", async () => {
+    const escrow = await createEscrow("approvals-duplicates");
+
+    await approve(escrow, finance);
+    await expectError(approve(escrow, finance), "AlreadyApproved");
+    await expectError(approve(escrow, recipient), "NotApprover");
+
+    const account = await program.account.escrowSolAccount.fetch(escrow);
+    expect(account.approvers.approved).to.equal(1);
+  });
+
+  it("lets approvers revoke before release", async () => {
+    const secret = "approvals-revoke";
+    const escrow = await createEscrow(secret);
+
+    await approve(escrow, finance);
+    await approve(escrow, legal);
+    await revoke(escrow, legal);
+    await expectError(revoke(escrow, legal), "ApprovalNotFound");
+    await expectError(redeem(secret, escrow), "ApprovalThresholdNotMet");
+  });
+
+  it("rejects a threshold above the number of approvers", async () => {
+    await expectError(
+      program.methods
+        .initializeTransferSol(
+          new anchor.BN(1_000_000),
+          new anchor.BN(Math.floor(Date.now() / 1000) + 3600),
+          hashSecret("approvals-bad-threshold"),
+          escrowOptions({ approval: { approvers: [finance.publicKey], threshold: 2 } })
+        )
+        .accounts({ sender: sender.publicKey } as any)
+        .signers([sender])
+        .rpc(),
+      "InvalidApprovers"
+    );
+  });
+});
@@ -0,0 +1,154 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { InstantSendProgram } from "../target/types/instant_send_program";
+import { MockCondition } from "../target/types/mock_condition";
+import {
+  SEED_ESCROW_SOL,
+  deriveEscrowAddress,
+  deriveSenderRegistry,
+  escrowOptions,
+  fundedKeypair,
+  hashConditionConfig,
+  hashSecret,
+} from "./utils";
+
+const IDL = require("../target/idl/instant_send_program");
+const MOCK_CONDITION_IDL = require("../target/idl/mock_condition");
+
+describe("Claim conditions", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = new Program<InstantSendProgram>(IDL, provider);
+  const mockCondition = new Program<MockCondition>(MOCK_CONDITION_IDL, provider);
+
+  let sender: Keypair;
+  let recipient: Keypair;
+  let passConfig: Keypair;
+  let failConfig: Keypair;
+
+  before(async () => {
+    sender = await fundedKeypair(provider);
+    recipient = await fundedKeypair(provider, 1);
+
+    passConfig = Keypair.generate();
+    failConfig = Keypair.generate();
+    for (const [config, pass] of [[passConfig, true], [failConfig, false]] as [Keypair, boolean][]) {
+      await mockCondition.methods
+        .initialize(pass)
+        .accounts({ conditionConfig: config.publicKey } as any)
+        .signers([config])
+        .rpc();
+    }
+  });
+
+  async function createEscrow(secret: string, config: PublicKey): Promise<PublicKey> {
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SOL, sender.publicKey);
+    const condition = {
+      programId: mockCondition.programId,
+      configHash: hashConditionConfig(mockCondition.programId, config),
+    };
+    await program.methods
+      .initializeTransferSol(
+        new anchor.BN(1_000_000),
+        new anchor.BN(Math.floor(Date.now() / 1000) + 3600),
+        hashSecret(secret),
+        escrowOptions({ condition })
+      )
+      .accounts({ sender: sender.publicKey } as any)
+      .signers([sender])
+      .rpc();
+    return escrow;
+  }
+
+  function redeem(secret: string, escrow: PublicKey, accounts: object) {
+    return program.methods
+      .redeemFundsSol(secret)
+      .accounts({
+        signer: recipient.publicKey,
+        recipient: recipient.publicKey,
+        escrowAccount: escrow,
+        sender: sender.publicKey,
+        rentReceiver: sender.publicKey,
+        senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+        ...accounts,
+      } as any)
+      .signers([recipient])
+      .rpc();
+  }
+
+  it("redeems when the condition passes", async () => {
+    const secret = "claim-condition-pass";
+    const escrow = await createEscrow(secret, passConfig.publicKey);
+
+    await redeem(secret, escrow, {
+      conditionProgram: mockCondition.programId,
+      conditionConfig: passConfig.publicKey,
+    });
+
+    expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+  });
+
+  it("fails when the condition rejects", async () => {
+    const secret = "claim-condition-fail";
+    const escrow = await createEscrow(secret, failConfig.publicKey);
+
+    try {
+      await redeem(secret, escrow, {
+        conditionProgram: mockCondition.programId,
+        conditionConfig: failConfig.publicKey,
+      });
+      expect.fail("redeem should have failed");
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal("Rejected");
+    }
+    expect(await provider.connection.getAccountInfo(escrow)).to.not.be.null;
+  });
+
+  it("rejects a config other than the one committed at creation", async () => {
+    const secret = "claim-condition-swapped-config";
+    const escrow = await createEscrow(secret, failConfig.publicKey);
+
+    try {
+      await redeem(secret, escrow, {
+        conditionProgram: mockCondition.programId,
+        conditionConfig: passConfig.publicKey,
+      });
+      expect.fail("redeem should have failed");
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal("InvalidConditionConfig");
+    }
+  });
+
+  it("reads the config as it is at redemption, not as it was at creation", async () => {
+    const config = Keypair.generate();
+    await mockCondition.methods
+      .initialize(false)
+      .accounts({ conditionConfig: config.publicKey } as any)
+      .signers([config])
+      .rpc();
+    const secret = "claim-condition-updated-config";
+    const escrow = await createEscrow(secret, config.publicKey);
+
+    await mockCondition.methods.setPass(true).accounts({ conditionConfig: config.publicKey } as any).rpc();
+    await redeem(secret, escrow, {
+      conditionProgram: mockCondition.programId,
+      conditionConfig: config.publicKey,
+    });
+
+    expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+  });
+
+  it("requires the condition accounts", async () => {
+    const secret = "claim-condition-missing";
+    const escrow = await createEscrow(secret, passConfig.publicKey);
+
+    try {
+      await redeem(secret, escrow, { conditionProgram: null, conditionConfig: null });
+      expect.fail("redeem should have failed");
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal("ConditionAccountsMissing");
+    }
+  });
+});
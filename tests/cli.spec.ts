@@ -0,0 +1,119 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair } from "@solana/web3.js";
+import { expect } from "chai";
+import { execFileSync } from "child_process";
+import * as fs from "fs";
+import * as os from "os";
+import * as path from "path";
+import { fundedKeypair } from "./utils";
+
+const CLI = path.join(__dirname, "../target/debug/instant-send");
+
+describe("CLI", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const dir = fs.mkdtempSync(path.join(os.tmpdir(), "cli-"));
+  let sender: Keypair;
+  let recipient: Keypair;
+
+  const keypairFile = (name: string, keypair: Keypair) => {
+    const file = path.join(dir, `${name}.json`);
+    fs.writeFileSync(file, JSON.stringify(Array.from(keypair.secretKey)));
+    return file;
+  };
+
+  before(async () => {
+    execFileSync("cargo", ["build", "-p", "instant-send-cli"], { stdio: "inherit" });
+    sender = await fundedKeypair(provider);
+    recipient = await fundedKeypair(provider, 1);
+  });
+
+  const run = (keypair: Keypair, ...args: string[]) =>
+    JSON.parse(
+      execFileSync(
+        CLI,
+        ["--json", "--url", provider.connection.rpcEndpoint, "--keypair", keypairFile("signer", keypair), ...args],
+        { encoding: "utf8" }
+      )
+    );
+
+  // Failures exit non-zero and print `{"error": ...}` on stdout.
+  const fail = (keypair: Keypair, ...args: string[]): string => {
+    try {
+      run(keypair, ...args);
+    } catch (err: any) {
+      return JSON.parse(err.stdout).error;
+    }
+    throw new Error("command succeeded");
+  };
+
+  it("sends SOL, inspects the link and redeems it", async () => {
+    const sent = run(sender, "send-sol", "1000000", "--memo", "Lunch");
+    expect(sent.link).to.match(/^instant-send:\/\/claim\/\w+#[1-9A-HJ-NP-Za-km-z]{43,44}$/);
+
+    const inspected = run(sender, "inspect", sent.link);
+    expect(inspected.address).to.equal(sent.escrow);
+    expect(inspected.asset).to.equal("sol");
+    expect(inspected.amount).to.equal(1_000_000);
+    expect(inspected.memo).to.equal("Lunch");
+    expect(inspected.is_redeemed).to.be.false;
+
+    const listed = run(sender, "list");
+    expect(listed.map((escrow: any) => escrow.address)).to.include(sent.escrow);
+
+    const before = await provider.connection.getBalance(recipient.publicKey);
+    const redeemed = run(recipient, "redeem", sent.link);
+    expect(redeemed.recipient).to.equal(recipient.publicKey.toBase58());
+    const after = await provider.connection.getBalance(recipient.publicKey, "confirmed");
+    expect(after).to.be.greaterThan(before);
+  });
+
+  it("refunds an expired escrow", async () => {
+    const sent = run(sender, "send-sol", "500000", "--expires-in", "2");
+    await new Promise((resolve) => setTimeout(resolve, 3000));
+
+    const refunded = run(sender, "refund", sent.escrow, "--secret", sent.link.split("#")[1]);
+    expect(refunded.sender).to.equal(sender.publicKey.toBase58());
+  });
+
+  it("bulk-sends a CSV and resumes without sending twice", async () => {
+    const input = path.join(dir, "payouts.csv");
+    const output = path.join(dir, "links.csv");
+    fs.writeFileSync(
+      input,
+      ["amount,mint,expires_in,email", "100000,,,ada@example.com", "200000,SOL,3600,", "300000,,,bob@example.com"].join(
+        "\n"
+      )
+    );
+
+    const first = run(sender, "bulk-send", input, "--output", output);
+    expect(first.rows).to.equal(3);
+    expect(first.sent).to.equal(3);
+
+    const sheet = fs.readFileSync(output, "utf8").trim().split("\n");
+    expect(sheet[0]).to.equal("email,amount,mint,expiration_time,escrow,link,signature");
+    const links = sheet.slice(1).map((line) => line.split(","));
+    expect(links.map((row) => row[0])).to.deep.equal(["ada@example.com", "", "bob@example.com"]);
+    for (const [, amount, , , escrow, link] of links) {
+      const inspected = run(sender, "inspect", link);
+      expect(inspected.address).to.equal(escrow);
+      expect(inspected.amount).to.equal(Number(amount));
+    }
+
+    const again = run(sender, "bulk-send", input, "--output", output);
+    expect(again.sent).to.equal(0);
+    expect(fs.readFileSync(output, "utf8").trim().split("\n")).to.deep.equal(sheet);
+
+    fs.appendFileSync(input, "\n400000,,,");
+    expect(fail(sender, "bulk-send", input, "--output", output)).to.match(/different input file/);
+  });
+
+  it("reports program errors by name", async () => {
+    const sent = run(sender, "send-sol", "500000");
+    const forged = sent.link.replace(/#.*$/, "#" + anchor.utils.bytes.bs58.encode(Buffer.alloc(32, 7)));
+
+    expect(fail(recipient, "redeem", forged)).to.match(/^InvalidSecret \(\d+\): The secret is invalid/);
+    expect(fail(sender, "refund", sent.link)).to.match(/^NotExpired/);
+  });
+});
@@ -0,0 +1,158 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  createAccount,
+  createMint,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import { InstantSendProgram } from "../target/types/instant_send_program";
+import { MockTreasury } from "../target/types/mock_treasury";
+import {
+  SEED_ESCROW_SOL,
+  SEED_ESCROW_SPL,
+  airdrop,
+  deriveConfig,
+  deriveEscrowAddress,
+  deriveSenderNonce,
+  deriveSenderRegistry,
+  escrowOptions,
+  fundedKeypair,
+  generateSecret,
+  hashSecret,
+} from "./utils";
+
+const IDL = require("../target/idl/instant_send_program");
+const MOCK_TREASURY_IDL = require("../target/idl/mock_treasury");
+
+// A program sending from its PDA treasury, signing for it with `invoke_signed`.
+describe("CPI senders", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = new Program<InstantSendProgram>(IDL, provider);
+  const treasuryProgram = new Program<MockTreasury>(MOCK_TREASURY_IDL, provider);
+
+  const [treasury] = PublicKey.findProgramAddressSync([Buffer.from("treasury")], treasuryProgram.programId);
+  const pda = (...seeds: Buffer[]) => PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+  const expiresIn = (seconds: number) => new anchor.BN(Math.floor(Date.now() / 1000) + seconds);
+
+  before(async () => {
+    // Lamports alone keep the treasury a system account, which can pay rent.
+    await airdrop(provider, treasury, 5);
+  });
+
+  // Accounts the escrow program needs from every sender, for `mint` or SOL.
+  const instantSendAccounts = (escrow: PublicKey, mint: PublicKey = SystemProgram.programId) => ({
+    senderNonce: deriveSenderNonce(program.programId, treasury),
+    senderRegistry: deriveSenderRegistry(program.programId, treasury),
+    escrowAccount: escrow,
+    config: deriveConfig(program.programId),
+    mintLimits: pda(Buffer.from("mint_limits"), mint.toBuffer()),
+    senderUsage: pda(Buffer.from("sender_usage"), treasury.toBuffer(), mint.toBuffer()),
+    instantSendProgram: program.programId,
+    systemProgram: SystemProgram.programId,
+    rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+  });
+
+  // The value the last program on the call stack returned, i.e. the mock relaying the escrow's.
+  async function returned(signature: string): Promise<[PublicKey, PublicKey]> {
+    const transaction = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const { programId, data } = transaction.meta.returnData;
+    return [new PublicKey(programId), new PublicKey(Buffer.from(data[0], "base64"))];
+  }
+
+  it("creates a SOL link from a PDA and returns the escrow address", async () => {
+    const secret = generateSecret();
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SOL, treasury);
+
+    const signature = await treasuryProgram.methods
+      .sendSol(new anchor.BN(1_000_000), expiresIn(3600), Array.from(hashSecret(secret)))
+      .accountsStrict({ treasury, instantSend: instantSendAccounts(escrow) })
+      .rpc({ commitment: "confirmed" });
+
+    expect(await returned(signature)).to.deep.equal([treasuryProgram.programId, escrow]);
+    const account = await program.account.escrowSolAccount.fetch(escrow);
+    expect(account.sender.toBase58()).to.equal(treasury.toBase58());
+    expect(account.amount.toNumber()).to.equal(1_000_000);
+
+    // The link redeems like any other.
+    const recipient = await fundedKeypair(provider, 1);
+    await program.methods
+      .redeemFundsSolV2(secret, null)
+      .accounts({
+        signer: recipient.publicKey,
+        recipient: recipient.publicKey,
+        escrowAccount: escrow,
+        sender: treasury,
+        rentReceiver: treasury,
+        senderRegistry: deriveSenderRegistry(program.programId, treasury),
+      } as any)
+      .signers([recipient])
+      .rpc();
+    expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+  });
+
+  it("creates an SPL link from a token account that is not the PDA's associated one", async () => {
+    const payer = await fundedKeypair(provider);
+    const mint = await createMint(provider.connection, payer, payer.publicKey, null, 0);
+    const treasuryTokenAccount = await createAccount(provider.connection, payer, mint, treasury, Keypair.generate());
+    await mintTo(provider.connection, payer, mint, treasuryTokenAccount, payer, 1_000);
+
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SPL, treasury);
+    const vault = getAssociatedTokenAddressSync(mint, escrow, true);
+    const signature = await treasuryProgram.methods
+      .sendSpl(new anchor.BN(250), expiresIn(3600), Array.from(hashSecret(generateSecret())))
+      .accountsStrict({
+        treasury,
+        instantSend: instantSendAccounts(escrow, mint),
+        escrowTokenAccount: vault,
+        treasuryTokenAccount,
+        tokenMint: mint,
+        mintListPage: pda(Buffer.from("mint_list"), mint.toBuffer().subarray(0, 1)),
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    expect(await returned(signature)).to.deep.equal([treasuryProgram.programId, escrow]);
+    expect(Number((await getAccount(provider.connection, vault, "confirmed")).amount)).to.equal(250);
+    expect(Number((await getAccount(provider.connection, treasuryTokenAccount, "confirmed")).amount)).to.equal(750);
+  });
+
+  it("rejects a source token account the sender does not own", async () => {
+    const sender = await fundedKeypair(provider);
+    const other = await fundedKeypair(provider, 1);
+    const mint = await createMint(provider.connection, sender, sender.publicKey, null, 0);
+    const otherTokenAccount = (await getOrCreateAssociatedTokenAccount(provider.connection, sender, mint, other.publicKey))
+      .address;
+    await mintTo(provider.connection, sender, mint, otherTokenAccount, sender, 100);
+
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SPL, sender.publicKey);
+    try {
+      await program.methods
+        .initializeTransferSpl(new anchor.BN(10), expiresIn(3600), hashSecret(generateSecret()), escrowOptions())
+        .accounts({
+          sender: sender.publicKey,
+          escrowAccount: escrow,
+          escrowTokenAccount: getAssociatedTokenAddressSync(mint, escrow, true),
+          senderTokenAccount: otherTokenAccount,
+          tokenMint: mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        } as any)
+        .signers([sender])
+        .rpc();
+      expect.fail("expected ConstraintTokenOwner");
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal("ConstraintTokenOwner");
+    }
+  });
+});
@@ -0,0 +1,123 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { expect } from "chai";
+import { InstantSendProgram } from "../target/types/instant_send_program";
+import { ensureConfig, escrowOptions, fundedKeypair, hashSecret } from "./utils";
+
+const IDL = require("../target/idl/instant_send_program");
+
+// SOL limits are keyed by the system program id.
+const NATIVE_MINT_KEY = SystemProgram.programId;
+const NO_CAPS = { maxEscrowAmount: new anchor.BN(0), windowSeconds: new anchor.BN(0), maxWindowVolume: new anchor.BN(0) };
+
+describe("Creation limits", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = new Program<InstantSendProgram>(IDL, provider);
+  const admin = (provider.wallet as anchor.Wallet).payer;
+
+  let sender: Keypair;
+
+  before(async () => {
+    await ensureConfig(program, provider);
+  });
+
+  beforeEach(async () => {
+    sender = await fundedKeypair(provider);
+  });
+
+  // Other specs share the config and create SOL escrows, so lift every cap.
+  afterEach(async () => {
+    await setCaps(NO_CAPS);
+    await setMaxActive(0);
+  });
+
+  const setCaps = (caps: object) =>
+    program.methods
+      .setMintLimits(NATIVE_MINT_KEY, { ...NO_CAPS, ...caps } as any)
+      .accounts({ admin: admin.publicKey } as any)
+      .rpc();
+
+  const setMaxActive = (max: number) =>
+    program.methods.setMaxActiveEscrows(max).accounts({ admin: admin.publicKey } as any).rpc();
+
+  const create = (secret: string, amount: number) =>
+    program.methods
+      .initializeTransferSol(
+        new anchor.BN(amount),
+        new anchor.BN(Math.floor(Date.now() / 1000) + 3600),
+        hashSecret(secret),
+        escrowOptions()
+      )
+      .accounts({ sender: sender.publicKey } as any)
+      .signers([sender])
+      .rpc();
+
+  const usagePda = () =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("sender_usage"), sender.publicKey.toBuffer(), NATIVE_MINT_KEY.toBuffer()],
+      program.programId
+    )[0];
+
+  // Windows follow the validator's clock, which can drift from ours.
+  async function waitForChainTime(unixTime: number) {
+    while ((await provider.connection.getBlockTime(await provider.connection.getSlot())) < unixTime) {
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+  }
+
+  async function expectError(promise: Promise<unknown>, code: string) {
+    try {
+      await promise;
+      expect.fail(`expected ${code}`);
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal(code);
+    }
+  }
+
+  it("caps the amount of a single escrow", async () => {
+    await setCaps({ maxEscrowAmount: new anchor.BN(1_000_000) });
+
+    await create("limits-amount-ok", 1_000_000);
+    await expectError(create("limits-amount-over", 1_000_001), "EscrowAmountCapExceeded");
+  });
+
+  it("caps the volume a sender escrows in one window", async () => {
+    await setCaps({ windowSeconds: new anchor.BN(3600), maxWindowVolume: new anchor.BN(1_500_000) });
+
+    await create("limits-volume-first", 1_000_000);
+    await expectError(create("limits-volume-over", 600_000), "WindowVolumeCapExceeded");
+    await create("limits-volume-rest", 500_000);
+
+    const { windowVolume, previousWindowVolume } = await program.account.senderUsage.fetch(usagePda());
+    expect(windowVolume.add(previousWindowVolume).toNumber()).to.equal(1_500_000);
+  });
+
+  it("keeps counting the previous window, so the cap can't be doubled across a boundary", async () => {
+    const windowSeconds = 4;
+    await setCaps({ windowSeconds: new anchor.BN(windowSeconds), maxWindowVolume: new anchor.BN(1_000_000) });
+
+    await create("limits-rolling-first", 1_000_000);
+    const windowStart = (await program.account.senderUsage.fetch(usagePda())).windowStart.toNumber();
+
+    // The next window has started, but the previous one still counts.
+    await waitForChainTime(windowStart + windowSeconds);
+    await expectError(create("limits-rolling-boundary", 1_000_000), "WindowVolumeCapExceeded");
+    // Once a whole window has passed without deposits the cap is free again.
+    await waitForChainTime(windowStart + 2 * windowSeconds);
+    await create("limits-rolling-later", 1_000_000);
+  });
+
+  it("rejects a volume cap without a window", async () => {
+    await expectError(setCaps({ maxWindowVolume: new anchor.BN(1) }), "InvalidLimits");
+  });
+
+  it("caps the number of open escrows per sender", async () => {
+    await setMaxActive(2);
+
+    await create("limits-active-1", 1_000);
+    await create("limits-active-2", 1_000);
+    await expectError(create("limits-active-3", 1_000), "TooManyActiveEscrows");
+  });
+});
@@ -0,0 +1,134 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  createMint,
+  getAssociatedTokenAddressSync,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import { InstantSendProgram } from "../target/types/instant_send_program";
+import {
+  SEED_ESCROW_SOL,
+  SEED_ESCROW_SPL,
+  deriveEscrowAddress,
+  deriveSenderRegistry,
+  escrowOptions,
+  fundedKeypair,
+  generateSecret,
+  hashSecret,
+} from "./utils";
+
+const IDL = require("../target/idl/instant_send_program");
+
+// The view instructions are only ever simulated; they answer through return data.
+describe("Escrow views", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = new Program<InstantSendProgram>(IDL, provider);
+
+  let sender: Keypair;
+
+  beforeEach(async () => {
+    sender = await fundedKeypair(provider);
+  });
+
+  const expiresIn = (seconds: number) => new anchor.BN(Math.floor(Date.now() / 1000) + seconds);
+  const status = (escrow: PublicKey) => program.methods.getEscrowStatus().accounts({ escrowAccount: escrow }).view();
+  const checkSecret = (escrow: PublicKey, secret: Buffer) =>
+    program.methods.checkSecret(secret).accounts({ escrowAccount: escrow }).view();
+
+  async function createSol(secret: Buffer | string): Promise<PublicKey> {
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SOL, sender.publicKey);
+    await program.methods
+      .initializeTransferSol(new anchor.BN(1_000_000), expiresIn(3 * 24 * 3600), hashSecret(secret), escrowOptions())
+      .accounts({ sender: sender.publicKey, escrowAccount: escrow } as any)
+      .signers([sender])
+      .rpc();
+    return escrow;
+  }
+
+  it("reports a SOL escrow and checks its secret", async () => {
+    const secret = generateSecret();
+    const escrow = await createSol(secret);
+
+    const result = await status(escrow);
+    expect(result.sender.toBase58()).to.equal(sender.publicKey.toBase58());
+    // `NATIVE_MINT_KEY` stands in for the mint of SOL escrows.
+    expect(result.mint.toBase58()).to.equal(SystemProgram.programId.toBase58());
+    expect(result.amount.toNumber()).to.equal(1_000_000);
+    expect(result.remaining.toNumber()).to.equal(1_000_000);
+    expect(result.expiresIn.toNumber()).to.be.within(3 * 24 * 3600 - 60, 3 * 24 * 3600);
+    expect(result.isRedeemed).to.be.false;
+    expect(result.pinRequired).to.be.false;
+    expect(result.isStream).to.be.false;
+    expect(result.riskFlags).to.equal(0);
+
+    expect(await checkSecret(escrow, secret)).to.be.true;
+    expect(await checkSecret(escrow, generateSecret())).to.be.false;
+  });
+
+  it("checks the secrets of legacy text links", async () => {
+    const escrow = await createSol("correct horse battery staple");
+
+    expect(await checkSecret(escrow, Buffer.from("correct horse battery staple"))).to.be.true;
+    expect(await checkSecret(escrow, Buffer.from("correct horse"))).to.be.false;
+  });
+
+  it("reports an SPL escrow", async () => {
+    const mint = await createMint(provider.connection, sender, sender.publicKey, null, 6);
+    const senderTokenAccount = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, sender, mint, sender.publicKey)
+    ).address;
+    await mintTo(provider.connection, sender, mint, senderTokenAccount, sender, 25_000_000);
+
+    const secret = generateSecret();
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SPL, sender.publicKey);
+    await program.methods
+      .initializeTransferSpl(new anchor.BN(25_000_000), expiresIn(3600), hashSecret(secret), escrowOptions())
+      .accounts({
+        sender: sender.publicKey,
+        escrowAccount: escrow,
+        escrowTokenAccount: getAssociatedTokenAddressSync(mint, escrow, true),
+        senderTokenAccount,
+        tokenMint: mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      } as any)
+      .signers([sender])
+      .rpc();
+
+    const result = await status(escrow);
+    expect(result.mint.toBase58()).to.equal(mint.toBase58());
+    expect(result.amount.toNumber()).to.equal(25_000_000);
+    expect(await checkSecret(escrow, secret)).to.be.true;
+  });
+
+  it("reports EscrowNotFound once the escrow is redeemed", async () => {
+    const secret = generateSecret();
+    const escrow = await createSol(secret);
+    const recipient = await fundedKeypair(provider, 1);
+    await program.methods
+      .redeemFundsSolV2(secret, null)
+      .accounts({
+        signer: recipient.publicKey,
+        recipient: recipient.publicKey,
+        escrowAccount: escrow,
+        sender: sender.publicKey,
+        rentReceiver: sender.publicKey,
+        senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+      } as any)
+      .signers([recipient])
+      .rpc();
+
+    try {
+      await status(escrow);
+      expect.fail("expected EscrowNotFound");
+    } catch (err) {
+      // A failed simulation carries the program's logs rather than a parsed AnchorError.
+      const logs: string[] = err.simulationResponse?.logs ?? err.logs ?? [];
+      expect(logs.join("\n") + err.message).to.contain("EscrowNotFound");
+    }
+  });
+});
@@ -1,17 +1,20 @@
+import { utils } from "@coral-xyz/anchor";
 import * as crypto from "crypto";
 
-const generateSecret = (length: number = 32): string => {
-  return crypto.randomBytes(length).toString("hex");
+// 32 random bytes, as taken by the `_v2` redeem and refund instructions and carried
+// base58 encoded in claim links.
+const generateSecret = (): Buffer => {
+  return crypto.randomBytes(32);
 };
 
-const hashSecret = (secret: string): string => {
+const hashSecret = (secret: Buffer): string => {
   const hash = crypto.createHash("sha256");
   hash.update(secret);
   return hash.digest("hex");
 };
 
 const secret = generateSecret();
-console.log("Generated Secret:", secret);
+console.log("Generated Secret:", utils.bytes.bs58.encode(secret));
 
 const hashedSecret = hashSecret(secret);
 console.log("Hashed Secret:", hashedSecret);
@@ -0,0 +1,115 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { execFileSync } from "child_process";
+import * as fs from "fs";
+import * as os from "os";
+import * as path from "path";
+import { InstantSendProgram } from "../target/types/instant_send_program";
+import {
+  SEED_ESCROW_SOL,
+  deriveEscrowAddress,
+  deriveSenderRegistry,
+  escrowOptions,
+  fundedKeypair,
+  hashSecret,
+} from "./utils";
+
+const IDL = require("../target/idl/instant_send_program");
+
+const INDEXER = path.join(__dirname, "../target/debug/instant-send-indexer");
+
+describe("Indexer", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = new Program<InstantSendProgram>(IDL, provider);
+
+  const db = path.join(fs.mkdtempSync(path.join(os.tmpdir(), "indexer-")), "escrows.db");
+  let sender: Keypair;
+  let recipient: Keypair;
+
+  before(async () => {
+    execFileSync("cargo", ["build", "-p", "instant-send-indexer"], { stdio: "inherit" });
+    sender = await fundedKeypair(provider);
+    recipient = await fundedKeypair(provider, 1);
+  });
+
+  const indexer = (...args: string[]) => execFileSync(INDEXER, ["--db", db, ...args], { encoding: "utf8" });
+
+  const sync = () => indexer("sync", "--once", "--url", provider.connection.rpcEndpoint);
+
+  const escrows = (...filters: string[]) =>
+    indexer("escrows", ...filters)
+      .split("\n")
+      .filter((line) => line.length > 0)
+      .map((line) => JSON.parse(line));
+
+  async function create(secret: string, memo: string | null = null): Promise<PublicKey> {
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SOL, sender.publicKey);
+    const escrowMemo = PublicKey.findProgramAddressSync(
+      [Buffer.from("escrow_memo"), escrow.toBuffer()],
+      program.programId
+    )[0];
+    await program.methods
+      .initializeTransferSol(
+        new anchor.BN(1_000_000),
+        new anchor.BN(Math.floor(Date.now() / 1000) + 3600),
+        hashSecret(secret),
+        escrowOptions({ memo })
+      )
+      .accounts({ sender: sender.publicKey, escrowAccount: escrow, escrowMemo: memo ? escrowMemo : null } as any)
+      .signers([sender])
+      .rpc({ commitment: "confirmed" });
+    return escrow;
+  }
+
+  const redeem = (escrow: PublicKey, secret: string) =>
+    program.methods
+      .redeemFundsSol(secret)
+      .accounts({
+        signer: recipient.publicKey,
+        recipient: recipient.publicKey,
+        escrowAccount: escrow,
+        sender: sender.publicKey,
+        rentReceiver: sender.publicKey,
+        senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+      } as any)
+      .signers([recipient])
+      .rpc({ commitment: "confirmed" });
+
+  it("records the lifecycle of escrows and answers queries", async () => {
+    const redeemed = await create("indexer-redeemed");
+    const open = await create("indexer-open", "Invoice #7");
+    await redeem(redeemed, "indexer-redeemed");
+
+    sync();
+    const rows = escrows("--sender", sender.publicKey.toBase58());
+    expect(rows.map((row) => row.escrow)).to.deep.equal([redeemed.toBase58(), open.toBase58()]);
+
+    const [redeemedRow] = escrows("--recipient", recipient.publicKey.toBase58());
+    expect(redeemedRow.escrow).to.equal(redeemed.toBase58());
+    expect(redeemedRow.status).to.equal("redeemed");
+    expect(redeemedRow.amount).to.equal(1_000_000);
+
+    const [openRow] = escrows("--sender", sender.publicKey.toBase58(), "--status", "open");
+    expect(openRow.escrow).to.equal(open.toBase58());
+    expect(openRow.memo).to.equal("Invoice #7");
+  });
+
+  it("resumes from the saved cursor without duplicating escrows", async () => {
+    const later = await create("indexer-later");
+
+    sync();
+    sync();
+    const rows = escrows("--sender", sender.publicKey.toBase58());
+    expect(rows).to.have.length(3);
+    expect(rows[2].escrow).to.equal(later.toBase58());
+  });
+
+  it("filters by creation time", async () => {
+    const now = Math.floor(Date.now() / 1000);
+    expect(escrows("--sender", sender.publicKey.toBase58(), "--until", String(now - 3600))).to.be.empty;
+    expect(escrows("--sender", sender.publicKey.toBase58(), "--since", String(now - 3600))).to.have.length(3);
+  });
+});
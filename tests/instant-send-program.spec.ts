@@ -18,6 +18,7 @@ import * as fs from "fs";
 import * as path from "path";
 import * as crypto from "crypto";
 import { Key } from "readline";
+import { deriveEscrowAddress, deriveSenderRegistry, escrowOptions } from "./utils";
 
 const IDL = require("../target/idl/instant_send_program");
 // const programAddress = new PublicKey(
@@ -187,6 +188,9 @@ describe("Instant Transfer", () => {
   let hashOfSecret;
   let SEED_ESCROW_SOL;
   let SEED_ESCROW_SPL;
+  // The claim link carries the escrow address, so later steps reuse it instead of re-deriving.
+  let escrowAccountPDASol: PublicKey;
+  let escrowAccountPDASpl: PublicKey;
   before("set secret, hashed_secret and seed-string buffer", async () => {
     secret = generateSecret();
     console.log(secret);
@@ -215,19 +219,10 @@ describe("Instant Transfer", () => {
     }
 
 
-    const [escrowAccountPDASol, escrowAccountBumpSol] =
-      await anchor.web3.PublicKey.findProgramAddressSync(
-        [
-          SEED_ESCROW_SOL,
-          // senderWallet.publicKey.toBuffer(),
-          hashOfSecret, // This should be a Buffer
-        ],
-        programAddress
-      );
+    escrowAccountPDASol = await deriveEscrowAddress(transferProgram, SEED_ESCROW_SOL, senderWallet.publicKey);
     console.log("Derived PDA in Test:", escrowAccountPDASol.toBase58());
-    console.log("Derived PDA Bump:", escrowAccountBumpSol);
 
-    const txSignature = await transferProgram.methods.initializeTransferSol(amount, expirationTime, hashOfSecret).accounts({sender: senderWallet.publicKey, escrowAccount: escrowAccountPDASol } as any).signers([senderWallet]).rpc();
+    const txSignature = await transferProgram.methods.initializeTransferSol(amount, expirationTime, hashOfSecret, escrowOptions()).accounts({sender: senderWallet.publicKey, escrowAccount: escrowAccountPDASol } as any).signers([senderWallet]).rpc();
 
     const escrower = await transferProgram.account.escrowSolAccount.fetch(escrowAccountPDASol);
     console.log(escrower)
@@ -248,12 +243,9 @@ describe("Instant Transfer", () => {
         `Hash must be exactly 32 bytes. Got ${hashOfSecret.length} bytes`
       );
     }
-    const [escrowAccountPDASpl, escrowAccountBumpSpl] = await anchor.web3.PublicKey.findProgramAddressSync(
-      [SEED_ESCROW_SPL, hashOfSecret],
-      programAddress
-    );
+    escrowAccountPDASpl = await deriveEscrowAddress(transferProgram, SEED_ESCROW_SPL, senderWallet.publicKey);
     console.log("Derived spl escrow account", escrowAccountPDASpl.toBase58())
-    const txSignature = await transferProgram.methods.initializeTransferSpl(amount, expirationTime, hashOfSecret).accounts({
+    const txSignature = await transferProgram.methods.initializeTransferSpl(amount, expirationTime, hashOfSecret, escrowOptions()).accounts({
         sender: senderWallet.publicKey,
         escrowAccount: escrowAccountPDASpl,
         tokenMint: tokenMint,
@@ -269,10 +261,6 @@ describe("Instant Transfer", () => {
   });
 
   it.skip("Redeem funds from Escrow wallet Native SOL", async() => {
-        const [escrowAccountPDASol, escrowAccountBumpSol] = await anchor.web3.PublicKey.findProgramAddressSync(
-            [SEED_ESCROW_SOL, hashOfSecret],
-            programAddress
-        );
         const escrowAccountInfoBefore = await provider.connection.getAccountInfo(escrowAccountPDASol);
         const senderAccountInfoBefore = await provider.connection.getAccountInfo(senderWallet.publicKey);
         //const receiverAccountInfoBefore = await provider.connection.getAccountInfo(receiverWallet.publicKey);
@@ -281,7 +269,7 @@ describe("Instant Transfer", () => {
         console.log('Sender Account Balance Before Redemption (Lamports):', senderAccountInfoBefore?.lamports);
         //console.log('Receiver Account Balance Before Redemption (Lamports):', receiverAccountInfoBefore?.lamports);
 
-        const txSignature = await transferProgram.methods.redeemFundsSol(secret).accounts({signer: centralFeePayerWallet.publicKey, sender: senderWallet.publicKey, recipient: receiverWallet.publicKey, escrowAccount: escrowAccountPDASol}).signers([centralFeePayerWallet]).rpc();
+        const txSignature = await transferProgram.methods.redeemFundsSol(secret).accounts({signer: centralFeePayerWallet.publicKey, sender: senderWallet.publicKey, recipient: receiverWallet.publicKey, rentReceiver: senderWallet.publicKey, escrowAccount: escrowAccountPDASol} as any).signers([centralFeePayerWallet]).rpc();
         
         // Fetch and print balances after redemption
         //const escrowAccountInfoAfter = await provider.connection.getAccountInfo(escrowAccountPDASol);
@@ -295,11 +283,6 @@ describe("Instant Transfer", () => {
     });
 
   it.skip("Redeem funds from Escrow Wallet SPL", async() => {
-    const [escrowAccountPDASpl, escrowAccountBumpSpl] = await anchor.web3.PublicKey.findProgramAddressSync(
-        [SEED_ESCROW_SPL, hashOfSecret],
-        programAddress
-    );
-
     const recipientTokenAccount = getAssociatedTokenAddressSync(
         tokenMint,
         receiverWallet.publicKey
@@ -337,6 +320,8 @@ describe("Instant Transfer", () => {
             escrowTokenAccount: escrowTokenAccount,
             recipientTokenAccount: recipientTokenAccount,
             tokenMint: tokenMint,
+            rentReceiver: senderWallet.publicKey,
+            senderRegistry: deriveSenderRegistry(programAddress, senderWallet.publicKey),
         }as any)
         .signers([centralFeePayerWallet])
         .rpc();
@@ -14,6 +14,7 @@ import * as fs from "fs";
 import * as path from "path";
 import * as crypto from "crypto";
 import { Key } from "readline";
+import { deriveEscrowAddress, deriveSenderRegistry, escrowOptions } from "./utils";
 
 const IDL = require("../target/idl/instant_send_program");
 
@@ -117,6 +118,9 @@ describe("Instant Transfer", () => {
   let SEED_ESCROW_SOL;
   let SEED_ESCROW_SPL;
   let mintPubkey: PublicKey;
+  // The claim link carries the escrow address, so later steps reuse it instead of re-deriving.
+  let escrowAccountPDASol: PublicKey;
+  let escrowAccountPDASpl: PublicKey;
 
   before("set secret, hashed_secret and seed-string buffer", async () => {
     secret = generateSecret();
@@ -144,19 +148,10 @@ describe("Instant Transfer", () => {
     }
 
 
-    const [escrowAccountPDASol, escrowAccountBumpSol] =
-      await anchor.web3.PublicKey.findProgramAddressSync(
-        [
-          SEED_ESCROW_SOL,
-          // senderWallet.publicKey.toBuffer(),
-          hashOfSecret, // This should be a Buffer
-        ],
-        programAddress
-      );
+    escrowAccountPDASol = await deriveEscrowAddress(transferProgram, SEED_ESCROW_SOL, senderWallet.publicKey);
     console.log("Derived PDA in Test:", escrowAccountPDASol.toBase58());
-    console.log("Derived PDA Bump:", escrowAccountBumpSol);
 
-    const txSignature = await transferProgram.methods.initializeTransferSol(amount, expirationTime, hashOfSecret).accounts({sender: senderWallet.publicKey, escrowAccount: escrowAccountPDASol } as any).signers([senderWallet]).rpc();
+    const txSignature = await transferProgram.methods.initializeTransferSol(amount, expirationTime, hashOfSecret, escrowOptions({ rentPolicy: { sender: {} } })).accounts({sender: senderWallet.publicKey, escrowAccount: escrowAccountPDASol } as any).signers([senderWallet]).rpc();
 
     const escrower = await transferProgram.account.escrowSolAccount.fetch(escrowAccountPDASol);
     console.log(escrower)
@@ -166,10 +161,6 @@ describe("Instant Transfer", () => {
 
   });
   it.skip("Redeem funds from Escrow wallet Native SOL", async() => {
-    const [escrowAccountPDASol, escrowAccountBumpSol] = await anchor.web3.PublicKey.findProgramAddressSync(
-        [SEED_ESCROW_SOL, hashOfSecret],
-        programAddress
-    );
     const escrowAccountInfoBefore = await provider.connection.getAccountInfo(escrowAccountPDASol);
     const senderAccountInfoBefore = await provider.connection.getAccountInfo(senderWallet.publicKey);
     //const receiverAccountInfoBefore = await provider.connection.getAccountInfo(receiverWallet.publicKey);
@@ -178,7 +169,7 @@ describe("Instant Transfer", () => {
     console.log('Sender Account Balance Before Redemption (Lamports):', senderAccountInfoBefore?.lamports);
     //console.log('Receiver Account Balance Before Redemption (Lamports):', receiverAccountInfoBefore?.lamports);
 
-    const txSignature = await transferProgram.methods.redeemFundsSol(secret).accounts({signer: centralFeePayerWallet.publicKey, sender: senderWallet.publicKey, recipient: receiverWallet.publicKey, escrowAccount: escrowAccountPDASol}).signers([centralFeePayerWallet]).rpc();
+    const txSignature = await transferProgram.methods.redeemFundsSol(secret).accounts({signer: centralFeePayerWallet.publicKey, sender: senderWallet.publicKey, recipient: receiverWallet.publicKey, rentReceiver: senderWallet.publicKey, escrowAccount: escrowAccountPDASol} as any).signers([centralFeePayerWallet]).rpc();
     
     // Fetch and print balances after redemption
     //const escrowAccountInfoAfter = await provider.connection.getAccountInfo(escrowAccountPDASol);
@@ -212,10 +203,7 @@ describe("Instant Transfer", () => {
       }
   
       // Derive the escrow account PDA
-      const [escrowAccountPDASpl, escrowAccountBump] = PublicKey.findProgramAddressSync(
-        [SEED_ESCROW_SPL, hashOfSecret],
-        programAddress
-      );
+      escrowAccountPDASpl = await deriveEscrowAddress(transferProgram, SEED_ESCROW_SPL, senderWallet.publicKey);
   
       // Get escrow token account (ATA)
       const escrowTokenAccount = getAssociatedTokenAddressSync(
@@ -224,7 +212,7 @@ describe("Instant Transfer", () => {
         true // Allow PDA owner
       );
       console.log("Derived spl escrow account", escrowAccountPDASpl.toBase58())
-      const txSignature = await transferProgram.methods.initializeTransferSpl(amount, expirationTime, hashOfSecret).accounts({
+      const txSignature = await transferProgram.methods.initializeTransferSpl(amount, expirationTime, hashOfSecret, escrowOptions({ rentPolicy: { recipient: {} } })).accounts({
           sender: senderWallet.publicKey,
           escrowAccount: escrowAccountPDASpl,
           tokenMint: mintPubkey,
@@ -237,15 +225,16 @@ describe("Instant Transfer", () => {
       // Fetch and log escrow account state
       const escrowAccount = await transferProgram.account.escrowAccount.fetch(escrowAccountPDASpl);
       console.log("Escrow Account State:", escrowAccount);
+
+      // The new escrow is listed in the sender's registry
+      const openEscrows = await transferProgram.methods
+        .listSenderEscrows(0, 31)
+        .accounts({ senderRegistry: deriveSenderRegistry(programAddress, senderWallet.publicKey) } as any)
+        .view();
+      console.log("Open escrows:", openEscrows.map((key: PublicKey) => key.toBase58()));
     });
 
     it("Redeem SPL Token Transfer", async () => {
-        // Get the escrow PDA and token accounts
-        const [escrowAccountPDASpl] = PublicKey.findProgramAddressSync(
-            [SEED_ESCROW_SPL, hashOfSecret],
-            programAddress
-        );
-
         // Get the recipient's token account
         const recipientTokenAccount = getAssociatedTokenAddressSync(
             mintPubkey,  // Use the same mint from the initialize test
@@ -269,6 +258,8 @@ describe("Instant Transfer", () => {
                 recipientTokenAccount: recipientTokenAccount,
                 tokenMint: mintPubkey,
                 sender: senderWallet.publicKey,
+                rentReceiver: receiverWallet.publicKey,
+                senderRegistry: deriveSenderRegistry(programAddress, senderWallet.publicKey),
                 tokenProgram: TOKEN_PROGRAM_ID,
                 associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
                 systemProgram: anchor.web3.SystemProgram.programId,
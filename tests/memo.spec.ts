@@ -0,0 +1,129 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { InstantSendProgram } from "../target/types/instant_send_program";
+import {
+  SEED_ESCROW_SOL,
+  deriveEscrowAddress,
+  deriveSenderRegistry,
+  escrowOptions,
+  fundedKeypair,
+  hashSecret,
+} from "./utils";
+
+const IDL = require("../target/idl/instant_send_program");
+
+const MEMO_PROGRAM_ID = new PublicKey("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+describe("Escrow memos", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = new Program<InstantSendProgram>(IDL, provider);
+
+  let sender: Keypair;
+  let recipient: Keypair;
+
+  beforeEach(async () => {
+    sender = await fundedKeypair(provider);
+    recipient = await fundedKeypair(provider, 1);
+  });
+
+  const deriveEscrowMemo = (escrow: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from("escrow_memo"), escrow.toBuffer()], program.programId)[0];
+
+  async function create(secret: string, memo: string | null): Promise<PublicKey> {
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SOL, sender.publicKey);
+    await program.methods
+      .initializeTransferSol(
+        new anchor.BN(1_000_000),
+        new anchor.BN(Math.floor(Date.now() / 1000) + 3600),
+        hashSecret(secret),
+        escrowOptions({ memo })
+      )
+      .accounts({
+        sender: sender.publicKey,
+        escrowAccount: escrow,
+        escrowMemo: memo === null ? null : deriveEscrowMemo(escrow),
+      } as any)
+      .signers([sender])
+      .rpc();
+    return escrow;
+  }
+
+  const redeem = (escrow: PublicKey, secret: string, withMemo: boolean) =>
+    program.methods
+      .redeemFundsSol(secret)
+      .accounts({
+        signer: recipient.publicKey,
+        recipient: recipient.publicKey,
+        escrowAccount: escrow,
+        sender: sender.publicKey,
+        rentReceiver: sender.publicKey,
+        senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+        escrowMemo: withMemo ? deriveEscrowMemo(escrow) : null,
+        memoProgram: withMemo ? MEMO_PROGRAM_ID : null,
+      } as any)
+      .signers([recipient])
+      .rpc();
+
+  async function expectError(promise: Promise<unknown>, code: string) {
+    try {
+      await promise;
+      expect.fail(`expected ${code}`);
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal(code);
+    }
+  }
+
+  it("stores the memo and emits it in the created event", async () => {
+    let listener: number;
+    const event = new Promise<any>((resolve) => {
+      listener = program.addEventListener("escrowCreated", resolve);
+    });
+
+    const escrow = await create("memo-created", "Happy birthday!");
+    const created = await event;
+    await program.removeEventListener(listener);
+
+    expect(created.escrow.equals(escrow)).to.be.true;
+    expect(created.memo).to.equal("Happy birthday!");
+    expect((await program.account.escrowSolAccount.fetch(escrow)).hasMemo).to.be.true;
+    const memo = await program.account.escrowMemo.fetch(deriveEscrowMemo(escrow));
+    expect(memo.memo).to.equal("Happy birthday!");
+    expect(memo.sender.equals(sender.publicKey)).to.be.true;
+  });
+
+  it("logs the memo with the memo program on redemption", async () => {
+    const escrow = await create("memo-redeem", "Invoice #1042");
+
+    await expectError(redeem(escrow, "memo-redeem", false), "MemoAccountsMissing");
+
+    const signature = await redeem(escrow, "memo-redeem", true);
+    const tx = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    expect(tx.meta.logMessages.join("\n")).to.include("Invoice #1042");
+  });
+
+  it("rejects memos over the length limit", async () => {
+    await expectError(create("memo-too-long", "x".repeat(201)), "MemoTooLong");
+  });
+
+  it("lets the sender close the memo once the escrow is gone", async () => {
+    const escrow = await create("memo-close", "Thanks for lunch");
+    const escrowMemo = deriveEscrowMemo(escrow);
+    const close = () =>
+      program.methods
+        .closeEscrowMemo()
+        .accounts({ sender: sender.publicKey, escrowMemo, escrowAccount: escrow } as any)
+        .signers([sender])
+        .rpc();
+
+    await expectError(close(), "EscrowStillOpen");
+    await redeem(escrow, "memo-close", true);
+    await close();
+    expect(await provider.connection.getAccountInfo(escrowMemo)).to.be.null;
+  });
+});
@@ -0,0 +1,107 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  createMint,
+  getOrCreateAssociatedTokenAccount,
+  getAssociatedTokenAddressSync,
+  mintTo,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import { InstantSendProgram } from "../target/types/instant_send_program";
+import {
+  SEED_ESCROW_SPL,
+  deriveEscrowAddress,
+  ensureConfig,
+  escrowOptions,
+  fundedKeypair,
+  hashSecret,
+} from "./utils";
+
+const IDL = require("../target/idl/instant_send_program");
+
+describe("Mint policy", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = new Program<InstantSendProgram>(IDL, provider);
+  const admin = (provider.wallet as anchor.Wallet).payer;
+
+  let sender: Keypair;
+  let listedMint: PublicKey;
+  let otherMint: PublicKey;
+
+  before(async () => {
+    sender = await fundedKeypair(provider);
+    await ensureConfig(program, provider);
+
+    listedMint = await createMint(provider.connection, sender, sender.publicKey, null, 0);
+    otherMint = await createMint(provider.connection, sender, sender.publicKey, null, 0);
+    for (const mint of [listedMint, otherMint]) {
+      const account = await getOrCreateAssociatedTokenAccount(provider.connection, sender, mint, sender.publicKey);
+      await mintTo(provider.connection, sender, mint, account.address, sender, 1_000);
+    }
+    await program.methods.addListedMint(listedMint).accounts({ admin: admin.publicKey } as any).rpc();
+  });
+
+  // Other specs create escrows with fresh mints, so leave the policy off.
+  after(async () => {
+    await setMode({ disabled: {} });
+    await program.methods.removeListedMint(listedMint).accounts({ admin: admin.publicKey } as any).rpc();
+  });
+
+  const setMode = (mode: object) =>
+    program.methods.setMintPolicy(mode as any).accounts({ admin: admin.publicKey } as any).rpc();
+
+  async function create(mint: PublicKey, secret: string) {
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SPL, sender.publicKey);
+    await program.methods
+      .initializeTransferSpl(
+        new anchor.BN(10),
+        new anchor.BN(Math.floor(Date.now() / 1000) + 3600),
+        hashSecret(secret),
+        escrowOptions()
+      )
+      .accounts({
+        sender: sender.publicKey,
+        escrowAccount: escrow,
+        escrowTokenAccount: getAssociatedTokenAddressSync(mint, escrow, true),
+        senderTokenAccount: getAssociatedTokenAddressSync(mint, sender.publicKey),
+        tokenMint: mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      } as any)
+      .signers([sender])
+      .rpc();
+  }
+
+  async function expectMintNotAllowed(promise: Promise<unknown>) {
+    try {
+      await promise;
+      expect.fail("expected MintNotAllowed");
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal("MintNotAllowed");
+    }
+  }
+
+  it("only accepts listed mints in allow-list mode", async () => {
+    await setMode({ allowList: {} });
+    await create(listedMint, "mint-policy-allowed");
+    await expectMintNotAllowed(create(otherMint, "mint-policy-not-allowed"));
+  });
+
+  it("rejects listed mints in deny-list mode", async () => {
+    await setMode({ denyList: {} });
+    await create(otherMint, "mint-policy-not-denied");
+    await expectMintNotAllowed(create(listedMint, "mint-policy-denied"));
+  });
+
+  it("emits an event when the policy changes", async () => {
+    let listener: number;
+    const event = new Promise<any>((resolve) => {
+      listener = program.addEventListener("mintPolicyChanged", resolve);
+    });
+    await setMode({ allowList: {} });
+    expect(await event).to.have.nested.property("mode.allowList");
+    await program.removeEventListener(listener);
+  });
+});
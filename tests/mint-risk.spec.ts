@@ -0,0 +1,147 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram, Transaction, sendAndConfirmTransaction } from "@solana/web3.js";
+import {
+  ExtensionType,
+  TOKEN_2022_PROGRAM_ID,
+  createInitializeMintInstruction,
+  createInitializePermanentDelegateInstruction,
+  getAssociatedTokenAddressSync,
+  getMintLen,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import { InstantSendProgram } from "../target/types/instant_send_program";
+import {
+  SEED_ESCROW_SPL,
+  deriveEscrowAddress,
+  ensureConfig,
+  escrowOptions,
+  fundedKeypair,
+  hashSecret,
+} from "./utils";
+
+const IDL = require("../target/idl/instant_send_program");
+
+const MINT_RISK_FREEZE_AUTHORITY = 1 << 0;
+const MINT_RISK_PERMANENT_DELEGATE = 1 << 1;
+
+describe("Mint risk screening", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = new Program<InstantSendProgram>(IDL, provider);
+  const admin = (provider.wallet as anchor.Wallet).payer;
+
+  let sender: Keypair;
+  let mint: PublicKey;
+
+  // Token-2022 mint with a permanent delegate and a freeze authority.
+  async function createRiskyMint(): Promise<PublicKey> {
+    const mintKeypair = Keypair.generate();
+    const space = getMintLen([ExtensionType.PermanentDelegate]);
+    const lamports = await provider.connection.getMinimumBalanceForRentExemption(space);
+    const tx = new Transaction().add(
+      SystemProgram.createAccount({
+        fromPubkey: sender.publicKey,
+        newAccountPubkey: mintKeypair.publicKey,
+        space,
+        lamports,
+        programId: TOKEN_2022_PROGRAM_ID,
+      }),
+      createInitializePermanentDelegateInstruction(
+        mintKeypair.publicKey,
+        sender.publicKey,
+        TOKEN_2022_PROGRAM_ID
+      ),
+      createInitializeMintInstruction(
+        mintKeypair.publicKey,
+        0,
+        sender.publicKey,
+        sender.publicKey,
+        TOKEN_2022_PROGRAM_ID
+      )
+    );
+    await sendAndConfirmTransaction(provider.connection, tx, [sender, mintKeypair]);
+
+    const account = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      sender,
+      mintKeypair.publicKey,
+      sender.publicKey,
+      false,
+      undefined,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await mintTo(provider.connection, sender, mintKeypair.publicKey, account.address, sender, 1_000, [], undefined, TOKEN_2022_PROGRAM_ID);
+    return mintKeypair.publicKey;
+  }
+
+  before(async () => {
+    sender = await fundedKeypair(provider);
+    await ensureConfig(program, provider);
+    mint = await createRiskyMint();
+  });
+
+  after(async () => {
+    await setMasks(0, 0);
+  });
+
+  const setMasks = (reject: number, warn: number) =>
+    program.methods.setMintRiskPolicy(reject, warn).accounts({ admin: admin.publicKey } as any).rpc();
+
+  async function create(secret: string): Promise<PublicKey> {
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SPL, sender.publicKey);
+    await program.methods
+      .initializeTransferSpl(
+        new anchor.BN(10),
+        new anchor.BN(Math.floor(Date.now() / 1000) + 3600),
+        hashSecret(secret),
+        escrowOptions()
+      )
+      .accounts({
+        sender: sender.publicKey,
+        escrowAccount: escrow,
+        escrowTokenAccount: getAssociatedTokenAddressSync(mint, escrow, true, TOKEN_2022_PROGRAM_ID),
+        senderTokenAccount: getAssociatedTokenAddressSync(mint, sender.publicKey, false, TOKEN_2022_PROGRAM_ID),
+        tokenMint: mint,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      } as any)
+      .signers([sender])
+      .rpc();
+    return escrow;
+  }
+
+  it("stores the risk flags when the policy allows the mint", async () => {
+    await setMasks(0, 0);
+    const escrow = await create("mint-risk-allowed");
+
+    const account = await program.account.escrowAccount.fetch(escrow);
+    expect(account.riskFlags).to.equal(MINT_RISK_FREEZE_AUTHORITY | MINT_RISK_PERMANENT_DELEGATE);
+  });
+
+  it("emits an event for flags in the warn mask", async () => {
+    await setMasks(0, MINT_RISK_PERMANENT_DELEGATE);
+    let listener: number;
+    const event = new Promise<any>((resolve) => {
+      listener = program.addEventListener("mintRiskDetected", resolve);
+    });
+
+    const escrow = await create("mint-risk-warned");
+    const detected = await event;
+    expect(detected.escrow.equals(escrow)).to.be.true;
+    expect(detected.flags).to.equal(MINT_RISK_PERMANENT_DELEGATE);
+    await program.removeEventListener(listener);
+  });
+
+  it("rejects mints with flags in the reject mask", async () => {
+    await setMasks(MINT_RISK_PERMANENT_DELEGATE, 0);
+    try {
+      await create("mint-risk-rejected");
+      expect.fail("create should have failed");
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal("MintRiskRejected");
+    }
+  });
+});
@@ -0,0 +1,135 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { InstantSendProgram } from "../target/types/instant_send_program";
+import {
+  SEED_ESCROW_SOL,
+  deriveEscrowAddress,
+  deriveSenderRegistry,
+  ensureConfig,
+  escrowOptions,
+  fundedKeypair,
+  hashSecret,
+} from "./utils";
+
+const IDL = require("../target/idl/instant_send_program");
+
+const NONE_PAUSED = { create: false, redeem: false, refund: false, admin: false };
+
+describe("Pause flags", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = new Program<InstantSendProgram>(IDL, provider);
+  const admin = (provider.wallet as anchor.Wallet).payer;
+  const guardian = Keypair.generate();
+
+  let config: PublicKey;
+  let sender: Keypair;
+  let recipient: Keypair;
+
+  before(async () => {
+    sender = await fundedKeypair(provider);
+    recipient = await fundedKeypair(provider, 1);
+    config = await ensureConfig(program, provider);
+    await program.methods
+      .setGuardian(guardian.publicKey)
+      .accounts({ admin: admin.publicKey } as any)
+      .rpc();
+  });
+
+  // Other specs share the config, so leave everything running.
+  afterEach(async () => {
+    await setPause(NONE_PAUSED, admin);
+  });
+
+  const setPause = (paused: object, authority: Keypair) =>
+    program.methods
+      .setPause({ ...NONE_PAUSED, ...paused })
+      .accounts({ authority: authority.publicKey } as any)
+      .signers([authority])
+      .rpc();
+
+  const create = (secret: string, expiresIn = 3600) =>
+    program.methods
+      .initializeTransferSol(
+        new anchor.BN(1_000_000),
+        new anchor.BN(Math.floor(Date.now() / 1000) + expiresIn),
+        hashSecret(secret),
+        escrowOptions()
+      )
+      .accounts({ sender: sender.publicKey } as any)
+      .signers([sender])
+      .rpc();
+
+  async function expectError(promise: Promise<unknown>, code: string) {
+    try {
+      await promise;
+      expect.fail(`expected ${code}`);
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal(code);
+    }
+  }
+
+  it("lets the guardian pause creates", async () => {
+    await setPause({ create: true }, guardian);
+    expect((await program.account.config.fetch(config)).paused.create).to.be.true;
+
+    await expectError(create("pause-create"), "ProgramPaused");
+  });
+
+  it("pauses redemption", async () => {
+    const secret = "pause-redeem";
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SOL, sender.publicKey);
+    await create(secret);
+    await setPause({ redeem: true }, admin);
+
+    await expectError(
+      program.methods
+        .redeemFundsSol(secret)
+        .accounts({
+          signer: recipient.publicKey,
+          recipient: recipient.publicKey,
+          escrowAccount: escrow,
+          sender: sender.publicKey,
+          rentReceiver: sender.publicKey,
+          senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+        } as any)
+        .signers([recipient])
+        .rpc(),
+      "ProgramPaused"
+    );
+  });
+
+  it("still refunds expired escrows when everything is paused", async () => {
+    const secret = "pause-refund";
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SOL, sender.publicKey);
+    await create(secret, 1);
+    await setPause({ create: true, redeem: true, refund: true, admin: true }, guardian);
+    await new Promise((resolve) => setTimeout(resolve, 3000));
+
+    await program.methods
+      .refundFundsSol(secret)
+      .accounts({
+        signer: sender.publicKey,
+        escrowAccount: escrow,
+        sender: sender.publicKey,
+        senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+      } as any)
+      .signers([sender])
+      .rpc();
+    expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+  });
+
+  it("pauses admin instructions but not set_pause", async () => {
+    await setPause({ admin: true }, admin);
+    await expectError(
+      program.methods.setGuardian(guardian.publicKey).accounts({ admin: admin.publicKey } as any).rpc(),
+      "ProgramPaused"
+    );
+  });
+
+  it("rejects other signers", async () => {
+    await expectError(setPause({ create: true }, recipient), "Unauthorized");
+  });
+});
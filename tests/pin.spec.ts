@@ -0,0 +1,124 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { InstantSendProgram } from "../target/types/instant_send_program";
+import {
+  SEED_ESCROW_SOL,
+  deriveEscrowAddress,
+  deriveSenderRegistry,
+  escrowOptions,
+  fundedKeypair,
+  generatePinSalt,
+  generateSecret,
+  hashPin,
+  hashSecret,
+} from "./utils";
+
+const IDL = require("../target/idl/instant_send_program");
+
+describe("PIN second factor", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = new Program<InstantSendProgram>(IDL, provider);
+
+  let sender: Keypair;
+  let recipient: Keypair;
+
+  beforeEach(async () => {
+    sender = await fundedKeypair(provider);
+    recipient = await fundedKeypair(provider, 1);
+  });
+
+  async function create(secret: Buffer, pinHash: number[]): Promise<PublicKey> {
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SOL, sender.publicKey);
+    await program.methods
+      .initializeTransferSol(
+        new anchor.BN(1_000_000),
+        new anchor.BN(Math.floor(Date.now() / 1000) + 3600),
+        hashSecret(secret),
+        escrowOptions({ pinHash })
+      )
+      .accounts({ sender: sender.publicKey, escrowAccount: escrow } as any)
+      .signers([sender])
+      .rpc();
+    return escrow;
+  }
+
+  const redeem = (escrow: PublicKey, secret: Buffer, pin: { salt: number[]; pin: string } | null) =>
+    program.methods
+      .redeemFundsSolV2(secret, pin)
+      .accounts({
+        signer: recipient.publicKey,
+        recipient: recipient.publicKey,
+        escrowAccount: escrow,
+        sender: sender.publicKey,
+        rentReceiver: sender.publicKey,
+        senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+      } as any)
+      .signers([recipient])
+      .rpc();
+
+  async function expectError(promise: Promise<unknown>, code: string) {
+    try {
+      await promise;
+      expect.fail(`expected ${code}`);
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal(code);
+    }
+  }
+
+  it("requires the PIN committed with the secret", async () => {
+    const secret = generateSecret();
+    const salt = generatePinSalt();
+    const escrow = await create(secret, hashPin(secret, salt, "4821"));
+
+    await expectError(redeem(escrow, secret, null), "InvalidPin");
+    await expectError(redeem(escrow, secret, { salt: Array.from(salt), pin: "4822" }), "InvalidPin");
+    await redeem(escrow, secret, { salt: Array.from(salt), pin: "4821" });
+
+    expect(await provider.connection.getAccountInfo(escrow, "confirmed")).to.be.null;
+  });
+
+  it("rejects PIN-protected escrows on the legacy instruction, which takes no PIN", async () => {
+    const secret = "a text secret behind a PIN";
+    const salt = generatePinSalt();
+    const escrow = await create(Buffer.from(secret, "utf8"), hashPin(secret, salt, "4821"));
+
+    await expectError(
+      program.methods
+        .redeemFundsSol(secret)
+        .accounts({
+          signer: recipient.publicKey,
+          recipient: recipient.publicKey,
+          escrowAccount: escrow,
+          sender: sender.publicKey,
+          rentReceiver: sender.publicKey,
+          senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+        } as any)
+        .signers([recipient])
+        .rpc(),
+      "InvalidPin"
+    );
+    await redeem(escrow, Buffer.from(secret, "utf8"), { salt: Array.from(salt), pin: "4821" });
+  });
+
+  it("rejects commitments that are not bound to the secret", async () => {
+    const secret = generateSecret();
+    const salt = generatePinSalt();
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SOL, sender.publicKey);
+    // The earlier `sha256(escrow || pin)` form, which anyone could brute-force from chain state.
+    await create(secret, hashPin(escrow.toBuffer(), salt, "4821"));
+
+    await expectError(redeem(escrow, secret, { salt: Array.from(salt), pin: "4821" }), "InvalidPin");
+  });
+
+  it("needs the salt delivered with the PIN, not only the link", async () => {
+    const secret = generateSecret();
+    const salt = generatePinSalt();
+    const escrow = await create(secret, hashPin(secret, salt, "4821"));
+
+    await expectError(redeem(escrow, secret, { salt: new Array(16).fill(0), pin: "4821" }), "InvalidPin");
+    await redeem(escrow, secret, { salt: Array.from(salt), pin: "4821" });
+  });
+});
@@ -0,0 +1,254 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  burn,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  getOrCreateAssociatedTokenAccount,
+  transfer,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import { InstantSendProgram } from "../target/types/instant_send_program";
+import {
+  SEED_ESCROW_SOL,
+  balance,
+  deriveEscrowAddress,
+  deriveSenderRegistry,
+  escrowOptions,
+  fundedKeypair,
+  generateSecret,
+  hashSecret,
+} from "./utils";
+
+const IDL = require("../target/idl/instant_send_program");
+
+const AMOUNT = 1_000_000;
+
+describe("Refund receipts", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = new Program<InstantSendProgram>(IDL, provider);
+
+  let sender: Keypair;
+  let holder: Keypair;
+
+  beforeEach(async () => {
+    sender = await fundedKeypair(provider);
+    holder = await fundedKeypair(provider);
+  });
+
+  const expiresIn = (seconds: number) => new anchor.BN(Math.floor(Date.now() / 1000) + seconds);
+  const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+  const deriveReceiptMint = (escrow: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from("receipt_mint"), escrow.toBuffer()], program.programId)[0];
+  const receiptAccountOf = (escrow: PublicKey, owner: PublicKey) =>
+    getAssociatedTokenAddressSync(deriveReceiptMint(escrow), owner, false, TOKEN_2022_PROGRAM_ID);
+
+  async function create(secret: Buffer, seconds: number): Promise<PublicKey> {
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SOL, sender.publicKey);
+    await program.methods
+      .initializeTransferSol(new anchor.BN(AMOUNT), expiresIn(seconds), hashSecret(secret), escrowOptions({ receipt: true }))
+      .accounts({
+        sender: sender.publicKey,
+        escrowAccount: escrow,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        receiptMint: deriveReceiptMint(escrow),
+        receiptAccount: receiptAccountOf(escrow, sender.publicKey),
+        receiptTokenProgram: TOKEN_2022_PROGRAM_ID,
+      } as any)
+      .signers([sender])
+      .rpc();
+    return escrow;
+  }
+
+  // Moves the receipt from the sender to `holder`.
+  async function handOver(escrow: PublicKey): Promise<PublicKey> {
+    const to = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      holder,
+      deriveReceiptMint(escrow),
+      holder.publicKey,
+      false,
+      undefined,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await transfer(
+      provider.connection,
+      sender,
+      receiptAccountOf(escrow, sender.publicKey),
+      to.address,
+      sender,
+      1,
+      [],
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    return to.address;
+  }
+
+  const refundWithReceipt = (escrow: PublicKey, signer: Keypair, receiptAccount: PublicKey | null) =>
+    program.methods
+      .refundWithReceiptSol()
+      .accounts({
+        holder: signer.publicKey,
+        escrowAccount: escrow,
+        receiptMint: deriveReceiptMint(escrow),
+        receiptAccount,
+        senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+        receiptTokenProgram: TOKEN_2022_PROGRAM_ID,
+      } as any)
+      .signers([signer])
+      .rpc();
+
+  async function expectError(promise: Promise<unknown>, code: string) {
+    try {
+      await promise;
+      expect.fail(`expected ${code}`);
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal(code);
+    }
+  }
+
+  it("mints a single receipt to the sender", async () => {
+    const escrow = await create(generateSecret(), 3600);
+
+    expect((await program.account.escrowSolAccount.fetch(escrow)).hasReceipt).to.be.true;
+    const account = await getAccount(
+      provider.connection,
+      receiptAccountOf(escrow, sender.publicKey),
+      "confirmed",
+      TOKEN_2022_PROGRAM_ID
+    );
+    expect(Number(account.amount)).to.equal(1);
+  });
+
+  it("refunds to whoever holds the receipt and closes the receipt mint", async () => {
+    const secret = generateSecret();
+    const escrow = await create(secret, 1);
+    const receiptAccount = await handOver(escrow);
+    await sleep(2000);
+
+    // The original sender lost the right to the refund along with the receipt.
+    await expectError(
+      program.methods
+        .refundFundsSolV2(secret)
+        .accounts({
+          signer: sender.publicKey,
+          escrowAccount: escrow,
+          sender: sender.publicKey,
+          senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+        } as any)
+        .signers([sender])
+        .rpc(),
+      "RefundRequiresReceipt"
+    );
+    await expectError(
+      refundWithReceipt(escrow, sender, receiptAccountOf(escrow, sender.publicKey)),
+      "ReceiptNotHeld"
+    );
+
+    const before = await balance(provider, holder.publicKey);
+    await refundWithReceipt(escrow, holder, receiptAccount);
+
+    expect(await balance(provider, holder.publicKey)).to.be.greaterThan(before + AMOUNT - 10_000);
+    expect(await provider.connection.getAccountInfo(escrow, "confirmed")).to.be.null;
+    expect(await provider.connection.getAccountInfo(deriveReceiptMint(escrow), "confirmed")).to.be.null;
+  });
+
+  it("burns the receipt when the escrow is redeemed", async () => {
+    const secret = generateSecret();
+    const escrow = await create(secret, 3600);
+    const receiptAccount = await handOver(escrow);
+    const recipient = await fundedKeypair(provider, 1);
+    const redeem = (withReceipt: boolean) =>
+      program.methods
+        .redeemFundsSolV2(secret, null)
+        .accounts({
+          signer: recipient.publicKey,
+          recipient: recipient.publicKey,
+          escrowAccount: escrow,
+          sender: sender.publicKey,
+          rentReceiver: sender.publicKey,
+          senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+          receiptMint: withReceipt ? deriveReceiptMint(escrow) : null,
+          receiptAccount: withReceipt ? receiptAccount : null,
+          receiptTokenProgram: withReceipt ? TOKEN_2022_PROGRAM_ID : null,
+        } as any)
+        .signers([recipient])
+        .rpc();
+
+    await expectError(redeem(false), "ReceiptAccountsMissing");
+    // The holder's signature isn't needed: the escrow is the receipt's permanent delegate.
+    await redeem(true);
+
+    expect(await provider.connection.getAccountInfo(deriveReceiptMint(escrow), "confirmed")).to.be.null;
+    const account = await getAccount(provider.connection, receiptAccount, "confirmed", TOKEN_2022_PROGRAM_ID);
+    expect(Number(account.amount)).to.equal(0);
+  });
+
+  it("still redeems after the holder burns the receipt", async () => {
+    const secret = generateSecret();
+    const escrow = await create(secret, 3600);
+    const receiptAccount = await handOver(escrow);
+    await burn(provider.connection, holder, receiptAccount, deriveReceiptMint(escrow), holder, 1, [], undefined, TOKEN_2022_PROGRAM_ID);
+    const recipient = await fundedKeypair(provider, 1);
+
+    // Nobody holds the receipt, so the redeemer has no account to pass; the mint is just closed.
+    await program.methods
+      .redeemFundsSolV2(secret, null)
+      .accounts({
+        signer: recipient.publicKey,
+        recipient: recipient.publicKey,
+        escrowAccount: escrow,
+        sender: sender.publicKey,
+        rentReceiver: sender.publicKey,
+        senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+        receiptMint: deriveReceiptMint(escrow),
+        receiptAccount: null,
+        receiptTokenProgram: TOKEN_2022_PROGRAM_ID,
+      } as any)
+      .signers([recipient])
+      .rpc();
+
+    expect(await provider.connection.getAccountInfo(escrow, "confirmed")).to.be.null;
+    expect(await provider.connection.getAccountInfo(deriveReceiptMint(escrow), "confirmed")).to.be.null;
+  });
+
+  it("hands the refund back to the sender once the receipt is burned", async () => {
+    const escrow = await create(generateSecret(), 1);
+    const receiptAccount = await handOver(escrow);
+    await burn(provider.connection, holder, receiptAccount, deriveReceiptMint(escrow), holder, 1, [], undefined, TOKEN_2022_PROGRAM_ID);
+    await sleep(2000);
+
+    await expectError(refundWithReceipt(escrow, holder, null), "Unauthorized");
+    const before = await balance(provider, sender.publicKey);
+    await refundWithReceipt(escrow, sender, null);
+
+    expect(await balance(provider, sender.publicKey)).to.be.greaterThan(before + AMOUNT - 10_000);
+    expect(await provider.connection.getAccountInfo(escrow, "confirmed")).to.be.null;
+    expect(await provider.connection.getAccountInfo(deriveReceiptMint(escrow), "confirmed")).to.be.null;
+  });
+
+  it("rejects receipt accounts without the receipt option", async () => {
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SOL, sender.publicKey);
+    await expectError(
+      program.methods
+        .initializeTransferSol(new anchor.BN(AMOUNT), expiresIn(3600), hashSecret(generateSecret()), escrowOptions())
+        .accounts({
+          sender: sender.publicKey,
+          escrowAccount: escrow,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          receiptMint: deriveReceiptMint(escrow),
+          receiptAccount: receiptAccountOf(escrow, sender.publicKey),
+          receiptTokenProgram: TOKEN_2022_PROGRAM_ID,
+        } as any)
+        .signers([sender])
+        .rpc(),
+      "InvalidReceiptAccounts"
+    );
+  });
+});
@@ -0,0 +1,186 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  createMint,
+  getOrCreateAssociatedTokenAccount,
+  getAssociatedTokenAddressSync,
+  getAccount,
+  mintTo,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import { InstantSendProgram } from "../target/types/instant_send_program";
+import { MockSwap } from "../target/types/mock_swap";
+import {
+  SEED_ESCROW_SPL,
+  deriveEscrowAddress,
+  deriveSenderRegistry,
+  ensureConfig,
+  escrowOptions,
+  fundedKeypair,
+  hashSecret,
+} from "./utils";
+
+const IDL = require("../target/idl/instant_send_program");
+const MOCK_SWAP_IDL = require("../target/idl/mock_swap");
+
+describe("Redeem and invoke", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = new Program<InstantSendProgram>(IDL, provider);
+  const mockSwap = new Program<MockSwap>(MOCK_SWAP_IDL, provider);
+  const admin = (provider.wallet as anchor.Wallet).payer;
+
+  const [whitelistedPDA] = PublicKey.findProgramAddressSync(
+    [Buffer.from("whitelisted_program"), mockSwap.programId.toBuffer()],
+    program.programId
+  );
+  const [mintAuthority] = PublicKey.findProgramAddressSync(
+    [Buffer.from("mint_authority")],
+    mockSwap.programId
+  );
+
+  let sender: Keypair;
+  let recipient: Keypair;
+  let giftMint: PublicKey;
+  let outputMint: PublicKey;
+  let senderTokenAccount: PublicKey;
+  let vault: PublicKey;
+  let recipientOutput: PublicKey;
+
+  before(async () => {
+    sender = await fundedKeypair(provider);
+    recipient = await fundedKeypair(provider, 1);
+
+    await ensureConfig(program, provider);
+    if (!(await provider.connection.getAccountInfo(whitelistedPDA))) {
+      await program.methods
+        .addWhitelistedProgram()
+        .accounts({ admin: admin.publicKey, targetProgram: mockSwap.programId } as any)
+        .rpc();
+    }
+
+    giftMint = await createMint(provider.connection, sender, sender.publicKey, null, 0);
+    senderTokenAccount = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, sender, giftMint, sender.publicKey)
+    ).address;
+    await mintTo(provider.connection, sender, giftMint, senderTokenAccount, sender, 1_000);
+    vault = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, admin, giftMint, admin.publicKey)
+    ).address;
+
+    outputMint = await createMint(provider.connection, admin, mintAuthority, null, 0);
+    recipientOutput = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, admin, outputMint, recipient.publicKey)
+    ).address;
+  });
+
+  async function createEscrow(secret: string, amount: number): Promise<PublicKey> {
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SPL, sender.publicKey);
+    await program.methods
+      .initializeTransferSpl(
+        new anchor.BN(amount),
+        new anchor.BN(Math.floor(Date.now() / 1000) + 3600),
+        hashSecret(secret),
+        escrowOptions()
+      )
+      .accounts({
+        sender: sender.publicKey,
+        escrowAccount: escrow,
+        escrowTokenAccount: getAssociatedTokenAddressSync(giftMint, escrow, true),
+        senderTokenAccount,
+        tokenMint: giftMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      } as any)
+      .signers([sender])
+      .rpc();
+    return escrow;
+  }
+
+  async function redeemAndSwap(
+    secret: string,
+    escrow: PublicKey,
+    amountOut: number,
+    minOutput: number,
+    { amountIn = 50, outputTokenAccount = recipientOutput } = {}
+  ) {
+    const recipientGift = getAssociatedTokenAddressSync(giftMint, recipient.publicKey);
+    const swapIx = await mockSwap.methods
+      .swap(new anchor.BN(amountIn), new anchor.BN(amountOut))
+      .accounts({
+        user: recipient.publicKey,
+        userSource: recipientGift,
+        vault,
+        sourceMint: giftMint,
+        outputMint,
+        userDestination: recipientOutput,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      } as any)
+      .instruction();
+
+    return program.methods
+      .redeemAndInvoke(secret, null, swapIx.data, new anchor.BN(minOutput), 0)
+      .accounts({
+        redeem: {
+          signer: recipient.publicKey,
+          recipient: recipient.publicKey,
+          escrowAccount: escrow,
+          escrowTokenAccount: getAssociatedTokenAddressSync(giftMint, escrow, true),
+          recipientTokenAccount: recipientGift,
+          tokenMint: giftMint,
+          sender: sender.publicKey,
+          rentReceiver: sender.publicKey,
+          senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        whitelistedProgram: whitelistedPDA,
+        invokeProgram: mockSwap.programId,
+        outputTokenAccount,
+      } as any)
+      .remainingAccounts(swapIx.keys)
+      .signers([recipient])
+      .rpc();
+  }
+
+  it("redeems and swaps through the whitelisted program", async () => {
+    const secret = "redeem-and-invoke-ok";
+    const escrow = await createEscrow(secret, 50);
+    const before = (await getAccount(provider.connection, recipientOutput)).amount;
+
+    await redeemAndSwap(secret, escrow, 75, 70);
+
+    const after = (await getAccount(provider.connection, recipientOutput)).amount;
+    expect(Number(after - before)).to.equal(75);
+    expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+  });
+
+  it("fails when the output is below the minimum", async () => {
+    const secret = "redeem-and-invoke-slippage";
+    const escrow = await createEscrow(secret, 50);
+
+    try {
+      await redeemAndSwap(secret, escrow, 10, 70);
+      expect.fail("redeem_and_invoke should have failed");
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal("InsufficientOutput");
+    }
+    // The whole redemption rolled back, so the escrow is still open.
+    expect(await provider.connection.getAccountInfo(escrow)).to.not.be.null;
+  });
+
+  it("doesn't count the redeemed tokens as output", async () => {
+    const secret = "redeem-and-invoke-self-output";
+    const escrow = await createEscrow(secret, 50);
+    const recipientGift = getAssociatedTokenAddressSync(giftMint, recipient.publicKey);
+
+    // The swap takes nothing, so only the redeem itself would credit the gift account.
+    try {
+      await redeemAndSwap(secret, escrow, 0, 50, { amountIn: 0, outputTokenAccount: recipientGift });
+      expect.fail("redeem_and_invoke should have failed");
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal("InsufficientOutput");
+    }
+    expect(await provider.connection.getAccountInfo(escrow)).to.not.be.null;
+  });
+});
@@ -0,0 +1,188 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ACCOUNT_SIZE,
+  createMint,
+  getOrCreateAssociatedTokenAccount,
+  getAssociatedTokenAddressSync,
+  mintTo,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import { InstantSendProgram } from "../target/types/instant_send_program";
+import {
+  SEED_ESCROW_SOL,
+  SEED_ESCROW_SPL,
+  balance,
+  deriveEscrowAddress,
+  deriveSenderRegistry,
+  escrowOptions,
+  fundedKeypair,
+  hashSecret,
+} from "./utils";
+
+const IDL = require("../target/idl/instant_send_program");
+
+// Fees are paid by the provider wallet, so the balances below move by rent and amount only.
+describe("Rent policy", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = new Program<InstantSendProgram>(IDL, provider);
+
+  let sender: Keypair;
+  let recipient: Keypair;
+  let redeemer: Keypair;
+
+  before(async () => {
+    sender = await fundedKeypair(provider);
+    recipient = await fundedKeypair(provider, 1);
+    redeemer = await fundedKeypair(provider, 1);
+  });
+
+  const policies: { name: string; policy: object; receiver: () => PublicKey }[] = [
+    { name: "sender", policy: { sender: {} }, receiver: () => sender.publicKey },
+    { name: "recipient", policy: { recipient: {} }, receiver: () => recipient.publicKey },
+    { name: "redeemer", policy: { redeemer: {} }, receiver: () => redeemer.publicKey },
+  ];
+
+  for (const { name, policy, receiver } of policies) {
+    it(`returns SOL escrow rent to the ${name}`, async () => {
+      const secret = `rent-policy-sol-${name}`;
+      const amount = new anchor.BN(100_000_000);
+      const expirationTime = new anchor.BN(Math.floor(Date.now() / 1000) + 3600);
+      const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SOL, sender.publicKey);
+
+      await program.methods
+        .initializeTransferSol(amount, expirationTime, hashSecret(secret), escrowOptions({ rentPolicy: policy }))
+        .accounts({ sender: sender.publicKey } as any)
+        .signers([sender])
+        .rpc();
+
+      const escrowRent = (await balance(provider, escrow)) - amount.toNumber();
+      const before = {
+        sender: await balance(provider, sender.publicKey),
+        recipient: await balance(provider, recipient.publicKey),
+        redeemer: await balance(provider, redeemer.publicKey),
+      };
+
+      await program.methods
+        .redeemFundsSol(secret)
+        .accounts({
+          signer: redeemer.publicKey,
+          recipient: recipient.publicKey,
+          escrowAccount: escrow,
+          sender: sender.publicKey,
+          rentReceiver: receiver(),
+          senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+        } as any)
+        .signers([redeemer])
+        .rpc();
+
+      const rentTo = (key: PublicKey) => (receiver().equals(key) ? escrowRent : 0);
+      expect(await balance(provider, sender.publicKey)).to.equal(
+        before.sender + rentTo(sender.publicKey)
+      );
+      expect(await balance(provider, recipient.publicKey)).to.equal(
+        before.recipient + amount.toNumber() + rentTo(recipient.publicKey)
+      );
+      expect(await balance(provider, redeemer.publicKey)).to.equal(
+        before.redeemer + rentTo(redeemer.publicKey)
+      );
+      expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+    });
+  }
+
+  it("returns SPL escrow and vault rent to the recipient", async () => {
+    const secret = "rent-policy-spl-recipient";
+    const mint = await createMint(provider.connection, sender, sender.publicKey, null, 0);
+    const senderTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      sender,
+      mint,
+      sender.publicKey
+    );
+    await mintTo(provider.connection, sender, mint, senderTokenAccount.address, sender, 100);
+
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SPL, sender.publicKey);
+    const escrowTokenAccount = getAssociatedTokenAddressSync(mint, escrow, true);
+    const recipientTokenAccount = getAssociatedTokenAddressSync(mint, recipient.publicKey);
+
+    await program.methods
+      .initializeTransferSpl(
+        new anchor.BN(50),
+        new anchor.BN(Math.floor(Date.now() / 1000) + 3600),
+        hashSecret(secret),
+        escrowOptions({ rentPolicy: { recipient: {} } })
+      )
+      .accounts({
+        sender: sender.publicKey,
+        escrowAccount: escrow,
+        escrowTokenAccount,
+        senderTokenAccount: senderTokenAccount.address,
+        tokenMint: mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      } as any)
+      .signers([sender])
+      .rpc();
+
+    const reclaimed = (await balance(provider, escrow)) + (await balance(provider, escrowTokenAccount));
+    const tokenAccountRent = await provider.connection.getMinimumBalanceForRentExemption(ACCOUNT_SIZE);
+    const recipientBefore = await balance(provider, recipient.publicKey);
+    const redeemerBefore = await balance(provider, redeemer.publicKey);
+
+    await program.methods
+      .redeemFundsSpl(secret)
+      .accounts({
+        signer: redeemer.publicKey,
+        recipient: recipient.publicKey,
+        escrowAccount: escrow,
+        escrowTokenAccount,
+        recipientTokenAccount,
+        tokenMint: mint,
+        sender: sender.publicKey,
+        rentReceiver: recipient.publicKey,
+        senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+        tokenProgram: TOKEN_PROGRAM_ID,
+      } as any)
+      .signers([redeemer])
+      .rpc();
+
+    expect(await balance(provider, recipient.publicKey)).to.equal(recipientBefore + reclaimed);
+    // The redeemer only pays for the recipient's new token account.
+    expect(await balance(provider, redeemer.publicKey)).to.equal(redeemerBefore - tokenAccountRent);
+  });
+
+  it("rejects a rent receiver that does not match the policy", async () => {
+    const secret = "rent-policy-mismatch";
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SOL, sender.publicKey);
+    await program.methods
+      .initializeTransferSol(
+        new anchor.BN(1_000_000),
+        new anchor.BN(Math.floor(Date.now() / 1000) + 3600),
+        hashSecret(secret),
+        escrowOptions({ rentPolicy: { sender: {} } })
+      )
+      .accounts({ sender: sender.publicKey } as any)
+      .signers([sender])
+      .rpc();
+
+    try {
+      await program.methods
+        .redeemFundsSol(secret)
+        .accounts({
+          signer: redeemer.publicKey,
+          recipient: recipient.publicKey,
+          escrowAccount: escrow,
+          sender: sender.publicKey,
+          rentReceiver: redeemer.publicKey,
+          senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+        } as any)
+        .signers([redeemer])
+        .rpc();
+      expect.fail("redeem should have failed");
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal("InvalidRentReceiver");
+    }
+  });
+});
@@ -0,0 +1,125 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { InstantSendProgram } from "../target/types/instant_send_program";
+import {
+  SEED_ESCROW_SOL,
+  balance,
+  deriveEscrowAddress,
+  deriveSenderRegistry,
+  escrowOptions,
+  fundedKeypair,
+  generateSecret,
+  hashSecret,
+} from "./utils";
+
+const IDL = require("../target/idl/instant_send_program");
+
+describe("Byte-array secrets", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = new Program<InstantSendProgram>(IDL, provider);
+
+  let sender: Keypair;
+  let recipient: Keypair;
+
+  beforeEach(async () => {
+    sender = await fundedKeypair(provider);
+    recipient = await fundedKeypair(provider, 1);
+  });
+
+  async function create(secret: string | Buffer, expiresIn = 3600): Promise<PublicKey> {
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SOL, sender.publicKey);
+    await program.methods
+      .initializeTransferSol(
+        new anchor.BN(1_000_000),
+        new anchor.BN(Math.floor(Date.now() / 1000) + expiresIn),
+        hashSecret(secret),
+        escrowOptions()
+      )
+      .accounts({ sender: sender.publicKey, escrowAccount: escrow } as any)
+      .signers([sender])
+      .rpc();
+    return escrow;
+  }
+
+  const redeemAccounts = (escrow: PublicKey) =>
+    ({
+      signer: recipient.publicKey,
+      recipient: recipient.publicKey,
+      escrowAccount: escrow,
+      sender: sender.publicKey,
+      rentReceiver: sender.publicKey,
+      senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+    }) as any;
+
+  const redeemV2 = (escrow: PublicKey, secret: Buffer) =>
+    program.methods.redeemFundsSolV2(secret, null).accounts(redeemAccounts(escrow)).signers([recipient]).rpc();
+
+  async function expectError(promise: Promise<unknown>, code: string) {
+    try {
+      await promise;
+      expect.fail(`expected ${code}`);
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal(code);
+    }
+  }
+
+  it("redeems with 32 random bytes", async () => {
+    const secret = generateSecret();
+    const escrow = await create(secret);
+
+    const before = await balance(provider, recipient.publicKey);
+    await redeemV2(escrow, secret);
+    expect(await balance(provider, recipient.publicKey)).to.equal(before + 1_000_000);
+    expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+  });
+
+  it("opens escrows created for a text secret with the same bytes", async () => {
+    const secret = "a text secret from an older client";
+    const escrow = await create(secret);
+
+    await redeemV2(escrow, Buffer.from(secret, "utf8"));
+    expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+  });
+
+  it("keeps the legacy String instruction working", async () => {
+    const escrow = await create("legacy");
+
+    await program.methods.redeemFundsSol("legacy").accounts(redeemAccounts(escrow)).signers([recipient]).rpc();
+    expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+  });
+
+  it("rejects secrets outside 16..=64 bytes", async () => {
+    const short = generateSecret().subarray(0, 15);
+    const long = Buffer.concat([generateSecret(), generateSecret(), Buffer.alloc(1)]);
+    const escrow = await create(short);
+
+    await expectError(redeemV2(escrow, short), "InvalidSecretLength");
+    await expectError(redeemV2(escrow, long), "InvalidSecretLength");
+  });
+
+  it("rejects the wrong bytes", async () => {
+    const escrow = await create(generateSecret());
+    await expectError(redeemV2(escrow, generateSecret()), "InvalidSecret");
+  });
+
+  it("refunds with the byte-array secret after expiry", async () => {
+    const secret = generateSecret();
+    const escrow = await create(secret, 2);
+    await new Promise((resolve) => setTimeout(resolve, 3000));
+
+    await program.methods
+      .refundFundsSolV2(secret)
+      .accounts({
+        signer: sender.publicKey,
+        escrowAccount: escrow,
+        sender: sender.publicKey,
+        senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+      } as any)
+      .signers([sender])
+      .rpc();
+    expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+  });
+});
@@ -0,0 +1,101 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { InstantSendProgram } from "../target/types/instant_send_program";
+import {
+  SEED_ESCROW_SOL,
+  deriveEscrowAddress,
+  deriveSenderRegistry,
+  escrowOptions,
+  fundedKeypair,
+  generateSecret,
+  hashSecret,
+} from "./utils";
+
+const IDL = require("../target/idl/instant_send_program");
+
+// One more escrow than fits a `list_sender_escrows` page, and then some.
+const PAGE_SIZE = 31;
+const ESCROWS = PAGE_SIZE + 9;
+
+describe("Sender registry", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = new Program<InstantSendProgram>(IDL, provider);
+
+  let sender: Keypair;
+  let recipient: Keypair;
+  let registry: PublicKey;
+
+  before(async () => {
+    sender = await fundedKeypair(provider, 5);
+    recipient = await fundedKeypair(provider, 1);
+    registry = deriveSenderRegistry(program.programId, sender.publicKey);
+  });
+
+  const list = async (): Promise<string[]> => {
+    const escrows: string[] = [];
+    for (let offset = 0; ; offset += PAGE_SIZE) {
+      const page: PublicKey[] = await program.methods
+        .listSenderEscrows(offset, PAGE_SIZE)
+        .accounts({ senderRegistry: registry } as any)
+        .view();
+      escrows.push(...page.map((escrow) => escrow.toBase58()));
+      if (page.length < PAGE_SIZE) return escrows;
+    }
+  };
+
+  it("lists and unlists more escrows than one page holds", async () => {
+    const created: { escrow: PublicKey; secret: Buffer }[] = [];
+    for (let i = 0; i < ESCROWS; i++) {
+      const secret = generateSecret();
+      const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SOL, sender.publicKey);
+      await program.methods
+        .initializeTransferSol(
+          new anchor.BN(100_000),
+          new anchor.BN(Math.floor(Date.now() / 1000) + 3600),
+          hashSecret(secret),
+          escrowOptions()
+        )
+        .accounts({ sender: sender.publicKey, escrowAccount: escrow } as any)
+        .signers([sender])
+        .rpc();
+      created.push({ escrow, secret });
+    }
+
+    expect((await list()).sort()).to.deep.equal(created.map(({ escrow }) => escrow.toBase58()).sort());
+
+    // Close them from the middle out, so removals swap entries across the page boundary.
+    const order = [...created.slice(ESCROWS / 2), ...created.slice(0, ESCROWS / 2)];
+    for (const [closed, { escrow, secret }] of order.entries()) {
+      await program.methods
+        .redeemFundsSolV2(secret, null)
+        .accounts({
+          signer: recipient.publicKey,
+          recipient: recipient.publicKey,
+          escrowAccount: escrow,
+          sender: sender.publicKey,
+          rentReceiver: sender.publicKey,
+          senderRegistry: registry,
+        } as any)
+        .signers([recipient])
+        .rpc();
+
+      if (closed % 10 === 0) {
+        const open = order.slice(closed + 1).map(({ escrow }) => escrow.toBase58());
+        expect((await list()).sort()).to.deep.equal(open.sort());
+      }
+    }
+
+    expect(await list()).to.be.empty;
+    expect((await program.account.senderRegistry.fetch(registry)).escrows).to.be.empty;
+
+    await program.methods
+      .closeSenderRegistry()
+      .accounts({ sender: sender.publicKey, senderRegistry: registry } as any)
+      .signers([sender])
+      .rpc();
+    expect(await provider.connection.getAccountInfo(registry, "confirmed")).to.be.null;
+  });
+});
@@ -0,0 +1,178 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { InstantSendProgram } from "../target/types/instant_send_program";
+import {
+  SEED_ESCROW_SOL,
+  balance,
+  deriveEscrowAddress,
+  deriveSenderRegistry,
+  escrowOptions,
+  fundedKeypair,
+  hashSecret,
+} from "./utils";
+
+const IDL = require("../target/idl/instant_send_program");
+
+const AMOUNT = 100_000_000;
+
+describe("Streaming escrows", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = new Program<InstantSendProgram>(IDL, provider);
+
+  let sender: Keypair;
+  let recipient: Keypair;
+  let other: Keypair;
+
+  before(async () => {
+    sender = await fundedKeypair(provider);
+    recipient = await fundedKeypair(provider, 1);
+    other = await fundedKeypair(provider, 1);
+  });
+
+  const now = () => Math.floor(Date.now() / 1000);
+
+  async function createStream(secret: string, vesting: object): Promise<PublicKey> {
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SOL, sender.publicKey);
+    await program.methods
+      .initializeTransferSol(
+        new anchor.BN(AMOUNT),
+        new anchor.BN(now() + 3600),
+        hashSecret(secret),
+        escrowOptions({ vesting })
+      )
+      .accounts({ sender: sender.publicKey } as any)
+      .signers([sender])
+      .rpc();
+    return escrow;
+  }
+
+  const withdraw = (escrow: PublicKey, secret: string | null, to: PublicKey) =>
+    program.methods
+      .withdrawVestedSol(secret, null)
+      .accounts({
+        signer: recipient.publicKey,
+        recipient: to,
+        escrowAccount: escrow,
+        rentReceiver: sender.publicKey,
+        senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+      } as any)
+      .signers([recipient])
+      .rpc();
+
+  async function expectError(promise: Promise<unknown>, code: string) {
+    try {
+      await promise;
+      expect.fail(`expected ${code}`);
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal(code);
+    }
+  }
+
+  it("pays out the vested part and binds the beneficiary", async () => {
+    const secret = "stream-half";
+    const start = now() - 500;
+    const escrow = await createStream(secret, {
+      startTime: new anchor.BN(start),
+      endTime: new anchor.BN(start + 1000),
+      cliffTime: null,
+    });
+
+    const before = await balance(provider, recipient.publicKey);
+    await withdraw(escrow, secret, recipient.publicKey);
+    const received = (await balance(provider, recipient.publicKey)) - before;
+    expect(received).to.be.within(AMOUNT * 0.45, AMOUNT * 0.6);
+
+    const account = await program.account.escrowSolAccount.fetch(escrow);
+    expect(account.withdrawn.toNumber()).to.equal(received);
+    expect(account.beneficiary.equals(recipient.publicKey)).to.be.true;
+
+    // Later withdrawals need no secret but only pay the beneficiary.
+    await expectError(withdraw(escrow, null, other.publicKey), "InvalidBeneficiary");
+  });
+
+  it("pays nothing before the cliff", async () => {
+    const secret = "stream-cliff";
+    const start = now() - 500;
+    const escrow = await createStream(secret, {
+      startTime: new anchor.BN(start),
+      endTime: new anchor.BN(start + 2000),
+      cliffTime: new anchor.BN(start + 1000),
+    });
+
+    await expectError(withdraw(escrow, secret, recipient.publicKey), "NothingToWithdraw");
+  });
+
+  it("closes the escrow once fully withdrawn", async () => {
+    const secret = "stream-done";
+    const start = now() - 2000;
+    const escrow = await createStream(secret, {
+      startTime: new anchor.BN(start),
+      endTime: new anchor.BN(start + 1000),
+      cliffTime: null,
+    });
+
+    const before = await balance(provider, recipient.publicKey);
+    await withdraw(escrow, secret, recipient.publicKey);
+    expect(await balance(provider, recipient.publicKey)).to.equal(before + AMOUNT);
+    expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+  });
+
+  it("lets the sender cancel and recover the unvested part", async () => {
+    const secret = "stream-cancel";
+    const start = now() - 500;
+    const escrow = await createStream(secret, {
+      startTime: new anchor.BN(start),
+      endTime: new anchor.BN(start + 1000),
+      cliffTime: null,
+    });
+
+    await program.methods
+      .cancelStreamSol()
+      .accounts({
+        sender: sender.publicKey,
+        escrowAccount: escrow,
+        senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+      } as any)
+      .signers([sender])
+      .rpc();
+
+    const account = await program.account.escrowSolAccount.fetch(escrow);
+    const vested = account.amount.toNumber();
+    expect(vested).to.be.within(AMOUNT * 0.45, AMOUNT * 0.6);
+
+    // The vested part is still claimable and closes the escrow.
+    const before = await balance(provider, recipient.publicKey);
+    await withdraw(escrow, secret, recipient.publicKey);
+    expect(await balance(provider, recipient.publicKey)).to.equal(before + vested);
+    expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+  });
+
+  it("keeps streaming escrows out of redeem_funds", async () => {
+    const secret = "stream-redeem";
+    const start = now() - 2000;
+    const escrow = await createStream(secret, {
+      startTime: new anchor.BN(start),
+      endTime: new anchor.BN(start + 1000),
+      cliffTime: null,
+    });
+
+    await expectError(
+      program.methods
+        .redeemFundsSol(secret)
+        .accounts({
+          signer: recipient.publicKey,
+          recipient: recipient.publicKey,
+          escrowAccount: escrow,
+          sender: sender.publicKey,
+          rentReceiver: sender.publicKey,
+          senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+        } as any)
+        .signers([recipient])
+        .rpc(),
+      "StreamingEscrow"
+    );
+  });
+});
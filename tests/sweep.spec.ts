@@ -0,0 +1,280 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  createMint,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+  transfer,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import { execFileSync } from "child_process";
+import * as fs from "fs";
+import * as os from "os";
+import * as path from "path";
+import { InstantSendProgram } from "../target/types/instant_send_program";
+import {
+  SEED_ESCROW_SOL,
+  SEED_ESCROW_SPL,
+  balance,
+  deriveEscrowAddress,
+  deriveSenderRegistry,
+  ensureConfig,
+  escrowOptions,
+  fundedKeypair,
+  generateSecret,
+  hashSecret,
+} from "./utils";
+
+const IDL = require("../target/idl/instant_send_program");
+const CRANK = path.join(__dirname, "../target/debug/instant-send-crank");
+const FEE = 5000;
+
+describe("Sweeping expired escrows", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = new Program<InstantSendProgram>(IDL, provider);
+  const admin = (provider.wallet as anchor.Wallet).payer;
+
+  let sender: Keypair;
+  let cranker: Keypair;
+
+  before(async () => {
+    await ensureConfig(program, provider);
+  });
+
+  beforeEach(async () => {
+    sender = await fundedKeypair(provider);
+    cranker = await fundedKeypair(provider, 1);
+  });
+
+  // Other specs rely on their expired escrows staying put.
+  after(async () => {
+    await setSweep({ enabled: false, delay: 0, bounty: 0 });
+  });
+
+  const setSweep = (settings: { enabled: boolean; delay: number; bounty: number }) =>
+    program.methods
+      .setSweepSettings({ ...settings, bounty: new anchor.BN(settings.bounty) } as any)
+      .accounts({ admin: admin.publicKey } as any)
+      .rpc();
+
+  const expiresIn = (seconds: number) => new anchor.BN(Math.floor(Date.now() / 1000) + seconds);
+  const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+
+  const deriveReceiptMint = (escrow: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from("receipt_mint"), escrow.toBuffer()], program.programId)[0];
+  const receiptAccountOf = (escrow: PublicKey, owner: PublicKey) =>
+    getAssociatedTokenAddressSync(deriveReceiptMint(escrow), owner, false, TOKEN_2022_PROGRAM_ID);
+
+  async function createSol(seconds: number, receipt = false): Promise<PublicKey> {
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SOL, sender.publicKey);
+    const receiptAccounts = receipt
+      ? {
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          receiptMint: deriveReceiptMint(escrow),
+          receiptAccount: receiptAccountOf(escrow, sender.publicKey),
+          receiptTokenProgram: TOKEN_2022_PROGRAM_ID,
+        }
+      : {};
+    await program.methods
+      .initializeTransferSol(
+        new anchor.BN(1_000_000),
+        expiresIn(seconds),
+        hashSecret(generateSecret()),
+        escrowOptions({ receipt })
+      )
+      .accounts({ sender: sender.publicKey, escrowAccount: escrow, ...receiptAccounts } as any)
+      .signers([sender])
+      .rpc();
+    return escrow;
+  }
+
+  // Moves the receipt of `escrow` from the sender to `holder`.
+  async function handOver(escrow: PublicKey, holder: Keypair): Promise<PublicKey> {
+    const to = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      holder,
+      deriveReceiptMint(escrow),
+      holder.publicKey,
+      false,
+      undefined,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await transfer(
+      provider.connection,
+      sender,
+      receiptAccountOf(escrow, sender.publicKey),
+      to.address,
+      sender,
+      1,
+      [],
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    return to.address;
+  }
+
+  const sweepSol = (escrow: PublicKey) =>
+    program.methods
+      .sweepExpiredSol()
+      .accounts({
+        cranker: cranker.publicKey,
+        escrowAccount: escrow,
+        sender: sender.publicKey,
+        senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+      } as any)
+      .signers([cranker])
+      .rpc();
+
+  async function expectError(promise: Promise<unknown>, code: string) {
+    try {
+      await promise;
+      expect.fail(`expected ${code}`);
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal(code);
+    }
+  }
+
+  it("refuses to sweep while sweeping is disabled", async () => {
+    await setSweep({ enabled: false, delay: 0, bounty: 0 });
+    const escrow = await createSol(1);
+    await sleep(2000);
+
+    await expectError(sweepSol(escrow), "SweepDisabled");
+  });
+
+  it("waits for the delay after expiry", async () => {
+    await setSweep({ enabled: true, delay: 3600, bounty: 0 });
+    const escrow = await createSol(1);
+    await sleep(2000);
+
+    await expectError(sweepSol(escrow), "NotSweepable");
+  });
+
+  it("returns SOL and rent to the sender, minus the bounty", async () => {
+    await setSweep({ enabled: true, delay: 0, bounty: 10_000 });
+    const escrow = await createSol(1);
+    const rent = (await balance(provider, escrow)) - 1_000_000;
+    await sleep(2000);
+
+    const senderBefore = await balance(provider, sender.publicKey);
+    const crankerBefore = await balance(provider, cranker.publicKey);
+    await sweepSol(escrow);
+
+    expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+    expect(await balance(provider, sender.publicKey)).to.equal(senderBefore + 1_000_000 + rent - 10_000);
+    expect(await balance(provider, cranker.publicKey)).to.equal(crankerBefore - FEE + 10_000);
+  });
+
+  it("returns the tokens and caps the bounty at the escrow's rent", async () => {
+    await setSweep({ enabled: true, delay: 0, bounty: 1_000_000_000 });
+    const mint = await createMint(provider.connection, sender, sender.publicKey, null, 0);
+    const senderTokenAccount = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, sender, mint, sender.publicKey)
+    ).address;
+    await mintTo(provider.connection, sender, mint, senderTokenAccount, sender, 100);
+
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SPL, sender.publicKey);
+    const vault = getAssociatedTokenAddressSync(mint, escrow, true);
+    await program.methods
+      .initializeTransferSpl(new anchor.BN(100), expiresIn(1), hashSecret(generateSecret()), escrowOptions())
+      .accounts({
+        sender: sender.publicKey,
+        escrowAccount: escrow,
+        escrowTokenAccount: vault,
+        senderTokenAccount,
+        tokenMint: mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      } as any)
+      .signers([sender])
+      .rpc();
+    const rent = await balance(provider, escrow);
+    await sleep(2000);
+
+    const crankerBefore = await balance(provider, cranker.publicKey);
+    await program.methods
+      .sweepExpiredSpl()
+      .accounts({
+        cranker: cranker.publicKey,
+        escrowAccount: escrow,
+        escrowTokenAccount: vault,
+        sender: sender.publicKey,
+        senderTokenAccount,
+        tokenMint: mint,
+        senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+        tokenProgram: TOKEN_PROGRAM_ID,
+      } as any)
+      .signers([cranker])
+      .rpc();
+
+    expect(Number((await getAccount(provider.connection, senderTokenAccount, "confirmed")).amount)).to.equal(100);
+    expect(await provider.connection.getAccountInfo(vault)).to.be.null;
+    expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+    expect(await balance(provider, cranker.publicKey)).to.equal(crankerBefore - FEE + rent);
+  });
+
+  it("sweeps escrows with a receipt to the receipt holder and burns the receipt", async () => {
+    await setSweep({ enabled: true, delay: 0, bounty: 10_000 });
+    const holder = await fundedKeypair(provider, 1);
+    const escrow = await createSol(1, true);
+    const receiptAccount = await handOver(escrow, holder);
+    await sleep(2000);
+
+    const sweepWithReceipt = (owner: PublicKey, account: PublicKey) =>
+      program.methods
+        .sweepExpiredWithReceiptSol()
+        .accounts({
+          cranker: cranker.publicKey,
+          escrowAccount: escrow,
+          holder: owner,
+          receiptMint: deriveReceiptMint(escrow),
+          receiptAccount: account,
+          senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+          receiptTokenProgram: TOKEN_2022_PROGRAM_ID,
+        } as any)
+        .signers([cranker])
+        .rpc();
+
+    await expectError(sweepSol(escrow), "SweepRequiresReceipt");
+    // The sender gave the receipt away, and with it the refund.
+    await expectError(
+      sweepWithReceipt(sender.publicKey, receiptAccountOf(escrow, sender.publicKey)),
+      "ReceiptNotHeld"
+    );
+
+    const holderBefore = await balance(provider, holder.publicKey);
+    const crankerBefore = await balance(provider, cranker.publicKey);
+    await sweepWithReceipt(holder.publicKey, receiptAccount);
+
+    expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+    expect(await provider.connection.getAccountInfo(deriveReceiptMint(escrow))).to.be.null;
+    expect(await balance(provider, holder.publicKey)).to.be.greaterThan(holderBefore + 1_000_000);
+    expect(await balance(provider, cranker.publicKey)).to.equal(crankerBefore - FEE + 10_000);
+  });
+
+  it("is cranked by the instant-send-crank binary", async () => {
+    execFileSync("cargo", ["build", "-p", "instant-send-crank"], { stdio: "inherit" });
+    await setSweep({ enabled: true, delay: 0, bounty: 10_000 });
+    const holder = await fundedKeypair(provider, 1);
+    const escrow = await createSol(1);
+    const withReceipt = await createSol(1, true);
+    await handOver(withReceipt, holder);
+    await sleep(2000);
+
+    const keypair = path.join(fs.mkdtempSync(path.join(os.tmpdir(), "crank-")), "cranker.json");
+    fs.writeFileSync(keypair, JSON.stringify(Array.from(cranker.secretKey)));
+    execFileSync(CRANK, ["--once", "--url", provider.connection.rpcEndpoint, "--keypair", keypair], {
+      stdio: "inherit",
+    });
+
+    expect(await provider.connection.getAccountInfo(escrow, "confirmed")).to.be.null;
+    expect(await provider.connection.getAccountInfo(withReceipt, "confirmed")).to.be.null;
+  });
+});
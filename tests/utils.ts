@@ -0,0 +1,128 @@
+//file: tests/utils.ts
+// Helpers shared by the localnet specs.
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import * as crypto from "crypto";
+import { InstantSendProgram } from "../target/types/instant_send_program";
+
+const BPF_LOADER_UPGRADEABLE = new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111");
+
+export const SEED_ESCROW_SOL = Buffer.from("escrow_sol");
+export const SEED_ESCROW_SPL = Buffer.from("escrow_spl");
+
+// Strings are hashed as UTF-8, the way the legacy `String` instructions read them.
+export const hashSecret = (secret: string | Buffer): Buffer => {
+  return crypto.createHash("sha256").update(secret).digest();
+};
+
+// 32 random bytes, the secret format used with the `_v2` instructions.
+export const generateSecret = (): Buffer => crypto.randomBytes(32);
+
+export async function airdrop(
+  provider: anchor.AnchorProvider,
+  to: PublicKey,
+  sol: number
+) {
+  const signature = await provider.connection.requestAirdrop(to, sol * LAMPORTS_PER_SOL);
+  const latest = await provider.connection.getLatestBlockhash();
+  await provider.connection.confirmTransaction({ signature, ...latest }, "confirmed");
+}
+
+export async function fundedKeypair(provider: anchor.AnchorProvider, sol = 2): Promise<Keypair> {
+  const keypair = Keypair.generate();
+  await airdrop(provider, keypair.publicKey, sol);
+  return keypair;
+}
+
+export function deriveSenderNonce(programId: PublicKey, sender: PublicKey): PublicKey {
+  const [senderNoncePDA] = PublicKey.findProgramAddressSync(
+    [Buffer.from("sender_nonce"), sender.toBuffer()],
+    programId
+  );
+  return senderNoncePDA;
+}
+
+export function deriveSenderRegistry(programId: PublicKey, sender: PublicKey): PublicKey {
+  const [senderRegistryPDA] = PublicKey.findProgramAddressSync(
+    [Buffer.from("sender_registry"), sender.toBuffer()],
+    programId
+  );
+  return senderRegistryPDA;
+}
+
+// Escrows are seeded by [seed, sender, nonce]; the nonce is the sender's current counter.
+export async function deriveEscrowAddress(
+  program: Program<InstantSendProgram>,
+  seed: Buffer,
+  sender: PublicKey
+): Promise<PublicKey> {
+  const senderNonce = await program.account.senderNonce.fetchNullable(
+    deriveSenderNonce(program.programId, sender)
+  );
+  const nonce = senderNonce ? senderNonce.nextNonce : new anchor.BN(0);
+  const [escrowPDA] = PublicKey.findProgramAddressSync(
+    [seed, sender.toBuffer(), nonce.toArrayLike(Buffer, "le", 8)],
+    program.programId
+  );
+  return escrowPDA;
+}
+
+export async function balance(provider: anchor.AnchorProvider, key: PublicKey): Promise<number> {
+  return provider.connection.getBalance(key, "confirmed");
+}
+
+// Default `EscrowOptions`; Anchor needs every field present, so specs override only what they test.
+export function escrowOptions(overrides: object = {}): any {
+  return {
+    rentPolicy: { sender: {} },
+    pinHash: null,
+    condition: null,
+    approval: null,
+    vesting: null,
+    allowance: null,
+    memo: null,
+    receipt: false,
+    ...overrides,
+  };
+}
+
+// Salt delivered with a PIN; never stored on chain.
+export const generatePinSalt = (): Buffer => crypto.randomBytes(16);
+
+// PIN commitment stored on the escrow: sha256(secret || salt || pin), with string secrets as UTF-8.
+export const hashPin = (secret: string | Buffer, salt: Buffer, pin: string): number[] => {
+  return Array.from(crypto.createHash("sha256").update(secret).update(salt).update(pin, "utf8").digest());
+};
+
+// Config commitment stored in a claim condition: sha256(program_id || config_address).
+export const hashConditionConfig = (programId: PublicKey, config: PublicKey): number[] => {
+  return Array.from(
+    crypto.createHash("sha256").update(programId.toBuffer()).update(config.toBuffer()).digest()
+  );
+};
+
+export function deriveConfig(programId: PublicKey): PublicKey {
+  const [configPDA] = PublicKey.findProgramAddressSync([Buffer.from("config")], programId);
+  return configPDA;
+}
+
+// Creates the config with the provider wallet as admin. The wallet deploys the program on
+// localnet, so it is also the upgrade authority that `initialize_config` requires.
+export async function ensureConfig(
+  program: Program<InstantSendProgram>,
+  provider: anchor.AnchorProvider
+): Promise<PublicKey> {
+  const configPDA = deriveConfig(program.programId);
+  if (!(await provider.connection.getAccountInfo(configPDA))) {
+    const [programData] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      BPF_LOADER_UPGRADEABLE
+    );
+    await program.methods
+      .initializeConfig(provider.wallet.publicKey)
+      .accounts({ authority: provider.wallet.publicKey, programData } as any)
+      .rpc();
+  }
+  return configPDA;
+}
@@ -0,0 +1,138 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  createMint,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+  transfer,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import { InstantSendProgram } from "../target/types/instant_send_program";
+import {
+  SEED_ESCROW_SPL,
+  deriveEscrowAddress,
+  deriveSenderRegistry,
+  escrowOptions,
+  fundedKeypair,
+  generateSecret,
+  hashSecret,
+} from "./utils";
+
+const IDL = require("../target/idl/instant_send_program");
+
+// Anyone can send tokens to an escrow's vault. Redeem and refund must still close it, so they
+// pay out the vault's whole balance instead of the escrowed amount.
+describe("Vault donations", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = new Program<InstantSendProgram>(IDL, provider);
+
+  let sender: Keypair;
+  let recipient: Keypair;
+  let attacker: Keypair;
+  let mint: PublicKey;
+  let senderTokenAccount: PublicKey;
+  let attackerTokenAccount: PublicKey;
+
+  const tokenBalance = async (account: PublicKey) =>
+    Number((await getAccount(provider.connection, account, "confirmed")).amount);
+
+  beforeEach(async () => {
+    sender = await fundedKeypair(provider);
+    recipient = await fundedKeypair(provider, 1);
+    attacker = await fundedKeypair(provider, 1);
+
+    mint = await createMint(provider.connection, sender, sender.publicKey, null, 0);
+    senderTokenAccount = (await getOrCreateAssociatedTokenAccount(provider.connection, sender, mint, sender.publicKey))
+      .address;
+    attackerTokenAccount = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, sender, mint, attacker.publicKey)
+    ).address;
+    await mintTo(provider.connection, sender, mint, senderTokenAccount, sender, 1_000);
+    await mintTo(provider.connection, sender, mint, attackerTokenAccount, sender, 1);
+  });
+
+  async function create(secret: Buffer, expiresIn = 3600): Promise<[PublicKey, PublicKey]> {
+    const escrow = await deriveEscrowAddress(program, SEED_ESCROW_SPL, sender.publicKey);
+    const vault = getAssociatedTokenAddressSync(mint, escrow, true);
+    await program.methods
+      .initializeTransferSpl(
+        new anchor.BN(100),
+        new anchor.BN(Math.floor(Date.now() / 1000) + expiresIn),
+        hashSecret(secret),
+        escrowOptions()
+      )
+      .accounts({
+        sender: sender.publicKey,
+        escrowAccount: escrow,
+        escrowTokenAccount: vault,
+        senderTokenAccount,
+        tokenMint: mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      } as any)
+      .signers([sender])
+      .rpc();
+    return [escrow, vault];
+  }
+
+  const donate = (vault: PublicKey) =>
+    transfer(provider.connection, attacker, attackerTokenAccount, vault, attacker, 1);
+
+  it("redeems a vault holding more than the escrowed amount", async () => {
+    const secret = generateSecret();
+    const [escrow, vault] = await create(secret);
+    await donate(vault);
+
+    const recipientTokenAccount = getAssociatedTokenAddressSync(mint, recipient.publicKey);
+    await program.methods
+      .redeemFundsSplV2(secret, null)
+      .accounts({
+        signer: recipient.publicKey,
+        recipient: recipient.publicKey,
+        escrowAccount: escrow,
+        escrowTokenAccount: vault,
+        recipientTokenAccount,
+        tokenMint: mint,
+        sender: sender.publicKey,
+        rentReceiver: sender.publicKey,
+        senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+        tokenProgram: TOKEN_PROGRAM_ID,
+      } as any)
+      .signers([recipient])
+      .rpc();
+
+    expect(await tokenBalance(recipientTokenAccount)).to.equal(101);
+    expect(await provider.connection.getAccountInfo(vault)).to.be.null;
+    expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+  });
+
+  it("refunds a vault holding more than the escrowed amount", async () => {
+    const secret = generateSecret();
+    const [escrow, vault] = await create(secret, 2);
+    await donate(vault);
+    await new Promise((resolve) => setTimeout(resolve, 3000));
+
+    await program.methods
+      .refundFundsSplV2(secret)
+      .accounts({
+        signer: sender.publicKey,
+        escrowAccount: escrow,
+        escrowTokenAccount: vault,
+        sender: sender.publicKey,
+        senderTokenAccount,
+        tokenMint: mint,
+        senderRegistry: deriveSenderRegistry(program.programId, sender.publicKey),
+        tokenProgram: TOKEN_PROGRAM_ID,
+      } as any)
+      .signers([sender])
+      .rpc();
+
+    expect(await tokenBalance(senderTokenAccount)).to.equal(1_001);
+    expect(await provider.connection.getAccountInfo(vault)).to.be.null;
+    expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+  });
+});